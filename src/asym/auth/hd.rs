@@ -0,0 +1,275 @@
+// Hierarchical deterministic keys following SLIP-0010: https://github.com/satoshilabs/slips/blob/master/slip-0010.md
+
+use boolinator::Boolinator;
+use crypto_abstract::{ToAlgorithm, ToPublicKey};
+use ring::digest::SHA512;
+use ring::hmac;
+use serde::de;
+use serde::de::{Deserialize, Deserializer, MapAccess, Visitor};
+use serde::ser::{Serialize, Serializer, SerializeStruct};
+use std::fmt;
+use std::str::FromStr;
+
+use asym::auth::{Algorithm, PrivateKey, PublicKey, gen_from_seed};
+use internal::asym::auth::private_key_seed;
+use internal::{AlgorithmId, PKAIdentifier, PKAJ, ToIdentifier, ToVersionedIdentifier, deserialize_base64url, deserialize_psf, is_strict, serialize_base64url, serialize_psf, u8_to_fixed_length_32};
+
+/// Offset of hardened child indices.
+pub const HARDENED : u32 = 0x80000000;
+
+const CHAINCODELENGTH : usize = 32;
+const SEEDLENGTH : usize = 32;
+
+/// Derivation path of child indices, ex `m/44'/0'/3'`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DerivationPath {
+    indices : Vec<u32>
+}
+
+impl DerivationPath {
+    pub fn new( indices : Vec<u32>) -> DerivationPath {
+        DerivationPath{ indices : indices}
+    }
+
+    pub fn indices( &self) -> &[u32] {
+        &self.indices
+    }
+}
+
+impl FromStr for DerivationPath {
+    type Err = &'static str;
+
+    fn from_str( s : &str) -> Result<DerivationPath, &'static str> {
+        let mut components = s.split( '/');
+        (components.next() == Some( "m")).ok_or( "Derivation path must start with m.")?;
+
+        let indices = components.map( parse_index).collect::<Result<Vec<u32>, &'static str>>()?;
+
+        Ok( DerivationPath{ indices : indices})
+    }
+}
+
+fn parse_index( c : &str) -> Result<u32, &'static str> {
+    let (c, hardened) = if c.ends_with( "'") || c.ends_with( "H") || c.ends_with( "h") {
+        (&c[0 .. c.len() - 1], true)
+    }
+    else {
+        (c, false)
+    };
+
+    // Only accept plain decimal digits.
+    (!c.is_empty() && c.bytes().all(|b| b.is_ascii_digit())).ok_or( "Invalid derivation path index.")?;
+    let i : u32 = c.parse().map_err(|_| "Invalid derivation path index.")?;
+    (i < HARDENED).ok_or( "Derivation path index is too large.")?;
+
+    Ok( if hardened { i + HARDENED } else { i })
+}
+
+impl fmt::Display for DerivationPath {
+    fn fmt( &self, f : &mut fmt::Formatter) -> fmt::Result {
+        write!( f, "m")?;
+        for i in &self.indices {
+            if *i >= HARDENED {
+                write!( f, "/{}'", i - HARDENED)?;
+            }
+            else {
+                write!( f, "/{}", i)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Private key extended with a chain code so that child keys can be derived from it.
+pub struct ExtendedPrivateKey {
+    key : PrivateKey,
+    chain_code : [u8; CHAINCODELENGTH],
+    depth : u8,
+    index : u32,
+    parent : Option<PKAIdentifier>,
+}
+
+impl ExtendedPrivateKey {
+    /// Generate the master key from a seed of 16 to 64 bytes.
+    pub fn from_seed( alg : &Algorithm, seed : &[u8]) -> Result<ExtendedPrivateKey, &'static str> {
+        (seed.len() >= 16 && seed.len() <= 64).ok_or( "Seed is wrong length.")?;
+
+        let curve : &[u8] = match *alg {
+            Algorithm::AAEd25519 => b"ed25519 seed",
+        };
+
+        let i = hmac::sign( &hmac::SigningKey::new( &SHA512, curve), seed);
+        from_hmac( alg, i.as_ref(), 0, 0, None)
+    }
+
+    /// Derive the hardened child key at the given index.
+    pub fn derive_child( &self, index : u32) -> Result<ExtendedPrivateKey, &'static str> {
+        (index >= HARDENED).ok_or( "Ed25519 only supports hardened derivation.")?;
+        (self.depth < u8::max_value()).ok_or( "Maximum derivation depth reached.")?;
+
+        // Data = 0x00 || k || ser32(i)
+        let mut data = Vec::with_capacity( 1 + SEEDLENGTH + 4);
+        data.push( 0);
+        data.extend( private_key_seed( &self.key));
+        data.extend( &[ (index >> 24) as u8, (index >> 16) as u8, (index >> 8) as u8, index as u8]);
+
+        let i = hmac::sign( &hmac::SigningKey::new( &SHA512, &self.chain_code), &data);
+        let parent = ToIdentifier::to_identifier( &self.key);
+        from_hmac( &ToAlgorithm::to_algorithm( &self.key), i.as_ref(), self.depth + 1, index, Some( parent))
+    }
+
+    /// Derive the key at the end of a path, relative to this key.
+    pub fn derive_path( &self, path : &DerivationPath) -> Result<ExtendedPrivateKey, &'static str> {
+        let (first, rest) = path.indices().split_first().ok_or( "Derivation path is empty.")?;
+
+        let mut key = self.derive_child( *first)?;
+        for i in rest {
+            key = key.derive_child( *i)?;
+        }
+        Ok( key)
+    }
+
+    pub fn private_key( &self) -> &PrivateKey {
+        &self.key
+    }
+
+    pub fn public_key( &self) -> PublicKey {
+        ToPublicKey::to_public_key( &self.key)
+    }
+
+    pub fn chain_code( &self) -> &[u8; CHAINCODELENGTH] {
+        &self.chain_code
+    }
+
+    pub fn depth( &self) -> u8 {
+        self.depth
+    }
+
+    pub fn index( &self) -> u32 {
+        self.index
+    }
+
+    /// Identifier of the key this key was derived from.
+    pub fn parent_identifier( &self) -> Option<&PKAIdentifier> {
+        self.parent.as_ref()
+    }
+}
+
+fn from_hmac( alg : &Algorithm, i : &[u8], depth : u8, index : u32, parent : Option<PKAIdentifier>) -> Result<ExtendedPrivateKey, &'static str> {
+    let (il, ir) = i.split_at( SEEDLENGTH);
+    let key = gen_from_seed( alg, il)?;
+    let chain_code = u8_to_fixed_length_32( ir).ok_or( "Invalid chain code.")?;
+
+    Ok( ExtendedPrivateKey{ key : key, chain_code : chain_code, depth : depth, index : index, parent : parent})
+}
+
+impl ToAlgorithm for ExtendedPrivateKey {
+    type Algorithm = Algorithm;
+
+    fn to_algorithm( &self) -> Self::Algorithm {
+        ToAlgorithm::to_algorithm( &self.key)
+    }
+}
+
+impl ToIdentifier for ExtendedPrivateKey {
+    fn to_identifier( key : &ExtendedPrivateKey) -> PKAIdentifier {
+        ToIdentifier::to_identifier( &key.key)
+    }
+}
+
+impl ToVersionedIdentifier for ExtendedPrivateKey {
+    fn to_versioned_identifier( key : &ExtendedPrivateKey) -> PKAIdentifier {
+        ToVersionedIdentifier::to_versioned_identifier( &key.key)
+    }
+}
+
+impl<'a> Serialize for PKAJ<&'a ExtendedPrivateKey> {
+    fn serialize<S>( &self, serializer : S) -> Result<S::Ok, S::Error> where S : Serializer {
+        let mut o = serializer.serialize_struct( "ExtendedPrivateKey", 6)?;
+
+        o.serialize_field( "private_key", &serialize_psf( &self.pkaj.key))?;
+        o.serialize_field( "chain_code", &serialize_base64url( &self.pkaj.chain_code.to_vec()))?;
+        o.serialize_field( "depth", &self.pkaj.depth)?;
+        o.serialize_field( "index", &self.pkaj.index)?;
+        if let Some( ref parent) = self.pkaj.parent {
+            o.serialize_field( "parent", parent)?;
+        }
+        o.serialize_field( "algorithm", AlgorithmId::to_algorithm_id( &ToAlgorithm::to_algorithm( self.pkaj)))?;
+
+        o.end()
+    }
+}
+
+impl<'d> Deserialize<'d> for PKAJ<ExtendedPrivateKey> {
+    fn deserialize<D>( deserializer : D) -> Result<PKAJ<ExtendedPrivateKey>, D::Error> where D : Deserializer<'d> {
+        struct V;
+
+        const FIELDS : &'static [&'static str] = &["private_key", "chain_code", "depth", "index", "parent", "algorithm"];
+
+        impl<'d> Visitor<'d> for V {
+            type Value = PKAJ<ExtendedPrivateKey>;
+
+            fn expecting( &self, formatter : &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str( "JSON ExtendedPrivateKey")
+            }
+
+            fn visit_map<U>( self, mut map : U) -> Result<PKAJ<ExtendedPrivateKey>, U::Error> where U : MapAccess<'d> {
+                let mut private_key = None;
+                let mut chain_code = None;
+                let mut depth = None;
+                let mut index = None;
+                let mut parent = None;
+                let mut algorithm = None;
+
+                while let Some(k) = map.next_key::<String>()? {
+                    match k.as_str() {
+                        "private_key" => {
+                            private_key.is_none().ok_or( de::Error::duplicate_field( "private_key"))?;
+                            private_key = Some( map.next_value()?);
+                        }
+                        "chain_code" => {
+                            chain_code.is_none().ok_or( de::Error::duplicate_field( "chain_code"))?;
+                            chain_code = Some( map.next_value()?);
+                        }
+                        "depth" => {
+                            depth.is_none().ok_or( de::Error::duplicate_field( "depth"))?;
+                            depth = Some( map.next_value()?);
+                        }
+                        "index" => {
+                            index.is_none().ok_or( de::Error::duplicate_field( "index"))?;
+                            index = Some( map.next_value()?);
+                        }
+                        "parent" => {
+                            parent.is_none().ok_or( de::Error::duplicate_field( "parent"))?;
+                            parent = Some( map.next_value()?);
+                        }
+                        "algorithm" => {
+                            algorithm.is_none().ok_or( de::Error::duplicate_field( "algorithm"))?;
+                            algorithm = Some( map.next_value()?);
+                        }
+                        k => {
+                            // Skip unknown fields, unless deserializing strictly.
+                            (!is_strict()).ok_or( de::Error::unknown_field( k, FIELDS))?;
+                            map.next_value::<de::IgnoredAny>()?;
+                        }
+                    }
+                }
+
+                let private_key : String = private_key.ok_or_else(|| de::Error::missing_field( "private_key"))?;
+                let chain_code : String = chain_code.ok_or_else(|| de::Error::missing_field( "chain_code"))?;
+                let depth : u8 = depth.ok_or_else(|| de::Error::missing_field( "depth"))?;
+                let index : u32 = index.ok_or_else(|| de::Error::missing_field( "index"))?;
+                let algorithm : String = algorithm.ok_or_else(|| de::Error::missing_field( "algorithm"))?;
+
+                let algorithm = AlgorithmId::from_algorithm_id( &algorithm).ok_or( de::Error::custom( "invalid algorithm identifier"))?;
+                let key = deserialize_psf( &algorithm, &private_key).map_err( de::Error::custom)?;
+                let chain_code = deserialize_base64url( &chain_code).map_err( de::Error::custom)?;
+                let chain_code = u8_to_fixed_length_32( &chain_code).ok_or( de::Error::custom( "Chain code is wrong length."))?;
+
+                Ok( PKAJ{ pkaj : ExtendedPrivateKey{ key : key, chain_code : chain_code, depth : depth, index : index, parent : parent}})
+            }
+        }
+
+        deserializer.deserialize_struct( "ExtendedPrivateKey", FIELDS, V)
+    }
+}
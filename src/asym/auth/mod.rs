@@ -1,4 +1,7 @@
 
+/// Hierarchical deterministic keys (SLIP-0010).
+pub mod hd;
+
 use boolinator::Boolinator;
 use crypto_abstract::ToAlgorithm;
 use crypto_abstract::asym::auth;
@@ -7,17 +10,55 @@ use serde::de;
 use serde::de::{MapAccess, Visitor, Deserializer, Deserialize, DeserializeOwned};
 use serde::ser::{Serialize, Serializer, SerializeStruct};
 use serde_json;
+use serde_json::Value;
 use std::fmt;
-
-use internal::{AlgorithmId, serialize_base64url, serialize_psf, deserialize_base64url, deserialize_psf};
+use std::str::FromStr;
+
+use derive::MasterSecret;
+use encoding::{DecodeBinary, DecodeCbor, EncodeBinary, EncodeCbor, Format};
+use encoding::binary;
+use encoding::binary::ObjectType;
+use encoding::cbor;
+use encoding::cbor::Value as CborValue;
+use encoding::jcs;
+use encoding::token;
+use ring::rand::SystemRandom;
+use shamir;
+use shamir::Share;
+use internal;
+use internal::mnemonic;
+use metadata::{Clock, PKAKey, Usage};
+use policy::{Operation, Policy};
+use revocation::RevocationSet;
+use internal::{AlgorithmId, AlgorithmTag, ToIdentifier, serialize_base64url, serialize_psf, deserialize_base64url, deserialize_psf, is_strict};
 // use internal::{ToIdentifier, PKAIdentifier,PSF, EncodePSF, DecodePSF};
 
+/// Property of an inline signed JSON object that holds its signature.
+pub const INLINESIGNATURE : &'static str = "signature";
+
 pub struct PKASigned { //<T> {
     content : Vec<u8>, // JP: Base64 newtype wrapper??
     signature : auth::Signature,
     // identifier : PKAIdentifier
 }
 
+impl PKASigned {
+    /// Signed content, before its signature is verified. Only use this to find the key to verify it with.
+    pub fn unverified_content( &self) -> &[u8] {
+        &self.content
+    }
+}
+
+impl Clone for PKASigned {
+    fn clone( &self) -> PKASigned {
+        let signature = match self.signature {
+            auth::Signature::AAEd25519( s) => auth::Signature::AAEd25519( s),
+        };
+
+        PKASigned{ content : self.content.clone(), signature : signature}
+    }
+}
+
 impl ToAlgorithm for PKASigned {
     type Algorithm = Algorithm;
 
@@ -77,10 +118,10 @@ impl<'d> Deserialize<'d> for PKASigned {
                             algorithm.is_none().ok_or( de::Error::duplicate_field("algorithm"))?;
                             algorithm = Some( map.next_value()?);
                         }
-                        _k => {
-                            // Skip unknown fields.
-                            let _ : Result<(),U::Error> = map.next_value();
-                            // Err(de::Error::unknown_field(k, FIELDS))?;
+                        k => {
+                            // Skip unknown fields, unless deserializing strictly.
+                            (!is_strict()).ok_or( de::Error::unknown_field( k, FIELDS))?;
+                            map.next_value::<de::IgnoredAny>()?;
                         }
                     }
                 }
@@ -103,6 +144,103 @@ impl<'d> Deserialize<'d> for PKASigned {
     }
 }
 
+impl EncodeCbor for PKASigned {
+    fn encode_cbor( signed : &PKASigned) -> Vec<u8> {
+        cbor::encode( &CborValue::Map( vec![
+            (CborValue::Text( "content".to_string()), CborValue::Bytes( signed.content.clone())),
+            (CborValue::Text( "signature".to_string()), CborValue::Bytes( internal::EncodePSF::encode_psf( &signed.signature))),
+            (CborValue::Text( "algorithm".to_string()), CborValue::Text( AlgorithmId::to_algorithm_id( &ToAlgorithm::to_algorithm( signed)).to_string())),
+        ]))
+    }
+}
+
+impl DecodeCbor for PKASigned {
+    fn decode_cbor( bs : &[u8]) -> Result<PKASigned, &'static str> {
+        let v = cbor::decode( bs)?;
+        let algorithm = v.field( "algorithm")?.as_text().ok_or( "Invalid algorithm.")?;
+        let algorithm = AlgorithmId::from_algorithm_id( algorithm).ok_or( "Invalid algorithm identifier.")?;
+        let signature = v.field( "signature")?.as_bytes().ok_or( "Invalid signature.")?;
+        let signature = internal::DecodePSF::decode_psf( &algorithm, &signature.to_vec())?;
+        let content = v.field( "content")?.as_bytes().ok_or( "Invalid content.")?.to_vec();
+
+        Ok( PKASigned{ content : content, signature : signature})
+    }
+}
+
+impl EncodeBinary for PKASigned {
+    fn encode_binary( signed : &PKASigned) -> Vec<u8> {
+        let alg = AlgorithmTag::to_algorithm_tag( &ToAlgorithm::to_algorithm( signed));
+        binary::encode( ObjectType::Signed, alg, &[ &signed.content, &internal::EncodePSF::encode_psf( &signed.signature)])
+    }
+}
+
+impl DecodeBinary for PKASigned {
+    fn decode_binary( bs : &[u8]) -> Result<PKASigned, &'static str> {
+        let (tag, mut fields) = binary::decode( ObjectType::Signed, 2, bs)?;
+        let algorithm = AlgorithmTag::from_algorithm_tag( tag).ok_or( "Unknown algorithm tag.")?;
+        let signature = internal::DecodePSF::decode_psf( &algorithm, &fields[1])?;
+
+        Ok( PKASigned{ content : fields.swap_remove( 0), signature : signature})
+    }
+}
+
+/// Token form, `pka1.<alg>.<content>.<signature>`.
+impl fmt::Display for PKASigned {
+    fn fmt( &self, f : &mut fmt::Formatter) -> fmt::Result {
+        let alg = AlgorithmId::to_algorithm_id( &ToAlgorithm::to_algorithm( self));
+        f.write_str( &token::encode( alg, &[ &self.content, &internal::EncodePSF::encode_psf( &self.signature)]))
+    }
+}
+
+impl FromStr for PKASigned {
+    type Err = &'static str;
+
+    fn from_str( s : &str) -> Result<PKASigned, &'static str> {
+        let (alg, mut parts) = token::decode( s, 2)?;
+        let algorithm = AlgorithmId::from_algorithm_id( alg).ok_or( "Invalid algorithm identifier.")?;
+        let signature = internal::DecodePSF::decode_psf( &algorithm, &parts[1])?;
+
+        Ok( PKASigned{ content : parts.swap_remove( 0), signature : signature})
+    }
+}
+
+/// Deterministically generate a private key from a 32 byte seed.
+pub fn gen_from_seed( alg : &Algorithm, seed : &[u8]) -> Result<PrivateKey, &'static str> {
+    internal::asym::auth::private_key_from_seed( alg, seed)
+}
+
+/// Deterministically derive a private key from a master secret.
+pub fn gen_from_master( master : &MasterSecret, alg : &Algorithm) -> Result<PrivateKey, &'static str> {
+    gen_from_seed( alg, &master.seed( AlgorithmId::to_algorithm_id( alg)))
+}
+
+/// Back up a private key's seed as a mnemonic phrase.
+pub fn to_mnemonic( key : &PrivateKey) -> Result<String, &'static str> {
+    let seed = internal::asym::auth::private_key_seed( key);
+    mnemonic::encode_mnemonic( &ToAlgorithm::to_algorithm( key), &seed)
+}
+
+/// Restore a private key from a mnemonic phrase.
+pub fn from_mnemonic( phrase : &str) -> Result<PrivateKey, &'static str> {
+    let (alg, seed) = mnemonic::decode_mnemonic( phrase)?;
+    gen_from_seed( &alg, &seed)
+}
+
+/// Split a private key into `count` shares, any `threshold` of which recombine to it.
+pub fn split_shares( rng : &SystemRandom, key : &PrivateKey, threshold : u8, count : u8) -> Result<Vec<Share>, &'static str> {
+    let seed = internal::asym::auth::private_key_seed( key);
+    shamir::split( rng, &ToAlgorithm::to_algorithm( key), ToIdentifier::to_identifier( key), &seed, threshold, count)
+}
+
+/// Recombine shares to a private key, verifying its identifier.
+pub fn combine_shares( shares : &[Share]) -> Result<PrivateKey, &'static str> {
+    let (alg, identifier, seed) = shamir::combine( shares)?;
+    let key = gen_from_seed( &alg, &seed)?;
+    identifier.identifies( &key).ok_or( "Reconstructed key does not match its identifier.")?;
+
+    Ok( key)
+}
+
 // sign:
 // a -> bytestring -> PKASigned
 // signContent: 
@@ -122,6 +260,12 @@ pub fn verify<T>( key : &PublicKey, signed : PKASigned ) -> Result <T,&'static s
     serde_json::from_slice( &bs).map_err(|_| "Invalid json encoding.")
 }
 
+/// Verify content and deserialize it strictly, see `internal::from_slice_strict`.
+pub fn verify_strict<T>( key : &PublicKey, signed : PKASigned) -> Result<T, &'static str> where T : DeserializeOwned {
+    let bs = verify_content( key, signed)?;
+    internal::from_slice_strict( &bs)
+}
+
 pub fn sign_content( key : &PrivateKey, message : Vec<u8>) -> Result<PKASigned, &'static str> {
     let signature = auth::sign( &key, &message).map_err(|_| "Error signing content.")?;
     // let identifier = ToIdentifier::to_identifier( key);
@@ -148,9 +292,42 @@ pub fn verify_content( key : &PublicKey, signed : PKASigned) -> Result<Vec<u8>,
     Ok( signed.content)
 }
 
+/// Verify content, rejecting algorithms the policy does not allow for verification.
+pub fn verify_with_policy<T>( policy : &Policy, key : &PublicKey, signed : PKASigned) -> Result<T, &'static str> where T : DeserializeOwned {
+    let bs = verify_content_with_policy( policy, key, signed)?;
+    serde_json::from_slice( &bs).map_err(|_| "Invalid json encoding.")
+}
+
+pub fn verify_content_with_policy( policy : &Policy, key : &PublicKey, signed : PKASigned) -> Result<Vec<u8>, &'static str> {
+    policy.check( Operation::Verify, &ToAlgorithm::to_algorithm( &signed))?;
+    verify_content( key, signed)
+}
+
+/// Verify content, rejecting keys that are expired at the clock's time or are not for signing.
+pub fn verify_with_metadata<T, C>( clock : &C, key : &PKAKey<PublicKey>, signed : PKASigned) -> Result<T, &'static str> where T : DeserializeOwned, C : Clock {
+    let bs = verify_content_with_metadata( clock, key, signed)?;
+    serde_json::from_slice( &bs).map_err(|_| "Invalid json encoding.")
+}
+
+pub fn verify_content_with_metadata<C>( clock : &C, key : &PKAKey<PublicKey>, signed : PKASigned) -> Result<Vec<u8>, &'static str> where C : Clock {
+    key.metadata.check( clock, Usage::Sign)?;
+    verify_content( &key.key, signed)
+}
+
+/// Verify content, rejecting keys revoked by a revocation certificate. Revocation lists only apply to the certificates of their issuer, so they are consulted when validating certificate chains.
+pub fn verify_with_revocations<T>( revocations : &RevocationSet, key : &PublicKey, signed : PKASigned) -> Result<T, &'static str> where T : DeserializeOwned {
+    let bs = verify_content_with_revocations( revocations, key, signed)?;
+    serde_json::from_slice( &bs).map_err(|_| "Invalid json encoding.")
+}
+
+pub fn verify_content_with_revocations( revocations : &RevocationSet, key : &PublicKey, signed : PKASigned) -> Result<Vec<u8>, &'static str> {
+    revocations.check( &ToIdentifier::to_identifier( key))?;
+    verify_content( key, signed)
+}
+
 pub fn sign_bs<T>( key : &PrivateKey, o : &T) -> Result<Vec<u8>, &'static str> where T:Serialize {
     let v = serde_json::to_vec( &o).map_err(|_| "Error generating json.")?;
-    sign_content_bs( key, v)
+    sign_content_bs( key, v, &Format::Json)
 }
 
 pub fn verify_bs<T>( key : &PublicKey, signed : Vec<u8>) -> Result<T, &'static str> where T : DeserializeOwned {
@@ -158,13 +335,91 @@ pub fn verify_bs<T>( key : &PublicKey, signed : Vec<u8>) -> Result<T, &'static s
     serde_json::from_slice( &bs).map_err(|_| "Invalid json encoding.")
 }
 
-pub fn sign_content_bs( key :&PrivateKey, message : Vec<u8>) -> Result<Vec<u8>, &'static str> {
+pub fn sign_content_bs( key :&PrivateKey, message : Vec<u8>, format : &Format) -> Result<Vec<u8>, &'static str> {
 	let signed = sign_content( key, message)?;
-    serde_json::to_vec( &signed).map_err(|_| "Error generating json.")
+    match *format {
+        Format::Json => serde_json::to_vec( &signed).map_err(|_| "Error generating json."),
+        Format::Binary => Ok( EncodeBinary::encode_binary( &signed)),
+    }
 }
 
+/// Verify signed content in either format.
 pub fn verify_content_bs(pub_key : &PublicKey, signed : Vec<u8>) -> Result<Vec<u8>, &'static str> {
-    let signed = serde_json::from_slice( &signed).map_err(|_| "Invalid encoding.")?;
+    let signed = if binary::is_binary( &signed) {
+        DecodeBinary::decode_binary( &signed)?
+    }
+    else {
+        serde_json::from_slice( &signed).map_err(|_| "Invalid encoding.")?
+    };
     verify_content( pub_key, signed)
 }
 
+/// Verify signed content in either format, deserializing the json format strictly.
+pub fn verify_content_bs_strict( pub_key : &PublicKey, signed : Vec<u8>) -> Result<Vec<u8>, &'static str> {
+    let signed = if binary::is_binary( &signed) {
+        DecodeBinary::decode_binary( &signed)?
+    }
+    else {
+        internal::from_slice_strict( &signed)?
+    };
+    verify_content( pub_key, signed)
+}
+
+pub fn verify_bs_strict<T>( key : &PublicKey, signed : Vec<u8>) -> Result<T, &'static str> where T : DeserializeOwned {
+    let bs = verify_content_bs_strict( key, signed)?;
+    internal::from_slice_strict( &bs)
+}
+
+/// Sign the canonical JSON (RFC8785) encoding of a value, so other implementations can reproduce the signed bytes.
+pub fn sign_canonical<T>( key : &PrivateKey, o : &T) -> Result<PKASigned, &'static str> where T : Serialize {
+    let r = jcs::to_canonical( o)?;
+    sign_content( key, r)
+}
+
+/// Verify a signature over canonical JSON. Content that is not canonical is rejected.
+pub fn verify_canonical<T>( key : &PublicKey, signed : PKASigned) -> Result<T, &'static str> where T : DeserializeOwned {
+    let bs = verify_content( key, signed)?;
+    jcs::is_canonical( &bs).ok_or( "Content is not canonical json.")?;
+    serde_json::from_slice( &bs).map_err(|_| "Invalid json encoding.")
+}
+
+/// Sign a value that serializes to a JSON object and attach the signature to it, in the style of JSF (https://cyberphone.github.io/doc/security/jsf.html).
+/// The `signature` property holds the algorithm and the signature `value`. The canonical encoding of the object without the `value` is signed.
+pub fn sign_inline<T>( key : &PrivateKey, o : &T) -> Result<Value, &'static str> where T : Serialize {
+    let mut v = serde_json::to_value( o).map_err(|_| "Error generating json.")?;
+    {
+        let object = v.as_object_mut().ok_or( "Only json objects can be signed inline.")?;
+        (!object.contains_key( INLINESIGNATURE)).ok_or( "Object is already signed.")?;
+        object.insert( INLINESIGNATURE.to_string(), json!({
+            "algorithm": AlgorithmId::to_algorithm_id( &ToAlgorithm::to_algorithm( key)),
+        }));
+    }
+
+    let signature = auth::sign( key, &jcs::canonicalize( &v)?).map_err(|_| "Error signing content.")?;
+    v[INLINESIGNATURE]["value"] = Value::String( serialize_psf( &signature));
+
+    Ok( v)
+}
+
+/// Verify an inline signed JSON object and deserialize it without its signature.
+pub fn verify_inline<T>( key : &PublicKey, signed : &Value) -> Result<T, &'static str> where T : DeserializeOwned {
+    let mut v = signed.clone();
+    let signature = {
+        let signature = v.get_mut( INLINESIGNATURE).and_then(|s| s.as_object_mut()).ok_or( "Missing inline signature.")?;
+        let value = signature.remove( "value").ok_or( "Missing signature value.")?;
+        let value = value.as_str().ok_or( "Invalid signature value.")?.to_string();
+
+        let algorithm = signature.get( "algorithm").and_then(|a| a.as_str()).ok_or( "Missing signature algorithm.")?;
+        let algorithm : Algorithm = AlgorithmId::from_algorithm_id( algorithm).ok_or( "Invalid algorithm identifier.")?;
+        (ToAlgorithm::to_algorithm( key) == algorithm).ok_or( "Algorithms do not match.")?;
+
+        deserialize_psf( &algorithm, &value)?
+    };
+
+    auth::verify( key, &jcs::canonicalize( &v)?, &signature).ok_or( "Invalid signature.")?;
+
+    if let Some( object) = v.as_object_mut() {
+        object.remove( INLINESIGNATURE);
+    }
+    serde_json::from_value( v).map_err(|_| "Invalid json encoding.")
+}
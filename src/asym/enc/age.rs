@@ -0,0 +1,443 @@
+// age file encryption (v1) with X25519 and scrypt recipients: https://age-encryption.org/v1
+
+use base64;
+use boolinator::Boolinator;
+use ring::aead;
+use ring::digest::SHA256;
+use ring::hkdf;
+use ring::hmac;
+use ring::hmac::SigningKey;
+use ring::rand::{SecureRandom, SystemRandom};
+use scrypt;
+
+use asym::enc::{Algorithm, PrivateKey, PublicKey, gen};
+use crypto_abstract::{ToAlgorithm, ToPublicKey};
+use encoding::bech32;
+use internal::asym::enc::{agree_x25519, private_key_from_seed};
+use internal::{EncodePSF, u8_to_fixed_length_32};
+use metadata::{Clock, PKAKey, Usage};
+use policy::{Operation, Policy};
+
+/// Default scrypt work factor (log2 N) for passphrase recipients.
+pub const DEFAULTWORKFACTOR : u8 = 18;
+
+const RECIPIENTPREFIX : &'static str = "age";
+const IDENTITYPREFIX : &'static str = "age-secret-key-";
+
+const VERSIONLINE : &'static [u8] = b"age-encryption.org/v1\n";
+const X25519LABEL : &'static [u8] = b"age-encryption.org/v1/X25519";
+const SCRYPTLABEL : &'static [u8] = b"age-encryption.org/v1/scrypt";
+
+const ARMORBEGIN : &'static str = "-----BEGIN AGE ENCRYPTED FILE-----";
+const ARMOREND : &'static str = "-----END AGE ENCRYPTED FILE-----";
+
+const FILEKEYLENGTH : usize = 16;
+const WRAPPEDKEYLENGTH : usize = FILEKEYLENGTH + 16;
+const PAYLOADNONCELENGTH : usize = 16;
+const SCRYPTSALTLENGTH : usize = 16;
+const CHUNKLENGTH : usize = 64 * 1024;
+const TAGLENGTH : usize = 16;
+const COLUMNS : usize = 64;
+
+/// Recipient of an age file.
+pub enum Recipient<'a> {
+    X25519( &'a PublicKey),
+    /// Passphrase with a scrypt work factor (log2 N). Must be the file's only recipient.
+    Scrypt( &'a [u8], u8),
+}
+
+/// Identity that can decrypt an age file.
+pub enum Identity<'a> {
+    X25519( &'a PrivateKey),
+    /// Passphrase with the maximum scrypt work factor (log2 N) to accept.
+    Scrypt( &'a [u8], u8),
+}
+
+/// Render a public key as an age recipient (`age1...`).
+pub fn to_recipient_string( key : &PublicKey) -> String {
+    bech32::encode_variant( bech32::Variant::Bech32, RECIPIENTPREFIX, &EncodePSF::encode_psf( key))
+}
+
+/// Parse an age recipient (`age1...`).
+pub fn from_recipient_string( s : &str) -> Result<PublicKey, &'static str> {
+    let (hrp, data) = bech32::decode_variant( bech32::Variant::Bech32, s)?;
+    (hrp == RECIPIENTPREFIX).ok_or( "Not an age recipient.")?;
+    let key = u8_to_fixed_length_32( &data).ok_or( "Recipient is wrong length.")?;
+
+    Ok( PublicKey::AEX25519( key))
+}
+
+/// Render a private key as an age identity (`AGE-SECRET-KEY-1...`).
+pub fn to_identity_string( key : &PrivateKey) -> String {
+    bech32::encode_variant( bech32::Variant::Bech32, IDENTITYPREFIX, &EncodePSF::encode_psf( key)).to_uppercase()
+}
+
+/// Parse an age identity (`AGE-SECRET-KEY-1...`).
+pub fn from_identity_string( s : &str) -> Result<PrivateKey, &'static str> {
+    let (hrp, data) = bech32::decode_variant( bech32::Variant::Bech32, s)?;
+    (hrp == IDENTITYPREFIX).ok_or( "Not an age identity.")?;
+    (data.len() == 32).ok_or( "Identity is wrong length.")?;
+
+    private_key_from_seed( &Algorithm::AEX25519, &data)
+}
+
+/// Encrypt a file to the recipients in the binary age format.
+pub fn encrypt( rng : &SystemRandom, recipients : &[Recipient], plaintext : &[u8]) -> Result<Vec<u8>, &'static str> {
+    (!recipients.is_empty()).ok_or( "No recipients.")?;
+    let scrypt = recipients.iter().any(|r| match *r { Recipient::Scrypt(..) => true, _ => false});
+    (!scrypt || recipients.len() == 1).ok_or( "A passphrase must be the only recipient.")?;
+
+    let mut file_key = [0u8; FILEKEYLENGTH];
+    rng.fill( &mut file_key).map_err(|_| "Error generating file key.")?;
+
+    let mut header = VERSIONLINE.to_vec();
+    for recipient in recipients {
+        let (args, body) = wrap( rng, recipient, &file_key)?;
+        write_stanza( &mut header, &args, &body);
+    }
+    header.extend( b"---");
+    let mac = header_mac( &file_key, &header);
+    header.push( b' ');
+    header.extend( base64::encode_config( mac.as_ref(), base64::STANDARD_NO_PAD).as_bytes());
+    header.push( b'\n');
+
+    let mut nonce = [0u8; PAYLOADNONCELENGTH];
+    rng.fill( &mut nonce).map_err(|_| "Error generating nonce.")?;
+    let key = payload_key( &file_key, &nonce);
+
+    let mut out = header;
+    out.extend( &nonce);
+    if plaintext.is_empty() {
+        out.extend( seal_chunk( &key, 0, true, &[])?);
+    }
+    else {
+        let count = (plaintext.len() + CHUNKLENGTH - 1) / CHUNKLENGTH;
+        for (i, chunk) in plaintext.chunks( CHUNKLENGTH).enumerate() {
+            out.extend( seal_chunk( &key, i as u64, i + 1 == count, chunk)?);
+        }
+    }
+
+    Ok( out)
+}
+
+/// Decrypt an age file, binary or armored, with the first identity that unwraps its file key.
+pub fn decrypt( identities : &[Identity], ciphertext : &[u8]) -> Result<Vec<u8>, &'static str> {
+    let dearmored;
+    let ciphertext = match ::std::str::from_utf8( ciphertext) {
+        Ok( s) if s.trim_start().starts_with( ARMORBEGIN) => {
+            dearmored = dearmor( s)?;
+            &dearmored[..]
+        }
+        _ => ciphertext,
+    };
+
+    let (stanzas, mac, header_length, payload_start) = parse_header( ciphertext)?;
+
+    let scrypt = stanzas.iter().any(|s| s.kind == "scrypt");
+    (!scrypt || stanzas.len() == 1).ok_or( "A passphrase must be the only recipient.")?;
+
+    let mut file_key = None;
+    'search: for stanza in &stanzas {
+        for identity in identities {
+            if let Some( key) = unwrap( identity, stanza)? {
+                file_key = Some( key);
+                break 'search;
+            }
+        }
+    }
+    let file_key = file_key.ok_or( "No identity matches a recipient.")?;
+
+    let verification = hmac::VerificationKey::new( &SHA256, &hkdf_sha256( &file_key, b"", b"header"));
+    hmac::verify( &verification, &ciphertext[0 .. header_length], &mac).map_err(|_| "Invalid header MAC.")?;
+
+    let payload = &ciphertext[payload_start ..];
+    (payload.len() > PAYLOADNONCELENGTH).ok_or( "Payload is too short.")?;
+    let (nonce, mut payload) = payload.split_at( PAYLOADNONCELENGTH);
+    let key = payload_key( &file_key, nonce);
+
+    let mut plaintext = Vec::new();
+    let mut counter = 0;
+    loop {
+        let length = ::std::cmp::min( payload.len(), CHUNKLENGTH + TAGLENGTH);
+        let last = length == payload.len();
+        let (chunk, rest) = payload.split_at( length);
+
+        let chunk = open_chunk( &key, counter, last, chunk)?;
+        (!chunk.is_empty() || counter == 0).ok_or( "Final chunk is empty.")?;
+        plaintext.extend( chunk);
+
+        if last {
+            return Ok( plaintext)
+        }
+        payload = rest;
+        counter += 1;
+    }
+}
+
+/// Decrypt an age file, rejecting X25519 identities the policy does not allow for decryption. Passphrases have no key algorithm, so the policy does not restrict them.
+pub fn decrypt_with_policy( policy : &Policy, identities : &[Identity], ciphertext : &[u8]) -> Result<Vec<u8>, &'static str> {
+    for identity in identities {
+        if let Identity::X25519( key) = *identity {
+            policy.check( Operation::Decrypt, &ToAlgorithm::to_algorithm( key))?;
+        }
+    }
+    decrypt( identities, ciphertext)
+}
+
+/// Decrypt an age file with the keys that are valid at the clock's time and are for encryption. Fails if no key is.
+pub fn decrypt_with_metadata<C>( clock : &C, keys : &[&PKAKey<PrivateKey>], ciphertext : &[u8]) -> Result<Vec<u8>, &'static str> where C : Clock {
+    let mut identities = Vec::new();
+    let mut error = "No identity matches a recipient.";
+    for key in keys {
+        match key.metadata.check( clock, Usage::Encrypt) {
+            Ok(()) => identities.push( Identity::X25519( &key.key)),
+            Err( e) => error = e,
+        }
+    }
+    (!identities.is_empty()).ok_or( error)?;
+
+    decrypt( &identities, ciphertext)
+}
+
+/// Armor an age file in its PEM style text encoding.
+pub fn armor( file : &[u8]) -> String {
+    let encoded = base64::encode_config( file, base64::STANDARD);
+
+    let mut out = String::new();
+    out.push_str( ARMORBEGIN);
+    out.push( '\n');
+    for line in encoded.as_bytes().chunks( COLUMNS) {
+        out.push_str( &String::from_utf8_lossy( line));
+        out.push( '\n');
+    }
+    out.push_str( ARMOREND);
+    out.push( '\n');
+    out
+}
+
+/// Remove the armor from an age file.
+pub fn dearmor( s : &str) -> Result<Vec<u8>, &'static str> {
+    let lines : Vec<&str> = s.trim().lines().map(|l| l.trim_end_matches( '\r')).collect();
+    (lines.len() >= 2).ok_or( "Armor is too short.")?;
+    (lines[0] == ARMORBEGIN).ok_or( "Missing armor header.")?;
+    (lines[lines.len() - 1] == ARMOREND).ok_or( "Missing armor footer.")?;
+
+    let body = &lines[1 .. lines.len() - 1];
+    for (i, line) in body.iter().enumerate() {
+        let full = line.len() == COLUMNS;
+        (full || (i + 1 == body.len() && line.len() < COLUMNS)).ok_or( "Invalid armor line length.")?;
+    }
+    let encoded = body.concat();
+
+    let file = base64::decode_config( &encoded, base64::STANDARD).map_err(|_| "Invalid armor encoding.")?;
+    (base64::encode_config( &file, base64::STANDARD) == encoded).ok_or( "Non-canonical armor encoding.")?;
+    Ok( file)
+}
+
+struct Stanza {
+    kind : String,
+    args : Vec<String>,
+    body : Vec<u8>,
+}
+
+// Parse the header to its stanzas, its MAC, the length of the MACed prefix (up to and including "---"), and the start of the payload.
+fn parse_header( file : &[u8]) -> Result<(Vec<Stanza>, Vec<u8>, usize, usize), &'static str> {
+    file.starts_with( VERSIONLINE).ok_or( "Not an age v1 file.")?;
+
+    let mut stanzas = Vec::new();
+    let mut position = VERSIONLINE.len();
+    loop {
+        let line = read_line( file, position)?;
+        if line.starts_with( "---") {
+            (line.len() > 4 && line.as_bytes()[3] == b' ').ok_or( "Invalid header MAC line.")?;
+            let mac = decode_canonical( &line[4 ..])?;
+            (mac.len() == 32).ok_or( "Header MAC is wrong length.")?;
+
+            return Ok( (stanzas, mac, position + 3, position + line.len() + 1))
+        }
+
+        line.starts_with( "-> ").ok_or( "Invalid stanza.")?;
+        let mut args = line[3 ..].split( ' ').map( String::from).collect::<Vec<String>>();
+        args.iter().all(|a| !a.is_empty()).ok_or( "Empty stanza argument.")?;
+        let kind = args.remove( 0);
+        position += line.len() + 1;
+
+        let mut encoded = String::new();
+        loop {
+            let line = read_line( file, position)?;
+            (line.len() <= COLUMNS).ok_or( "Stanza body line is too long.")?;
+            encoded.push_str( line);
+            position += line.len() + 1;
+            if line.len() < COLUMNS {
+                break
+            }
+        }
+
+        stanzas.push( Stanza{ kind : kind, args : args, body : decode_canonical( &encoded)?});
+    }
+}
+
+fn read_line( file : &[u8], position : usize) -> Result<&str, &'static str> {
+    let rest = file.get( position ..).ok_or( "Header is truncated.")?;
+    let end = rest.iter().position(|&b| b == b'\n').ok_or( "Header is truncated.")?;
+    let line = ::std::str::from_utf8( &rest[0 .. end]).map_err(|_| "Header is not valid text.")?;
+    line.bytes().all(|b| b >= 0x20 && b <= 0x7e).ok_or( "Header is not printable ASCII.")?;
+
+    Ok( line)
+}
+
+fn write_stanza( header : &mut Vec<u8>, args : &[String], body : &[u8]) {
+    header.extend( b"->");
+    for arg in args {
+        header.push( b' ');
+        header.extend( arg.as_bytes());
+    }
+    header.push( b'\n');
+
+    // The body is wrapped at 64 columns and always ends with a partial (possibly empty) line.
+    let encoded = base64::encode_config( body, base64::STANDARD_NO_PAD);
+    let encoded = encoded.as_bytes();
+    let mut start = 0;
+    loop {
+        let end = ::std::cmp::min( start + COLUMNS, encoded.len());
+        header.extend( &encoded[start .. end]);
+        header.push( b'\n');
+        if end - start < COLUMNS {
+            break
+        }
+        start = end;
+    }
+}
+
+fn decode_canonical( s : &str) -> Result<Vec<u8>, &'static str> {
+    let bs = base64::decode_config( s, base64::STANDARD_NO_PAD).map_err(|_| "Invalid base64 encoding.")?;
+    (base64::encode_config( &bs, base64::STANDARD_NO_PAD) == s).ok_or( "Non-canonical base64 encoding.")?;
+    Ok( bs)
+}
+
+fn wrap( rng : &SystemRandom, recipient : &Recipient, file_key : &[u8]) -> Result<(Vec<String>, Vec<u8>), &'static str> {
+    match *recipient {
+        Recipient::X25519( public) => {
+            let ephemeral = gen( rng, &Algorithm::AEX25519)?;
+            let share = EncodePSF::encode_psf( &ToPublicKey::to_public_key( &ephemeral));
+            let shared = agree_x25519( &ephemeral, public)?;
+
+            let mut salt = share.clone();
+            salt.extend( EncodePSF::encode_psf( public));
+            let key = hkdf_sha256( &shared, &salt, X25519LABEL);
+
+            let args = vec![ "X25519".to_string(), base64::encode_config( &share, base64::STANDARD_NO_PAD)];
+            Ok( (args, seal_key( &key, file_key)?))
+        }
+        Recipient::Scrypt( passphrase, work_factor) => {
+            let mut salt = [0u8; SCRYPTSALTLENGTH];
+            rng.fill( &mut salt).map_err(|_| "Error generating salt.")?;
+            let key = scrypt_key( passphrase, &salt, work_factor)?;
+
+            let args = vec![ "scrypt".to_string(), base64::encode_config( &salt, base64::STANDARD_NO_PAD), work_factor.to_string()];
+            Ok( (args, seal_key( &key, file_key)?))
+        }
+    }
+}
+
+// Unwrap the file key if the stanza is for the identity.
+fn unwrap( identity : &Identity, stanza : &Stanza) -> Result<Option<Vec<u8>>, &'static str> {
+    match (identity, stanza.kind.as_str()) {
+        (&Identity::X25519( private), "X25519") => {
+            (stanza.args.len() == 1).ok_or( "Invalid X25519 stanza.")?;
+            let share = decode_canonical( &stanza.args[0])?;
+            let share = u8_to_fixed_length_32( &share).ok_or( "Invalid X25519 stanza.")?;
+            (stanza.body.len() == WRAPPEDKEYLENGTH).ok_or( "Invalid X25519 stanza.")?;
+
+            let shared = agree_x25519( private, &PublicKey::AEX25519( share))?;
+            let mut salt = share.to_vec();
+            salt.extend( EncodePSF::encode_psf( &ToPublicKey::to_public_key( private)));
+            let key = hkdf_sha256( &shared, &salt, X25519LABEL);
+
+            Ok( open_key( &key, &stanza.body))
+        }
+        (&Identity::Scrypt( passphrase, max_work_factor), "scrypt") => {
+            (stanza.args.len() == 2).ok_or( "Invalid scrypt stanza.")?;
+            let salt = decode_canonical( &stanza.args[0])?;
+            (salt.len() == SCRYPTSALTLENGTH).ok_or( "Invalid scrypt stanza.")?;
+            let work_factor : u8 = stanza.args[1].parse().map_err(|_| "Invalid scrypt stanza.")?;
+            (work_factor.to_string() == stanza.args[1]).ok_or( "Invalid scrypt stanza.")?;
+            (work_factor <= max_work_factor).ok_or( "scrypt work factor is too large.")?;
+            (stanza.body.len() == WRAPPEDKEYLENGTH).ok_or( "Invalid scrypt stanza.")?;
+
+            let key = scrypt_key( passphrase, &salt, work_factor)?;
+            Ok( open_key( &key, &stanza.body))
+        }
+        _ => Ok( None),
+    }
+}
+
+fn scrypt_key( passphrase : &[u8], salt : &[u8], work_factor : u8) -> Result<Vec<u8>, &'static str> {
+    (work_factor > 0 && work_factor < 64).ok_or( "Invalid scrypt work factor.")?;
+    let params = scrypt::Params::new( work_factor, 8, 1, 32).map_err(|_| "Invalid scrypt work factor.")?;
+
+    let mut label = SCRYPTLABEL.to_vec();
+    label.extend( salt);
+    let mut key = vec![0u8; 32];
+    scrypt::scrypt( passphrase, &label, &params, &mut key).map_err(|_| "Error deriving passphrase key.")?;
+    Ok( key)
+}
+
+// File keys are wrapped with ChaCha20-Poly1305 under a zero nonce, since every wrapping key is used once.
+fn seal_key( key : &[u8], file_key : &[u8]) -> Result<Vec<u8>, &'static str> {
+    seal( key, &[0u8; 12], file_key)
+}
+
+fn open_key( key : &[u8], wrapped : &[u8]) -> Option<Vec<u8>> {
+    open( key, &[0u8; 12], wrapped).ok()
+}
+
+fn seal_chunk( key : &[u8], counter : u64, last : bool, chunk : &[u8]) -> Result<Vec<u8>, &'static str> {
+    seal( key, &chunk_nonce( counter, last), chunk)
+}
+
+fn open_chunk( key : &[u8], counter : u64, last : bool, chunk : &[u8]) -> Result<Vec<u8>, &'static str> {
+    open( key, &chunk_nonce( counter, last), chunk)
+}
+
+// The chunk nonce is an 11 byte big endian counter followed by a flag for the last chunk.
+fn chunk_nonce( counter : u64, last : bool) -> [u8; 12] {
+    let mut nonce = [0u8; 12];
+    for i in 0 .. 8 {
+        nonce[10 - i] = (counter >> (8 * i)) as u8;
+    }
+    nonce[11] = if last { 1 } else { 0 };
+    nonce
+}
+
+fn seal( key : &[u8], nonce : &[u8], plaintext : &[u8]) -> Result<Vec<u8>, &'static str> {
+    let key = aead::SealingKey::new( &aead::CHACHA20_POLY1305, key).map_err(|_| "Invalid key.")?;
+
+    let mut in_out = plaintext.to_vec();
+    in_out.extend( &[0u8; TAGLENGTH]);
+    aead::seal_in_place( &key, nonce, &[], &mut in_out, TAGLENGTH).map_err(|_| "Error encrypting content.")?;
+    Ok( in_out)
+}
+
+fn open( key : &[u8], nonce : &[u8], ciphertext : &[u8]) -> Result<Vec<u8>, &'static str> {
+    let key = aead::OpeningKey::new( &aead::CHACHA20_POLY1305, key).map_err(|_| "Invalid key.")?;
+
+    let mut in_out = ciphertext.to_vec();
+    let length = aead::open_in_place( &key, nonce, &[], 0, &mut in_out).map_err(|_| "Could not decrypt ciphertext.")?.len();
+    in_out.truncate( length);
+    Ok( in_out)
+}
+
+fn header_mac( file_key : &[u8], header : &[u8]) -> hmac::Signature {
+    hmac::sign( &SigningKey::new( &SHA256, &hkdf_sha256( file_key, b"", b"header")), header)
+}
+
+fn payload_key( file_key : &[u8], nonce : &[u8]) -> Vec<u8> {
+    hkdf_sha256( file_key, nonce, b"payload")
+}
+
+fn hkdf_sha256( ikm : &[u8], salt : &[u8], info : &[u8]) -> Vec<u8> {
+    let mut out = vec![0u8; 32];
+    hkdf::extract_and_expand( &SigningKey::new( &SHA256, salt), ikm, info, &mut out);
+    out
+}
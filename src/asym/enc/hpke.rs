@@ -0,0 +1,475 @@
+// Hybrid public key encryption following RFC9180: https://www.rfc-editor.org/rfc/rfc9180.html
+// The KEM is DHKEM(X25519, HKDF-SHA256) and the KDF is HKDF-SHA256.
+
+use boolinator::Boolinator;
+use crypto_abstract::{ToAlgorithm, ToPublicKey};
+use ring::aead;
+use ring::digest::SHA256;
+use ring::hkdf;
+use ring::hmac;
+use ring::hmac::SigningKey;
+use ring::rand::SystemRandom;
+use serde::de;
+use serde::de::{Deserialize, Deserializer, MapAccess, Visitor};
+use serde::ser::{Serialize, Serializer, SerializeStruct};
+use std::fmt;
+
+use asym::enc::{PrivateKey, PublicKey, gen};
+use asym::enc::Algorithm as AEAlgorithm;
+use encoding::{DecodeCbor, EncodeCbor};
+use encoding::cbor;
+use encoding::cbor::Value as CborValue;
+use internal::asym::enc::{agree_x25519, private_key_from_seed};
+use internal::{AlgorithmId, AlgorithmStrength, DecodePSF, EncodePSF, deserialize_base64url, is_strict, serialize_base64url};
+use metadata::{Clock, PKAKey, Usage};
+use policy::{Operation, Policy};
+
+const KEMID : u16 = 0x0020;
+const KDFID : u16 = 0x0001;
+const VERSIONLABEL : &'static [u8] = b"HPKE-v1";
+const SECRETLENGTH : usize = 32;
+const NONCELENGTH : usize = 12;
+
+/// HPKE cipher suites over DHKEM(X25519, HKDF-SHA256) and HKDF-SHA256, named by their AEAD.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Algorithm {
+    HPKEX25519AesGcm128,
+    HPKEX25519AesGcm256,
+    HPKEX25519ChaCha20Poly1305,
+}
+
+impl Algorithm {
+    fn aead_id( &self) -> u16 {
+        match *self {
+            Algorithm::HPKEX25519AesGcm128 => 0x0001,
+            Algorithm::HPKEX25519AesGcm256 => 0x0002,
+            Algorithm::HPKEX25519ChaCha20Poly1305 => 0x0003,
+        }
+    }
+
+    fn aead( &self) -> &'static aead::Algorithm {
+        match *self {
+            Algorithm::HPKEX25519AesGcm128 => &aead::AES_128_GCM,
+            Algorithm::HPKEX25519AesGcm256 => &aead::AES_256_GCM,
+            Algorithm::HPKEX25519ChaCha20Poly1305 => &aead::CHACHA20_POLY1305,
+        }
+    }
+}
+
+impl AlgorithmId for Algorithm {
+    fn to_algorithm_id( alg : &Algorithm) -> &'static str {
+        match *alg {
+            Algorithm::HPKEX25519AesGcm128 => "hpke-x25519-aesgcm128",
+            Algorithm::HPKEX25519AesGcm256 => "hpke-x25519-aesgcm256",
+            Algorithm::HPKEX25519ChaCha20Poly1305 => "hpke-x25519-chacha20poly1305",
+        }
+    }
+
+    fn from_algorithm_id( id : &str) -> Option<Algorithm> {
+        match id {
+            "hpke-x25519-aesgcm128" => Some( Algorithm::HPKEX25519AesGcm128),
+            "hpke-x25519-aesgcm256" => Some( Algorithm::HPKEX25519AesGcm256),
+            "hpke-x25519-chacha20poly1305" => Some( Algorithm::HPKEX25519ChaCha20Poly1305),
+            _ => None
+        }
+    }
+}
+
+// Every suite uses X25519, so its key size is that of the AEAD.
+impl AlgorithmStrength for Algorithm {
+    fn key_bits( alg : &Algorithm) -> usize {
+        match *alg {
+            Algorithm::HPKEX25519AesGcm128 => 128,
+            Algorithm::HPKEX25519AesGcm256 => 256,
+            Algorithm::HPKEX25519ChaCha20Poly1305 => 256,
+        }
+    }
+
+    fn is_aead( _ : &Algorithm) -> bool {
+        true
+    }
+}
+
+/// Pre-shared key and its identifier.
+#[derive(Clone, Copy)]
+pub struct Psk<'a> {
+    pub psk : &'a [u8],
+    pub psk_id : &'a [u8],
+}
+
+/// Mode of the sender. The authenticated modes take the sender's private key.
+pub enum SenderMode<'a> {
+    Base,
+    Psk( Psk<'a>),
+    Auth( &'a PrivateKey),
+    AuthPsk( Psk<'a>, &'a PrivateKey),
+}
+
+/// Mode of the recipient. The authenticated modes take the sender's public key.
+pub enum ReceiverMode<'a> {
+    Base,
+    Psk( Psk<'a>),
+    Auth( &'a PublicKey),
+    AuthPsk( Psk<'a>, &'a PublicKey),
+}
+
+/// Encapsulated key that the recipient needs to set up its context.
+pub struct EncapsulatedKey {
+    algorithm : Algorithm,
+    enc : PublicKey,
+}
+
+impl EncapsulatedKey {
+    /// Encapsulated key received by other means, such as the raw bytes of a protocol message. `setup_receiver` rejects keys of small order.
+    pub fn new( algorithm : Algorithm, enc : PublicKey) -> EncapsulatedKey {
+        EncapsulatedKey{ algorithm : algorithm, enc : enc}
+    }
+
+    pub fn algorithm( &self) -> Algorithm {
+        self.algorithm
+    }
+
+    /// Ephemeral public key of the sender.
+    pub fn public_key( &self) -> &PublicKey {
+        &self.enc
+    }
+}
+
+struct Context {
+    algorithm : Algorithm,
+    key : Vec<u8>,
+    base_nonce : Vec<u8>,
+    exporter_secret : Vec<u8>,
+    suite_id : Vec<u8>,
+    seq : u64,
+}
+
+impl Context {
+    fn nonce( &self) -> Vec<u8> {
+        let mut nonce = self.base_nonce.clone();
+        for i in 0 .. 8 {
+            nonce[NONCELENGTH - 1 - i] ^= (self.seq >> (8 * i)) as u8;
+        }
+        nonce
+    }
+
+    fn increment( &mut self) -> Result<(), &'static str> {
+        self.seq = self.seq.checked_add( 1).ok_or( "HPKE message limit reached.")?;
+        Ok(())
+    }
+
+    fn export( &self, exporter_context : &[u8], length : usize) -> Result<Vec<u8>, &'static str> {
+        (length <= 255 * SECRETLENGTH).ok_or( "Exported secret is too long.")?;
+
+        Ok( labeled_expand( &self.suite_id, &self.exporter_secret, b"sec", exporter_context, length))
+    }
+}
+
+/// Context of a sender, which seals a sequence of messages to the recipient.
+pub struct SenderContext {
+    context : Context
+}
+
+impl SenderContext {
+    pub fn seal( &mut self, aad : &[u8], plaintext : &[u8]) -> Result<Vec<u8>, &'static str> {
+        let alg = self.context.algorithm.aead();
+        let key = aead::SealingKey::new( alg, &self.context.key).map_err(|_| "Invalid HPKE key.")?;
+
+        let mut in_out = plaintext.to_vec();
+        in_out.extend( vec![0u8; alg.tag_len()]);
+        aead::seal_in_place( &key, &self.context.nonce(), aad, &mut in_out, alg.tag_len()).map_err(|_| "Error encrypting content.")?;
+
+        self.context.increment()?;
+        Ok( in_out)
+    }
+
+    /// Export a secret of `length` bytes bound to the context.
+    pub fn export( &self, exporter_context : &[u8], length : usize) -> Result<Vec<u8>, &'static str> {
+        self.context.export( exporter_context, length)
+    }
+}
+
+/// Context of a recipient, which opens the sender's messages in order.
+pub struct ReceiverContext {
+    context : Context
+}
+
+impl ReceiverContext {
+    pub fn open( &mut self, aad : &[u8], ciphertext : &[u8]) -> Result<Vec<u8>, &'static str> {
+        let alg = self.context.algorithm.aead();
+        let key = aead::OpeningKey::new( alg, &self.context.key).map_err(|_| "Invalid HPKE key.")?;
+
+        let mut in_out = ciphertext.to_vec();
+        let length = aead::open_in_place( &key, &self.context.nonce(), aad, 0, &mut in_out).map_err(|_| "Could not decrypt ciphertext.")?.len();
+        in_out.truncate( length);
+
+        self.context.increment()?;
+        Ok( in_out)
+    }
+
+    /// Export a secret of `length` bytes bound to the context.
+    pub fn export( &self, exporter_context : &[u8], length : usize) -> Result<Vec<u8>, &'static str> {
+        self.context.export( exporter_context, length)
+    }
+}
+
+/// Deterministically derive a key pair from input keying material, as in DeriveKeyPair of RFC9180.
+pub fn derive_key_pair( ikm : &[u8]) -> Result<PrivateKey, &'static str> {
+    let suite_id = kem_suite_id();
+    let prk = labeled_extract( &suite_id, b"", b"dkp_prk", ikm);
+    let sk = labeled_expand( &suite_id, &prk, b"sk", b"", SECRETLENGTH);
+
+    private_key_from_seed( &AEAlgorithm::AEX25519, &sk)
+}
+
+/// Set up a context to send messages to the recipient.
+pub fn setup_sender( rng : &SystemRandom, alg : &Algorithm, recipient : &PublicKey, mode : &SenderMode, info : &[u8]) -> Result<(EncapsulatedKey, SenderContext), &'static str> {
+    let ephemeral = gen( rng, &AEAlgorithm::AEX25519)?;
+    setup_sender_with_ephemeral( &ephemeral, alg, recipient, mode, info)
+}
+
+/// Set up a sender context with the given ephemeral key. Only use this for test vectors, since ephemeral keys must never be reused.
+pub fn setup_sender_with_ephemeral( ephemeral : &PrivateKey, alg : &Algorithm, recipient : &PublicKey, mode : &SenderMode, info : &[u8]) -> Result<(EncapsulatedKey, SenderContext), &'static str> {
+    let enc = ToPublicKey::to_public_key( ephemeral);
+
+    let mut dh = agree_x25519( ephemeral, recipient)?.to_vec();
+    let mut kem_context = EncodePSF::encode_psf( &enc);
+    kem_context.extend( EncodePSF::encode_psf( recipient));
+
+    let (mode_id, psk) = match *mode {
+        SenderMode::Base => (0, None),
+        SenderMode::Psk( psk) => (1, Some( psk)),
+        SenderMode::Auth( sender) | SenderMode::AuthPsk( _, sender) => {
+            dh.extend( &agree_x25519( sender, recipient)?);
+            kem_context.extend( EncodePSF::encode_psf( &ToPublicKey::to_public_key( sender)));
+
+            match *mode {
+                SenderMode::AuthPsk( psk, _) => (3, Some( psk)),
+                _ => (2, None),
+            }
+        }
+    };
+
+    let shared_secret = extract_and_expand( &dh, &kem_context);
+    let context = key_schedule( alg, mode_id, &shared_secret, info, psk)?;
+
+    Ok( (EncapsulatedKey{ algorithm : *alg, enc : enc}, SenderContext{ context : context}))
+}
+
+/// Set up a context to receive messages from the sender of the encapsulated key.
+pub fn setup_receiver( encapsulated : &EncapsulatedKey, recipient : &PrivateKey, mode : &ReceiverMode, info : &[u8]) -> Result<ReceiverContext, &'static str> {
+    let enc = &encapsulated.enc;
+
+    let mut dh = agree_x25519( recipient, enc)?.to_vec();
+    let mut kem_context = EncodePSF::encode_psf( enc);
+    kem_context.extend( EncodePSF::encode_psf( &ToPublicKey::to_public_key( recipient)));
+
+    let (mode_id, psk) = match *mode {
+        ReceiverMode::Base => (0, None),
+        ReceiverMode::Psk( psk) => (1, Some( psk)),
+        ReceiverMode::Auth( sender) | ReceiverMode::AuthPsk( _, sender) => {
+            dh.extend( &agree_x25519( recipient, sender)?);
+            kem_context.extend( EncodePSF::encode_psf( sender));
+
+            match *mode {
+                ReceiverMode::AuthPsk( psk, _) => (3, Some( psk)),
+                _ => (2, None),
+            }
+        }
+    };
+
+    let shared_secret = extract_and_expand( &dh, &kem_context);
+    let context = key_schedule( &encapsulated.algorithm, mode_id, &shared_secret, info, psk)?;
+
+    Ok( ReceiverContext{ context : context})
+}
+
+/// Encrypt a single message to the recipient.
+pub fn seal( rng : &SystemRandom, alg : &Algorithm, recipient : &PublicKey, mode : &SenderMode, info : &[u8], aad : &[u8], plaintext : &[u8]) -> Result<(EncapsulatedKey, Vec<u8>), &'static str> {
+    let (encapsulated, mut context) = setup_sender( rng, alg, recipient, mode, info)?;
+    let ciphertext = context.seal( aad, plaintext)?;
+
+    Ok( (encapsulated, ciphertext))
+}
+
+/// Decrypt a single message from the sender of the encapsulated key.
+pub fn open( encapsulated : &EncapsulatedKey, recipient : &PrivateKey, mode : &ReceiverMode, info : &[u8], aad : &[u8], ciphertext : &[u8]) -> Result<Vec<u8>, &'static str> {
+    let mut context = setup_receiver( encapsulated, recipient, mode, info)?;
+    context.open( aad, ciphertext)
+}
+
+/// Decrypt a single message, rejecting suites and recipient keys the policy does not allow for decryption.
+pub fn open_with_policy( policy : &Policy, encapsulated : &EncapsulatedKey, recipient : &PrivateKey, mode : &ReceiverMode, info : &[u8], aad : &[u8], ciphertext : &[u8]) -> Result<Vec<u8>, &'static str> {
+    policy.check( Operation::Decrypt, &encapsulated.algorithm())?;
+    policy.check( Operation::Decrypt, &ToAlgorithm::to_algorithm( recipient))?;
+    open( encapsulated, recipient, mode, info, aad, ciphertext)
+}
+
+/// Decrypt a single message, rejecting recipient keys that are expired at the clock's time or are not for encryption.
+pub fn open_with_metadata<C>( clock : &C, encapsulated : &EncapsulatedKey, recipient : &PKAKey<PrivateKey>, mode : &ReceiverMode, info : &[u8], aad : &[u8], ciphertext : &[u8]) -> Result<Vec<u8>, &'static str> where C : Clock {
+    recipient.metadata.check( clock, Usage::Encrypt)?;
+    open( encapsulated, &recipient.key, mode, info, aad, ciphertext)
+}
+
+fn kem_suite_id() -> Vec<u8> {
+    let mut suite_id = b"KEM".to_vec();
+    suite_id.extend( &i2osp2( KEMID as usize));
+    suite_id
+}
+
+fn hpke_suite_id( alg : &Algorithm) -> Vec<u8> {
+    let mut suite_id = b"HPKE".to_vec();
+    suite_id.extend( &i2osp2( KEMID as usize));
+    suite_id.extend( &i2osp2( KDFID as usize));
+    suite_id.extend( &i2osp2( alg.aead_id() as usize));
+    suite_id
+}
+
+fn i2osp2( n : usize) -> [u8; 2] {
+    [ (n >> 8) as u8, n as u8]
+}
+
+// Extract is done with HMAC directly, since the hashes of the key schedule are needed as bytes.
+fn labeled_extract( suite_id : &[u8], salt : &[u8], label : &[u8], ikm : &[u8]) -> Vec<u8> {
+    let mut labeled_ikm = VERSIONLABEL.to_vec();
+    labeled_ikm.extend( suite_id);
+    labeled_ikm.extend( label);
+    labeled_ikm.extend( ikm);
+
+    hmac::sign( &SigningKey::new( &SHA256, salt), &labeled_ikm).as_ref().to_vec()
+}
+
+fn labeled_expand( suite_id : &[u8], prk : &[u8], label : &[u8], info : &[u8], length : usize) -> Vec<u8> {
+    let mut labeled_info = i2osp2( length).to_vec();
+    labeled_info.extend( VERSIONLABEL);
+    labeled_info.extend( suite_id);
+    labeled_info.extend( label);
+    labeled_info.extend( info);
+
+    let mut out = vec![0u8; length];
+    hkdf::expand( &SigningKey::new( &SHA256, prk), &labeled_info, &mut out);
+    out
+}
+
+fn extract_and_expand( dh : &[u8], kem_context : &[u8]) -> Vec<u8> {
+    let suite_id = kem_suite_id();
+    let prk = labeled_extract( &suite_id, b"", b"eae_prk", dh);
+    labeled_expand( &suite_id, &prk, b"shared_secret", kem_context, SECRETLENGTH)
+}
+
+fn key_schedule( alg : &Algorithm, mode : u8, shared_secret : &[u8], info : &[u8], psk : Option<Psk>) -> Result<Context, &'static str> {
+    let (psk, psk_id) = match psk {
+        Some( psk) => {
+            (!psk.psk.is_empty() && !psk.psk_id.is_empty()).ok_or( "Empty pre-shared key.")?;
+            (psk.psk, psk.psk_id)
+        }
+        None => (&b""[..], &b""[..]),
+    };
+
+    let suite_id = hpke_suite_id( alg);
+    let psk_id_hash = labeled_extract( &suite_id, b"", b"psk_id_hash", psk_id);
+    let info_hash = labeled_extract( &suite_id, b"", b"info_hash", info);
+
+    let mut key_schedule_context = vec![mode];
+    key_schedule_context.extend( psk_id_hash);
+    key_schedule_context.extend( info_hash);
+
+    let secret = labeled_extract( &suite_id, shared_secret, b"secret", psk);
+    let key = labeled_expand( &suite_id, &secret, b"key", &key_schedule_context, alg.aead().key_len());
+    let base_nonce = labeled_expand( &suite_id, &secret, b"base_nonce", &key_schedule_context, NONCELENGTH);
+    let exporter_secret = labeled_expand( &suite_id, &secret, b"exp", &key_schedule_context, SECRETLENGTH);
+
+    Ok( Context{ algorithm : *alg, key : key, base_nonce : base_nonce, exporter_secret : exporter_secret, suite_id : suite_id, seq : 0})
+}
+
+impl Serialize for EncapsulatedKey {
+    fn serialize<S>( &self, serializer : S) -> Result<S::Ok, S::Error> where S : Serializer {
+        let mut s = serializer.serialize_struct( "EncapsulatedKey", 2)?;
+
+        s.serialize_field( "encapsulated_key", &serialize_base64url( &EncodePSF::encode_psf( &self.enc)))?;
+        s.serialize_field( "algorithm", AlgorithmId::to_algorithm_id( &self.algorithm))?;
+
+        s.end()
+    }
+}
+
+impl<'d> Deserialize<'d> for EncapsulatedKey {
+    fn deserialize<D>( deserializer : D) -> Result<EncapsulatedKey, D::Error> where D : Deserializer<'d> {
+        struct V;
+
+        const FIELDS : &'static [&'static str] = &["encapsulated_key", "algorithm"];
+
+        impl<'d> Visitor<'d> for V {
+            type Value = EncapsulatedKey;
+
+            fn expecting( &self, formatter : &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str( "JSON EncapsulatedKey")
+            }
+
+            fn visit_map<U>( self, mut map : U) -> Result<EncapsulatedKey, U::Error> where U : MapAccess<'d> {
+                let mut enc = None;
+                let mut algorithm = None;
+
+                while let Some(k) = map.next_key::<String>()? {
+                    match k.as_str() {
+                        "encapsulated_key" => {
+                            enc.is_none().ok_or( de::Error::duplicate_field( "encapsulated_key"))?;
+                            enc = Some( map.next_value()?);
+                        }
+                        "algorithm" => {
+                            algorithm.is_none().ok_or( de::Error::duplicate_field( "algorithm"))?;
+                            algorithm = Some( map.next_value()?);
+                        }
+                        k => {
+                            // Skip unknown fields, unless deserializing strictly.
+                            (!is_strict()).ok_or( de::Error::unknown_field( k, FIELDS))?;
+                            map.next_value::<de::IgnoredAny>()?;
+                        }
+                    }
+                }
+
+                let enc : String = enc.ok_or_else(|| de::Error::missing_field( "encapsulated_key"))?;
+                let algorithm : String = algorithm.ok_or_else(|| de::Error::missing_field( "algorithm"))?;
+
+                let algorithm = AlgorithmId::from_algorithm_id( &algorithm).ok_or( de::Error::custom( "invalid algorithm identifier"))?;
+                let enc = deserialize_base64url( &enc).map_err( de::Error::custom)?;
+                let enc = DecodePSF::decode_psf( &AEAlgorithm::AEX25519, &enc).map_err( de::Error::custom)?;
+
+                Ok( EncapsulatedKey{ algorithm : algorithm, enc : enc})
+            }
+        }
+
+        deserializer.deserialize_struct( "EncapsulatedKey", FIELDS, V)
+    }
+}
+
+impl EncapsulatedKey {
+    // CBOR map of the key, so that other structures can nest it.
+    pub(crate) fn to_cbor_value( &self) -> CborValue {
+        CborValue::Map( vec![
+            (CborValue::Text( "encapsulated_key".to_string()), CborValue::Bytes( EncodePSF::encode_psf( &self.enc))),
+            (CborValue::Text( "algorithm".to_string()), CborValue::Text( AlgorithmId::to_algorithm_id( &self.algorithm).to_string())),
+        ])
+    }
+
+    pub(crate) fn from_cbor_value( v : &CborValue) -> Result<EncapsulatedKey, &'static str> {
+        let algorithm = v.field( "algorithm")?.as_text().ok_or( "Invalid algorithm.")?;
+        let algorithm = AlgorithmId::from_algorithm_id( algorithm).ok_or( "Invalid algorithm identifier.")?;
+        let enc = v.field( "encapsulated_key")?.as_bytes().ok_or( "Invalid encapsulated key.")?;
+        let enc = DecodePSF::decode_psf( &AEAlgorithm::AEX25519, &enc.to_vec())?;
+
+        Ok( EncapsulatedKey{ algorithm : algorithm, enc : enc})
+    }
+}
+
+impl EncodeCbor for EncapsulatedKey {
+    fn encode_cbor( key : &EncapsulatedKey) -> Vec<u8> {
+        cbor::encode( &key.to_cbor_value())
+    }
+}
+
+impl DecodeCbor for EncapsulatedKey {
+    fn decode_cbor( bs : &[u8]) -> Result<EncapsulatedKey, &'static str> {
+        EncapsulatedKey::from_cbor_value( &cbor::decode( bs)?)
+    }
+}
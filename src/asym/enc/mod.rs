@@ -1,6 +1,31 @@
 
 // use crypto_abstract::asym::enc;
 pub use crypto_abstract::asym::enc::{Algorithm, PublicKey, PrivateKey, gen};
+
+/// age file encryption (v1).
+pub mod age;
+
+/// Hybrid public key encryption (RFC9180).
+pub mod hpke;
+
+/// libsodium compatible boxes.
+pub mod sodium;
+
+use boolinator::Boolinator;
+use crypto_abstract::ToAlgorithm;
+use ring::digest::SHA256;
+use ring::hkdf;
+use ring::hmac::SigningKey;
+use ring::rand::SystemRandom;
+
+use asym::auth as aa;
+use derive::MasterSecret;
+use internal;
+use internal::{AlgorithmId, EncodePSF, ToIdentifier};
+use internal::mnemonic;
+use shamir;
+use shamir::Share;
+use sym::enc as se;
 // 
 // use internal::{PKAIdentifier};
 // use sym::enc as se;
@@ -17,3 +42,67 @@ pub struct PKAAsymEncrypted {
     ciphertext : se::PKASymEncrypted
 }
 */
+
+/// Deterministically generate a private key from a 32 byte seed.
+pub fn gen_from_seed( alg : &Algorithm, seed : &[u8]) -> Result<PrivateKey, &'static str> {
+    internal::asym::enc::private_key_from_seed( alg, seed)
+}
+
+/// Deterministically derive a private key from a master secret.
+pub fn gen_from_master( master : &MasterSecret, alg : &Algorithm) -> Result<PrivateKey, &'static str> {
+    gen_from_seed( alg, &master.seed( AlgorithmId::to_algorithm_id( alg)))
+}
+
+/// Back up a private key's seed as a mnemonic phrase.
+pub fn to_mnemonic( key : &PrivateKey) -> Result<String, &'static str> {
+    let seed = EncodePSF::encode_psf( key);
+    mnemonic::encode_mnemonic( &ToAlgorithm::to_algorithm( key), &seed)
+}
+
+/// Restore a private key from a mnemonic phrase.
+pub fn from_mnemonic( phrase : &str) -> Result<PrivateKey, &'static str> {
+    let (alg, seed) = mnemonic::decode_mnemonic( phrase)?;
+    gen_from_seed( &alg, &seed)
+}
+
+/// Split a private key into `count` shares, any `threshold` of which recombine to it.
+pub fn split_shares( rng : &SystemRandom, key : &PrivateKey, threshold : u8, count : u8) -> Result<Vec<Share>, &'static str> {
+    let seed = EncodePSF::encode_psf( key);
+    shamir::split( rng, &ToAlgorithm::to_algorithm( key), ToIdentifier::to_identifier( key), &seed, threshold, count)
+}
+
+/// Recombine shares to a private key, verifying its identifier.
+pub fn combine_shares( shares : &[Share]) -> Result<PrivateKey, &'static str> {
+    let (alg, identifier, seed) = shamir::combine( shares)?;
+    let key = gen_from_seed( &alg, &seed)?;
+    identifier.identifies( &key).ok_or( "Reconstructed key does not match its identifier.")?;
+
+    Ok( key)
+}
+
+/// Convert an `aa-ed25519` private key to an `ae-x25519` private key, so that one identity key can both sign and decrypt.
+pub fn from_auth_private_key( key : &aa::PrivateKey) -> Result<PrivateKey, &'static str> {
+    internal::asym::enc::private_key_from_ed25519( key)
+}
+
+/// Convert an `aa-ed25519` public key to the `ae-x25519` public key of the converted private key.
+pub fn from_auth_public_key( key : &aa::PublicKey) -> Result<PublicKey, &'static str> {
+    internal::asym::enc::public_key_from_ed25519( key)
+}
+
+/// Salt and info of the HKDF-SHA256 step of key agreement.
+pub struct KdfContext<'a> {
+    pub salt : &'a [u8],
+    pub info : &'a [u8],
+}
+
+/// Agree on a symmetric key with a peer. The X25519 shared secret is passed through HKDF-SHA256 with the context's salt and info.
+pub fn agree( key : &PrivateKey, peer : &PublicKey, context : &KdfContext) -> Result<se::Key, &'static str> {
+    let shared = internal::asym::enc::agree_x25519( key, peer)?;
+
+    let salt = SigningKey::new( &SHA256, context.salt);
+    let mut seed = [0u8; 32];
+    hkdf::extract_and_expand( &salt, &shared, context.info, &mut seed);
+
+    se::gen_from_seed( &se::Algorithm::SEAesGcm256, &seed)
+}
@@ -0,0 +1,113 @@
+// libsodium compatible crypto_box (X25519, XSalsa20-Poly1305) and sealed boxes: https://doc.libsodium.org/public-key_cryptography
+
+use blake2::Blake2bVar;
+use blake2::digest::{Update, VariableOutput};
+use boolinator::Boolinator;
+use crypto_abstract::{ToAlgorithm, ToPublicKey};
+use crypto_box::SalsaBox;
+use crypto_box::aead::Aead;
+use crypto_box::aead::generic_array::GenericArray;
+use ring::rand::{SecureRandom, SystemRandom};
+
+use asym::enc::{Algorithm, PrivateKey, PublicKey, gen};
+use internal::asym::enc::agree_x25519;
+use internal::{EncodePSF, u8_to_fixed_length_32};
+use metadata::{Clock, PKAKey, Usage};
+use policy::{Operation, Policy};
+
+/// Length of crypto_box nonces.
+pub const NONCELENGTH : usize = 24;
+
+const PUBLICKEYLENGTH : usize = 32;
+
+/// Generate a random crypto_box nonce.
+pub fn gen_nonce( rng : &SystemRandom) -> Result<[u8; NONCELENGTH], &'static str> {
+    let mut nonce = [0u8; NONCELENGTH];
+    rng.fill( &mut nonce).map_err(|_| "Error generating nonce.")?;
+    Ok( nonce)
+}
+
+/// Encrypt and authenticate a message from the sender to the recipient, like `crypto_box_easy`. The output is the tag followed by the ciphertext.
+pub fn box_encrypt( sender : &PrivateKey, recipient : &PublicKey, nonce : &[u8], message : &[u8]) -> Result<Vec<u8>, &'static str> {
+    (nonce.len() == NONCELENGTH).ok_or( "Nonce is wrong length.")?;
+
+    let b = salsa_box( sender, recipient)?;
+    b.encrypt( GenericArray::from_slice( nonce), message).map_err(|_| "Error encrypting content.")
+}
+
+/// Verify and decrypt a message from the sender, like `crypto_box_open_easy`.
+pub fn box_decrypt( recipient : &PrivateKey, sender : &PublicKey, nonce : &[u8], ciphertext : &[u8]) -> Result<Vec<u8>, &'static str> {
+    (nonce.len() == NONCELENGTH).ok_or( "Nonce is wrong length.")?;
+
+    let b = salsa_box( recipient, sender)?;
+    b.decrypt( GenericArray::from_slice( nonce), ciphertext).map_err(|_| "Could not decrypt ciphertext.")
+}
+
+/// Anonymously encrypt a message to the recipient, like `crypto_box_seal`. The output is the ephemeral public key followed by the boxed message.
+pub fn seal( rng : &SystemRandom, recipient : &PublicKey, message : &[u8]) -> Result<Vec<u8>, &'static str> {
+    let ephemeral = gen( rng, &Algorithm::AEX25519)?;
+    let ephemeral_pk = EncodePSF::encode_psf( &ToPublicKey::to_public_key( &ephemeral));
+    let nonce = seal_nonce( &ephemeral_pk, recipient)?;
+
+    let mut sealed = ephemeral_pk;
+    sealed.extend( box_encrypt( &ephemeral, recipient, &nonce, message)?);
+    Ok( sealed)
+}
+
+/// Decrypt an anonymously sealed message, like `crypto_box_seal_open`.
+pub fn seal_open( recipient : &PrivateKey, sealed : &[u8]) -> Result<Vec<u8>, &'static str> {
+    (sealed.len() >= PUBLICKEYLENGTH).ok_or( "Sealed box is too short.")?;
+
+    let (ephemeral_pk, ciphertext) = sealed.split_at( PUBLICKEYLENGTH);
+    let recipient_pk = ToPublicKey::to_public_key( recipient);
+    let nonce = seal_nonce( ephemeral_pk, &recipient_pk)?;
+    let ephemeral_pk = u8_to_fixed_length_32( ephemeral_pk).ok_or( "Invalid ephemeral public key.")?;
+
+    box_decrypt( recipient, &PublicKey::AEX25519( ephemeral_pk), &nonce, ciphertext)
+}
+
+/// Verify and decrypt a message from the sender, rejecting keys the policy does not allow for decryption.
+pub fn box_decrypt_with_policy( policy : &Policy, recipient : &PrivateKey, sender : &PublicKey, nonce : &[u8], ciphertext : &[u8]) -> Result<Vec<u8>, &'static str> {
+    policy.check( Operation::Decrypt, &ToAlgorithm::to_algorithm( recipient))?;
+    policy.check( Operation::Decrypt, &ToAlgorithm::to_algorithm( sender))?;
+    box_decrypt( recipient, sender, nonce, ciphertext)
+}
+
+/// Decrypt an anonymously sealed message, rejecting keys the policy does not allow for decryption.
+pub fn seal_open_with_policy( policy : &Policy, recipient : &PrivateKey, sealed : &[u8]) -> Result<Vec<u8>, &'static str> {
+    policy.check( Operation::Decrypt, &ToAlgorithm::to_algorithm( recipient))?;
+    seal_open( recipient, sealed)
+}
+
+/// Verify and decrypt a message from the sender, rejecting recipient keys that are expired at the clock's time or are not for encryption.
+pub fn box_decrypt_with_metadata<C>( clock : &C, recipient : &PKAKey<PrivateKey>, sender : &PublicKey, nonce : &[u8], ciphertext : &[u8]) -> Result<Vec<u8>, &'static str> where C : Clock {
+    recipient.metadata.check( clock, Usage::Encrypt)?;
+    box_decrypt( &recipient.key, sender, nonce, ciphertext)
+}
+
+/// Decrypt an anonymously sealed message, rejecting recipient keys that are expired at the clock's time or are not for encryption.
+pub fn seal_open_with_metadata<C>( clock : &C, recipient : &PKAKey<PrivateKey>, sealed : &[u8]) -> Result<Vec<u8>, &'static str> where C : Clock {
+    recipient.metadata.check( clock, Usage::Encrypt)?;
+    seal_open( &recipient.key, sealed)
+}
+
+// Sealed boxes use BLAKE2b(ephemeral public key || recipient public key) as the nonce.
+fn seal_nonce( ephemeral_pk : &[u8], recipient : &PublicKey) -> Result<[u8; NONCELENGTH], &'static str> {
+    let mut h = Blake2bVar::new( NONCELENGTH).map_err(|_| "Invalid nonce length.")?;
+    h.update( ephemeral_pk);
+    h.update( &EncodePSF::encode_psf( recipient));
+
+    let mut nonce = [0u8; NONCELENGTH];
+    h.finalize_variable( &mut nonce).map_err(|_| "Invalid nonce length.")?;
+    Ok( nonce)
+}
+
+fn salsa_box( key : &PrivateKey, peer : &PublicKey) -> Result<SalsaBox, &'static str> {
+    // Like libsodium, reject peers whose shared secret is all zeros.
+    agree_x25519( key, peer)?;
+
+    let secret = u8_to_fixed_length_32( &EncodePSF::encode_psf( key)).ok_or( "Private key is wrong length.")?;
+    let public = u8_to_fixed_length_32( &EncodePSF::encode_psf( peer)).ok_or( "Public key is wrong length.")?;
+
+    Ok( SalsaBox::new( &crypto_box::PublicKey::from( public), &crypto_box::SecretKey::from( secret)))
+}
@@ -0,0 +1,233 @@
+// Public key certificates. An issuer signs a subject's public key, its validity period, and its usage, and chains of certificates are validated from trusted roots.
+
+use boolinator::Boolinator;
+use serde::de;
+use serde::de::{Deserialize, Deserializer, MapAccess, Visitor};
+use serde::ser::{Serialize, Serializer, SerializeStruct};
+use serde_json;
+use serde_json::{Map, Value};
+use std::fmt;
+
+use asym::auth as aa;
+use asym::enc as ae;
+use crypto_abstract::ToPublicKey;
+use internal::{AlgorithmId, PKAIdentifier, PKAJ, ToIdentifier, ToVersionedIdentifier, is_strict};
+use metadata::{Clock, Usage};
+use revocation::RevocationSet;
+
+/// Public key certified by a certificate.
+pub enum SubjectKey {
+    Auth( aa::PublicKey),
+    Enc( ae::PublicKey),
+}
+
+impl ToIdentifier for SubjectKey {
+    fn to_identifier( key : &SubjectKey) -> PKAIdentifier {
+        match *key {
+            SubjectKey::Auth( ref key) => ToIdentifier::to_identifier( key),
+            SubjectKey::Enc( ref key) => ToIdentifier::to_identifier( key),
+        }
+    }
+}
+
+impl ToVersionedIdentifier for SubjectKey {
+    fn to_versioned_identifier( key : &SubjectKey) -> PKAIdentifier {
+        match *key {
+            SubjectKey::Auth( ref key) => ToVersionedIdentifier::to_versioned_identifier( key),
+            SubjectKey::Enc( ref key) => ToVersionedIdentifier::to_versioned_identifier( key),
+        }
+    }
+}
+
+impl Serialize for SubjectKey {
+    fn serialize<S>( &self, serializer : S) -> Result<S::Ok, S::Error> where S : Serializer {
+        match *self {
+            SubjectKey::Auth( ref key) => PKAJ{ pkaj : key}.serialize( serializer),
+            SubjectKey::Enc( ref key) => PKAJ{ pkaj : key}.serialize( serializer),
+        }
+    }
+}
+
+impl<'d> Deserialize<'d> for SubjectKey {
+    fn deserialize<D>( deserializer : D) -> Result<SubjectKey, D::Error> where D : Deserializer<'d> {
+        let v = Value::deserialize( deserializer)?;
+        let algorithm = v.get( "algorithm").and_then( Value::as_str).ok_or( de::Error::missing_field( "algorithm"))?.to_string();
+
+        if <aa::Algorithm as AlgorithmId>::from_algorithm_id( &algorithm).is_some() {
+            let key : PKAJ<aa::PublicKey> = serde_json::from_value( v).map_err( de::Error::custom)?;
+            Ok( SubjectKey::Auth( key.pkaj))
+        }
+        else if <ae::Algorithm as AlgorithmId>::from_algorithm_id( &algorithm).is_some() {
+            let key : PKAJ<ae::PublicKey> = serde_json::from_value( v).map_err( de::Error::custom)?;
+            Ok( SubjectKey::Enc( key.pkaj))
+        }
+        else {
+            Err( de::Error::custom( "Unknown subject key algorithm."))
+        }
+    }
+}
+
+/// Content of a certificate. Times are seconds since the Unix epoch, and the certificate is valid from `not_before` until, but excluding, `not_after`.
+pub struct Certificate {
+    pub subject_key : SubjectKey,
+    pub subject : PKAIdentifier,
+    pub issuer : PKAIdentifier,
+    pub not_before : u64,
+    pub not_after : u64,
+    pub usage : Vec<Usage>,
+    pub extensions : Map<String, Value>,
+}
+
+impl Certificate {
+    pub fn new( subject_key : SubjectKey, issuer : &aa::PublicKey, not_before : u64, not_after : u64, usage : Vec<Usage>) -> Certificate {
+        let subject = ToIdentifier::to_identifier( &subject_key);
+        Certificate{ subject_key : subject_key, subject : subject, issuer : ToIdentifier::to_identifier( issuer), not_before : not_before, not_after : not_after, usage : usage, extensions : Map::new()}
+    }
+
+    /// Check that the certificate is valid at the clock's current time and allows `usage`.
+    pub fn check<C>( &self, clock : &C, usage : Usage) -> Result<(), &'static str> where C : Clock {
+        let now = clock.now();
+        (self.not_before <= now).ok_or( "Certificate is not valid yet.")?;
+        (now < self.not_after).ok_or( "Certificate has expired.")?;
+        self.usage.contains( &usage).ok_or( "Certificate usage does not allow this operation.")?;
+
+        Ok(())
+    }
+}
+
+impl Serialize for Certificate {
+    fn serialize<S>( &self, serializer : S) -> Result<S::Ok, S::Error> where S : Serializer {
+        let mut s = serializer.serialize_struct( "Certificate", 7)?;
+
+        s.serialize_field( "subject_key", &self.subject_key)?;
+        s.serialize_field( "subject", &self.subject)?;
+        s.serialize_field( "issuer", &self.issuer)?;
+        s.serialize_field( "not_before", &self.not_before)?;
+        s.serialize_field( "not_after", &self.not_after)?;
+        s.serialize_field( "usage", &self.usage)?;
+        s.serialize_field( "extensions", &self.extensions)?;
+
+        s.end()
+    }
+}
+
+impl<'d> Deserialize<'d> for Certificate {
+    fn deserialize<D>( deserializer : D) -> Result<Certificate, D::Error> where D : Deserializer<'d> {
+        struct V;
+
+        const FIELDS : &'static [&'static str] = &["subject_key", "subject", "issuer", "not_before", "not_after", "usage", "extensions"];
+
+        impl<'d> Visitor<'d> for V {
+            type Value = Certificate;
+
+            fn expecting( &self, formatter : &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str( "JSON Certificate")
+            }
+
+            fn visit_map<U>( self, mut map : U) -> Result<Certificate, U::Error> where U : MapAccess<'d> {
+                let mut subject_key = None;
+                let mut subject = None;
+                let mut issuer = None;
+                let mut not_before = None;
+                let mut not_after = None;
+                let mut usage = None;
+                let mut extensions = None;
+
+                while let Some(k) = map.next_key::<String>()? {
+                    match k.as_str() {
+                        "subject_key" => {
+                            subject_key.is_none().ok_or( de::Error::duplicate_field( "subject_key"))?;
+                            subject_key = Some( map.next_value()?);
+                        }
+                        "subject" => {
+                            subject.is_none().ok_or( de::Error::duplicate_field( "subject"))?;
+                            subject = Some( map.next_value()?);
+                        }
+                        "issuer" => {
+                            issuer.is_none().ok_or( de::Error::duplicate_field( "issuer"))?;
+                            issuer = Some( map.next_value()?);
+                        }
+                        "not_before" => {
+                            not_before.is_none().ok_or( de::Error::duplicate_field( "not_before"))?;
+                            not_before = Some( map.next_value()?);
+                        }
+                        "not_after" => {
+                            not_after.is_none().ok_or( de::Error::duplicate_field( "not_after"))?;
+                            not_after = Some( map.next_value()?);
+                        }
+                        "usage" => {
+                            usage.is_none().ok_or( de::Error::duplicate_field( "usage"))?;
+                            usage = Some( map.next_value()?);
+                        }
+                        "extensions" => {
+                            extensions.is_none().ok_or( de::Error::duplicate_field( "extensions"))?;
+                            extensions = Some( map.next_value()?);
+                        }
+                        k => {
+                            // Skip unknown fields, unless deserializing strictly.
+                            (!is_strict()).ok_or( de::Error::unknown_field( k, FIELDS))?;
+                            map.next_value::<de::IgnoredAny>()?;
+                        }
+                    }
+                }
+
+                let subject_key : SubjectKey = subject_key.ok_or_else(|| de::Error::missing_field( "subject_key"))?;
+                let subject : PKAIdentifier = subject.ok_or_else(|| de::Error::missing_field( "subject"))?;
+                let issuer = issuer.ok_or_else(|| de::Error::missing_field( "issuer"))?;
+                let not_before : u64 = not_before.ok_or_else(|| de::Error::missing_field( "not_before"))?;
+                let not_after : u64 = not_after.ok_or_else(|| de::Error::missing_field( "not_after"))?;
+                let usage = usage.ok_or_else(|| de::Error::missing_field( "usage"))?;
+                let extensions = extensions.unwrap_or_else( Map::new);
+
+                (subject == ToIdentifier::to_identifier( &subject_key)).ok_or( de::Error::custom( "Subject identifier does not match subject key."))?;
+                (not_before < not_after).ok_or( de::Error::custom( "Certificate expires before it is valid."))?;
+
+                Ok( Certificate{ subject_key : subject_key, subject : subject, issuer : issuer, not_before : not_before, not_after : not_after, usage : usage, extensions : extensions})
+            }
+        }
+
+        deserializer.deserialize_struct( "Certificate", FIELDS, V)
+    }
+}
+
+/// Sign a certificate with the issuer's key.
+pub fn issue( issuer : &aa::PrivateKey, certificate : &Certificate) -> Result<aa::PKASigned, &'static str> {
+    (certificate.issuer == ToIdentifier::to_identifier( &ToPublicKey::to_public_key( issuer))).ok_or( "Issuer identifier does not match issuer key.")?;
+    aa::sign( issuer, certificate)
+}
+
+/// Verify a certificate's signature by the issuer's key. This does not check its validity period or usage.
+pub fn verify( issuer : &aa::PublicKey, signed : aa::PKASigned) -> Result<Certificate, &'static str> {
+    let certificate : Certificate = aa::verify( issuer, signed)?;
+    (certificate.issuer == ToIdentifier::to_identifier( issuer)).ok_or( "Issuer identifier does not match issuer key.")?;
+
+    Ok( certificate)
+}
+
+/// Validate a chain of certificates, ordered from the subject's certificate to one issued by a trusted root.
+/// Every certificate must be valid at the clock's time, issuers must be certified for `Usage::Certify`, and the subject's certificate must allow `usage`.
+pub fn validate_chain<C>( clock : &C, roots : &[aa::PublicKey], chain : Vec<aa::PKASigned>, usage : Usage) -> Result<Certificate, &'static str> where C : Clock {
+    validate_chain_with_revocations( clock, &RevocationSet::new(), roots, chain, usage)
+}
+
+/// Validate a chain of certificates, also rejecting it if any of its keys, including the root's, have been revoked. Each certificate's subject is checked against its issuer's revocation list.
+pub fn validate_chain_with_revocations<C>( clock : &C, revocations : &RevocationSet, roots : &[aa::PublicKey], chain : Vec<aa::PKASigned>, usage : Usage) -> Result<Certificate, &'static str> where C : Clock {
+    let mut chain = chain.into_iter().rev();
+    let top = chain.next().ok_or( "Empty certificate chain.")?;
+    let mut certificate = roots.iter().filter_map(|root| verify( root, top.clone()).ok()).next().ok_or( "Certificate chain is not issued by a trusted root.")?;
+    revocations.check( &certificate.issuer)?;
+
+    for signed in chain {
+        revocations.check_issued( &certificate.issuer, &certificate.subject)?;
+        certificate.check( clock, Usage::Certify)?;
+        let next = match certificate.subject_key {
+            SubjectKey::Auth( ref issuer) => verify( issuer, signed)?,
+            SubjectKey::Enc( _) => return Err( "Certificate issuer is not a signing key."),
+        };
+        certificate = next;
+    }
+
+    revocations.check_issued( &certificate.issuer, &certificate.subject)?;
+    certificate.check( clock, usage)?;
+    Ok( certificate)
+}
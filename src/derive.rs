@@ -0,0 +1,73 @@
+use ring::digest::SHA256;
+use ring::hkdf;
+use ring::hmac::SigningKey;
+use ring::rand::{SecureRandom, SystemRandom};
+
+use internal::u8_to_fixed_length_32;
+
+const SALT : &'static [u8] = b"PKAuth key derivation v1";
+const CHILDLABEL : &'static [u8] = b"child";
+const SEEDLABEL : &'static [u8] = b"seed";
+
+/// Length of master secrets and derived seeds.
+pub const SEEDLENGTH : usize = 32;
+
+/// Master secret from which PKAuth keys can be deterministically regenerated.
+///
+/// Children are derived with HKDF-SHA256 using context labels (ex, a tenant or a purpose), so a single backed up master secret covers every key derived from it.
+pub struct MasterSecret {
+    secret : [u8; SEEDLENGTH]
+}
+
+impl MasterSecret {
+    /// Generate a random master secret.
+    pub fn gen( rng : &SystemRandom) -> Result<MasterSecret, &'static str> {
+        let mut secret = [0u8; SEEDLENGTH];
+        rng.fill( &mut secret).map_err(|_| "Error generating master secret.")?;
+
+        Ok( MasterSecret{ secret : secret})
+    }
+
+    /// Load a master secret from its raw bytes.
+    pub fn from_bytes( bytes : &[u8]) -> Result<MasterSecret, &'static str> {
+        let secret = u8_to_fixed_length_32( bytes).ok_or( "Master secret is wrong length.")?;
+
+        Ok( MasterSecret{ secret : secret})
+    }
+
+    pub fn as_bytes( &self) -> &[u8] {
+        &self.secret
+    }
+
+    /// Derive the child master secret for a context label.
+    pub fn child( &self, label : &str) -> MasterSecret {
+        MasterSecret{ secret : expand( &self.secret, CHILDLABEL, label.as_bytes())}
+    }
+
+    /// Derive the master secret at the end of a path of context labels (ex, `&["tenant-42", "signing"]`).
+    pub fn derive_path( &self, labels : &[&str]) -> MasterSecret {
+        let mut secret = MasterSecret{ secret : self.secret};
+        for label in labels {
+            secret = secret.child( label);
+        }
+        secret
+    }
+
+    /// Derive the seed of a key for the given algorithm identifier (ex, `"aa-ed25519"`).
+    pub fn seed( &self, algorithm_id : &str) -> [u8; SEEDLENGTH] {
+        expand( &self.secret, SEEDLABEL, algorithm_id.as_bytes())
+    }
+}
+
+// Labels are prefixed with their kind and a separator so that children and seeds never collide.
+fn expand( secret : &[u8; SEEDLENGTH], kind : &[u8], label : &[u8]) -> [u8; SEEDLENGTH] {
+    let mut info = Vec::with_capacity( kind.len() + 1 + label.len());
+    info.extend( kind);
+    info.push( 0);
+    info.extend( label);
+
+    let salt = SigningKey::new( &SHA256, SALT);
+    let mut out = [0u8; SEEDLENGTH];
+    hkdf::extract_and_expand( &salt, secret, &info, &mut out);
+    out
+}
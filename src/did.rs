@@ -0,0 +1,79 @@
+// did:key identifiers following https://w3c-ccg.github.io/did-method-key/
+
+use boolinator::Boolinator;
+use asym::auth as aa;
+use asym::enc as ae;
+use serde_json::Value;
+
+use encoding::{DecodeMultibase, EncodeMultibase};
+
+const PREFIX : &'static str = "did:key:";
+
+/// Convert a public key to its `did:key` identifier.
+pub fn to_did_key<K>( key : &K) -> String where K : EncodeMultibase {
+    let mut did = PREFIX.to_owned();
+    did.push_str( &EncodeMultibase::encode_multibase( key));
+    did
+}
+
+/// Recover the public key from a `did:key` identifier, ignoring any fragment.
+pub fn from_did_key<K>( did : &str) -> Result<K, &'static str> where K : DecodeMultibase {
+    (did.starts_with( PREFIX)).ok_or( "Not a did:key identifier.")?;
+
+    let fingerprint = did[PREFIX.len() ..].split( '#').next().unwrap_or( "");
+    DecodeMultibase::decode_multibase( fingerprint)
+}
+
+/// DID document of an Ed25519 `did:key`. Its key agreement key is the X25519 key converted from the Ed25519 key, as resolvers derive it.
+pub fn document( key : &aa::PublicKey) -> Result<Value, &'static str> {
+    let fingerprint = EncodeMultibase::encode_multibase( key);
+    let did = format!( "{}{}", PREFIX, fingerprint);
+    let id = format!( "{}#{}", did, fingerprint);
+
+    let key_agreement = EncodeMultibase::encode_multibase( &ae::from_auth_public_key( key)?);
+    let key_agreement_id = format!( "{}#{}", did, key_agreement);
+
+    Ok( json!({
+        "@context": [
+            "https://www.w3.org/ns/did/v1",
+            "https://w3id.org/security/suites/ed25519-2020/v1",
+            "https://w3id.org/security/suites/x25519-2020/v1"
+        ],
+        "id": did,
+        "verificationMethod": [
+            verification_method( &did, &id, "Ed25519VerificationKey2020", &fingerprint),
+            verification_method( &did, &key_agreement_id, "X25519KeyAgreementKey2020", &key_agreement),
+        ],
+        "authentication": [ id],
+        "assertionMethod": [ id],
+        "capabilityInvocation": [ id],
+        "capabilityDelegation": [ id],
+        "keyAgreement": [ key_agreement_id],
+    }))
+}
+
+/// DID document of an X25519 `did:key`, which can only be used for key agreement.
+pub fn key_agreement_document( key : &ae::PublicKey) -> Value {
+    let fingerprint = EncodeMultibase::encode_multibase( key);
+    let did = format!( "{}{}", PREFIX, fingerprint);
+    let id = format!( "{}#{}", did, fingerprint);
+
+    json!({
+        "@context": [
+            "https://www.w3.org/ns/did/v1",
+            "https://w3id.org/security/suites/x25519-2020/v1"
+        ],
+        "id": did,
+        "verificationMethod": [ verification_method( &did, &id, "X25519KeyAgreementKey2020", &fingerprint)],
+        "keyAgreement": [ id],
+    })
+}
+
+fn verification_method( did : &str, id : &str, typ : &str, fingerprint : &str) -> Value {
+    json!({
+        "id": id,
+        "type": typ,
+        "controller": did,
+        "publicKeyMultibase": fingerprint,
+    })
+}
@@ -0,0 +1,240 @@
+// ASCII armor in the style of OpenPGP (RFC 4880, section 6): https://www.rfc-editor.org/rfc/rfc4880#section-6
+// The body is the binary wire format of the object, followed by a CRC-24 checksum. Parsing ignores text around the armor and whitespace damage within it, unless it is strict.
+
+use base64;
+use boolinator::Boolinator;
+use crypto_abstract::ToAlgorithm;
+use crypto_abstract::asym::auth as aa;
+use crypto_abstract::asym::enc as ae;
+use crypto_abstract::sym::enc as se;
+
+use asym::auth::PKASigned;
+use encoding::{DecodeArmor, DecodeBinary, EncodeArmor, EncodeBinary};
+use internal::{AlgorithmId, PKAIdentifier, ToIdentifier, is_strict, strict};
+use sym::enc::PKASymEncrypted;
+
+const COLUMNS : usize = 64;
+const CRC24INIT : u32 = 0xb704ce;
+const CRC24POLY : u32 = 0x1864cfb;
+
+const HEADERTYPE : &'static str = "Type";
+const HEADERALGORITHM : &'static str = "Algorithm";
+const HEADERIDENTIFIER : &'static str = "Identifier";
+
+/// Armor data with a label, ex `PKAUTH PUBLIC KEY`, and headers.
+pub fn encode( label : &str, headers : &[(&str, String)], data : &[u8]) -> String {
+    let mut out = format!( "-----BEGIN {}-----\n", label);
+    for &(k, ref v) in headers {
+        out.push_str( &format!( "{}: {}\n", k, v));
+    }
+    out.push( '\n');
+
+    let encoded = base64::encode_config( data, base64::STANDARD);
+    for line in encoded.as_bytes().chunks( COLUMNS) {
+        out.push_str( &String::from_utf8_lossy( line));
+        out.push( '\n');
+    }
+
+    let crc = crc24( data);
+    out.push( '=');
+    out.push_str( &base64::encode_config( &[ (crc >> 16) as u8, (crc >> 8) as u8, crc as u8], base64::STANDARD));
+    out.push( '\n');
+
+    out.push_str( &format!( "-----END {}-----\n", label));
+    out
+}
+
+/// Find the first armor with the given label in some text. Returns its headers and data.
+/// When deserializing strictly, the text must be the armor alone, without text around it or whitespace within its lines.
+pub fn decode( label : &str, s : &str) -> Result<(Vec<(String, String)>, Vec<u8>), &'static str> {
+    let begin = format!( "-----BEGIN {}-----", label);
+    let end = format!( "-----END {}-----", label);
+
+    if is_strict() {
+        let s = s.trim();
+        (s.starts_with( &begin) && s.ends_with( &end)).ok_or( "Text around the armor.")?;
+        s.lines().all(|l| l.trim() == l).ok_or( "Whitespace in the armor.")?;
+    }
+
+    let mut lines = s.lines().map( str::trim).skip_while(|l| *l != begin);
+    lines.next().ok_or( "Missing armor header.")?;
+
+    let mut headers = Vec::new();
+    let mut encoded = String::new();
+    let mut ended = false;
+    for line in lines {
+        if line == end {
+            ended = true;
+            break
+        }
+
+        // Base64 never contains a colon.
+        match line.find( ':') {
+            Some( i) if encoded.is_empty() => headers.push( (line[.. i].trim().to_string(), line[i + 1 ..].trim().to_string())),
+            Some( _) => return Err( "Armor header after the body."),
+            None => encoded.extend( line.chars().filter(|c| !c.is_whitespace())),
+        }
+    }
+    ended.ok_or( "Missing armor footer.")?;
+
+    // The body's length is a multiple of 4, so its checksum is the trailing `=` and 4 characters even if the lines were joined.
+    let split = encoded.len().checked_sub( 5).ok_or( "Missing armor checksum.")?;
+    (encoded.len() % 4 == 1 && encoded.is_char_boundary( split) && encoded[split ..].starts_with( '=')).ok_or( "Missing armor checksum.")?;
+    let data = base64::decode_config( &encoded[.. split], base64::STANDARD).map_err(|_| "Invalid armor encoding.")?;
+    let crc = base64::decode_config( &encoded[split + 1 ..], base64::STANDARD).map_err(|_| "Invalid armor checksum.")?;
+
+    let expected = crc24( &data);
+    (crc == [ (expected >> 16) as u8, (expected >> 8) as u8, expected as u8]).ok_or( "Armor checksum does not match.")?;
+
+    Ok( (headers, data))
+}
+
+fn crc24( data : &[u8]) -> u32 {
+    let mut crc = CRC24INIT;
+    for b in data {
+        crc ^= (*b as u32) << 16;
+        for _ in 0 .. 8 {
+            crc <<= 1;
+            if crc & 0x1000000 != 0 {
+                crc ^= CRC24POLY;
+            }
+        }
+    }
+    crc & 0xffffff
+}
+
+/// Decode an armored object strictly, rejecting text around the armor and unknown headers.
+pub fn decode_armor_strict<T>( s : &str) -> Result<T, &'static str> where T : DecodeArmor {
+    strict(|| DecodeArmor::decode_armor( s))
+}
+
+// Headers are informational, but must match the object if present.
+fn check_header( headers : &[(String, String)], name : &str, expected : &str) -> Result<(), &'static str> {
+    for &(ref k, ref v) in headers {
+        if k.eq_ignore_ascii_case( name) {
+            (v == expected).ok_or( "Armor header does not match its content.")?;
+        }
+    }
+    Ok(())
+}
+
+fn encode_object<T>( label : &str, typ : &str, o : &T, identifier : Option<PKAIdentifier>) -> String where T : EncodeBinary + ToAlgorithm, T::Algorithm : AlgorithmId {
+    let mut headers = vec![
+        (HEADERTYPE, typ.to_string()),
+        (HEADERALGORITHM, AlgorithmId::to_algorithm_id( &ToAlgorithm::to_algorithm( o)).to_string()),
+    ];
+    if let Some( identifier) = identifier {
+        headers.push( (HEADERIDENTIFIER, identifier.to_string()));
+    }
+
+    encode( &format!( "PKAUTH {}", label), &headers, &EncodeBinary::encode_binary( o))
+}
+
+fn decode_object<T>( label : &str, typ : &str, s : &str, identifier : fn( &T) -> Option<PKAIdentifier>) -> Result<T, &'static str> where T : DecodeBinary + ToAlgorithm, T::Algorithm : AlgorithmId {
+    let (headers, data) = decode( &format!( "PKAUTH {}", label), s)?;
+    let o : T = DecodeBinary::decode_binary( &data)?;
+
+    if is_strict() {
+        headers.iter().all(|&(ref k, _)| [ HEADERTYPE, HEADERALGORITHM, HEADERIDENTIFIER].iter().any(|h| k.eq_ignore_ascii_case( h))).ok_or( "Unknown armor header.")?;
+    }
+
+    check_header( &headers, HEADERTYPE, typ)?;
+    check_header( &headers, HEADERALGORITHM, AlgorithmId::to_algorithm_id( &ToAlgorithm::to_algorithm( &o)))?;
+    if let Some( identifier) = identifier( &o) {
+        check_header( &headers, HEADERIDENTIFIER, &identifier.to_string())?;
+    }
+
+    Ok( o)
+}
+
+fn key_identifier<K>( key : &K) -> Option<PKAIdentifier> where K : ToIdentifier {
+    Some( ToIdentifier::to_identifier( key))
+}
+
+fn no_identifier<T>( _ : &T) -> Option<PKAIdentifier> {
+    None
+}
+
+impl EncodeArmor for aa::PublicKey {
+    fn encode_armor( key : &aa::PublicKey) -> String {
+        encode_object( "PUBLIC KEY", "public-key", key, key_identifier( key))
+    }
+}
+
+impl DecodeArmor for aa::PublicKey {
+    fn decode_armor( s : &str) -> Result<aa::PublicKey, &'static str> {
+        decode_object( "PUBLIC KEY", "public-key", s, key_identifier)
+    }
+}
+
+impl EncodeArmor for aa::PrivateKey {
+    fn encode_armor( key : &aa::PrivateKey) -> String {
+        encode_object( "PRIVATE KEY", "private-key", key, key_identifier( key))
+    }
+}
+
+impl DecodeArmor for aa::PrivateKey {
+    fn decode_armor( s : &str) -> Result<aa::PrivateKey, &'static str> {
+        decode_object( "PRIVATE KEY", "private-key", s, key_identifier)
+    }
+}
+
+impl EncodeArmor for ae::PublicKey {
+    fn encode_armor( key : &ae::PublicKey) -> String {
+        encode_object( "PUBLIC KEY", "public-key", key, key_identifier( key))
+    }
+}
+
+impl DecodeArmor for ae::PublicKey {
+    fn decode_armor( s : &str) -> Result<ae::PublicKey, &'static str> {
+        decode_object( "PUBLIC KEY", "public-key", s, key_identifier)
+    }
+}
+
+impl EncodeArmor for ae::PrivateKey {
+    fn encode_armor( key : &ae::PrivateKey) -> String {
+        encode_object( "PRIVATE KEY", "private-key", key, key_identifier( key))
+    }
+}
+
+impl DecodeArmor for ae::PrivateKey {
+    fn decode_armor( s : &str) -> Result<ae::PrivateKey, &'static str> {
+        decode_object( "PRIVATE KEY", "private-key", s, key_identifier)
+    }
+}
+
+impl EncodeArmor for se::Key {
+    fn encode_armor( key : &se::Key) -> String {
+        encode_object( "SYMMETRIC KEY", "symmetric-key", key, key_identifier( key))
+    }
+}
+
+impl DecodeArmor for se::Key {
+    fn decode_armor( s : &str) -> Result<se::Key, &'static str> {
+        decode_object( "SYMMETRIC KEY", "symmetric-key", s, key_identifier)
+    }
+}
+
+impl EncodeArmor for PKASigned {
+    fn encode_armor( signed : &PKASigned) -> String {
+        encode_object( "SIGNED MESSAGE", "signed", signed, None)
+    }
+}
+
+impl DecodeArmor for PKASigned {
+    fn decode_armor( s : &str) -> Result<PKASigned, &'static str> {
+        decode_object( "SIGNED MESSAGE", "signed", s, no_identifier)
+    }
+}
+
+impl EncodeArmor for PKASymEncrypted {
+    fn encode_armor( cipher : &PKASymEncrypted) -> String {
+        encode_object( "ENCRYPTED MESSAGE", "sym-encrypted", cipher, None)
+    }
+}
+
+impl DecodeArmor for PKASymEncrypted {
+    fn decode_armor( s : &str) -> Result<PKASymEncrypted, &'static str> {
+        decode_object( "ENCRYPTED MESSAGE", "sym-encrypted", s, no_identifier)
+    }
+}
@@ -0,0 +1,138 @@
+// Bech32m following BIP-350: https://github.com/bitcoin/bips/blob/master/bip-0350.mediawiki
+// Plain Bech32 (BIP-173) is kept for formats that predate Bech32m, like age.
+
+use boolinator::Boolinator;
+
+const CHARSET : &'static [u8] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+/// Checksum variant.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Variant {
+    Bech32,
+    Bech32m,
+}
+
+impl Variant {
+    fn constant( &self) -> u32 {
+        match *self {
+            Variant::Bech32 => 1,
+            Variant::Bech32m => 0x2bc830a3,
+        }
+    }
+}
+
+const CHECKSUMLENGTH : usize = 6;
+const MAXLENGTH : usize = 90;
+
+fn polymod( values : &[u8]) -> u32 {
+    const GENERATOR : [u32; 5] = [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+
+    let mut chk : u32 = 1;
+    for v in values {
+        let b = chk >> 25;
+        chk = ((chk & 0x1ffffff) << 5) ^ (*v as u32);
+        for (i, g) in GENERATOR.iter().enumerate() {
+            if (b >> i) & 1 == 1 {
+                chk ^= *g;
+            }
+        }
+    }
+    chk
+}
+
+fn hrp_expand( hrp : &[u8]) -> Vec<u8> {
+    let mut v : Vec<u8> = hrp.iter().map(|c| c >> 5).collect();
+    v.push( 0);
+    v.extend( hrp.iter().map(|c| c & 31));
+    v
+}
+
+fn checksum( variant : Variant, hrp : &[u8], data : &[u8]) -> Vec<u8> {
+    let mut values = hrp_expand( hrp);
+    values.extend( data);
+    values.extend( &[0u8; CHECKSUMLENGTH]);
+
+    let m = polymod( &values) ^ variant.constant();
+    (0 .. CHECKSUMLENGTH).map(|i| ((m >> (5 * (5 - i))) & 31) as u8).collect()
+}
+
+// Regroup bits from `from` bit groups to `to` bit groups, padding the last group with zeros.
+fn convert_bits( data : &[u8], from : u32, to : u32, pad : bool) -> Vec<u8> {
+    let mut acc : u32 = 0;
+    let mut bits : u32 = 0;
+    let mut out = Vec::with_capacity( data.len() * from as usize / to as usize + 1);
+    let max = (1 << to) - 1;
+    let max_acc = (1 << (from + to - 1)) - 1;
+
+    for d in data {
+        acc = ((acc << from) | (*d as u32)) & max_acc;
+        bits += from;
+        while bits >= to {
+            bits -= to;
+            out.push( ((acc >> bits) & max) as u8);
+        }
+    }
+
+    if pad && bits > 0 {
+        out.push( ((acc << (to - bits)) & max) as u8);
+    }
+    out
+}
+
+// Decoding drops the padding, which must be fewer than 5 bits and all zero.
+fn unpad_bits( data : &[u8]) -> Result<Vec<u8>, &'static str> {
+    let padding = data.len() * 5 % 8;
+    (padding < 5).ok_or( "Invalid Bech32 padding.")?;
+    let last = data.last().map_or( 0, |d| *d);
+    (last & ((1 << padding) - 1) == 0).ok_or( "Invalid Bech32 padding.")?;
+
+    Ok( convert_bits( data, 5, 8, false))
+}
+
+/// Encode bytes as Bech32m with the given lowercase human-readable prefix.
+pub fn encode( hrp : &str, data : &[u8]) -> String {
+    encode_variant( Variant::Bech32m, hrp, data)
+}
+
+/// Decode a Bech32m string to its human-readable prefix and bytes.
+pub fn decode( s : &str) -> Result<(String, Vec<u8>), &'static str> {
+    decode_variant( Variant::Bech32m, s)
+}
+
+/// Encode bytes with the given checksum variant.
+pub fn encode_variant( variant : Variant, hrp : &str, data : &[u8]) -> String {
+    let data = convert_bits( data, 8, 5, true);
+    let checksum = checksum( variant, hrp.as_bytes(), &data);
+
+    let mut s = String::with_capacity( hrp.len() + 1 + data.len() + CHECKSUMLENGTH);
+    s.push_str( hrp);
+    s.push( '1');
+    for d in data.iter().chain( checksum.iter()) {
+        s.push( CHARSET[*d as usize] as char);
+    }
+    s
+}
+
+/// Decode a string with the given checksum variant.
+pub fn decode_variant( variant : Variant, s : &str) -> Result<(String, Vec<u8>), &'static str> {
+    (s.len() <= MAXLENGTH).ok_or( "Bech32 string is too long.")?;
+    (s.bytes().all(|c| c >= 33 && c <= 126)).ok_or( "Invalid Bech32 character.")?;
+    (s.to_lowercase() == s || s.to_uppercase() == s).ok_or( "Bech32 string has mixed case.")?;
+
+    let s = s.to_lowercase();
+    let separator = s.rfind( '1').ok_or( "Missing Bech32 separator.")?;
+    let (hrp, data) = (&s[0 .. separator], &s[separator + 1 ..]);
+    (!hrp.is_empty() && data.len() >= CHECKSUMLENGTH).ok_or( "Bech32 string is too short.")?;
+
+    let data = data.bytes().map(|c| {
+        CHARSET.iter().position(|d| *d == c).map(|i| i as u8).ok_or( "Invalid Bech32 character.")
+    }).collect::<Result<Vec<u8>, &'static str>>()?;
+
+    let mut values = hrp_expand( hrp.as_bytes());
+    values.extend( &data);
+    (polymod( &values) == variant.constant()).ok_or( "Invalid Bech32 checksum.")?;
+
+    let data = unpad_bits( &data[0 .. data.len() - CHECKSUMLENGTH])?;
+
+    Ok( (hrp.to_owned(), data))
+}
@@ -0,0 +1,156 @@
+// Compact binary wire format. An object is a version byte, an object type byte, and an algorithm tag, followed by its fields.
+// Each field is prefixed by its length as a 4 byte big endian integer.
+
+use boolinator::Boolinator;
+use crypto_abstract::ToAlgorithm;
+use crypto_abstract::asym::auth as aa;
+use crypto_abstract::asym::enc as ae;
+use crypto_abstract::sym::enc as se;
+
+use encoding::{DecodeBinary, EncodeBinary};
+use internal::{AlgorithmTag, DecodePSF, EncodePSF};
+
+/// Version of the binary wire format.
+pub const VERSION : u8 = 0x01;
+
+const LENGTHSIZE : usize = 4;
+
+/// Type of object encoded in the binary wire format.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ObjectType {
+    PublicKey,
+    PrivateKey,
+    Key,
+    Signed,
+    SymEncrypted,
+}
+
+impl ObjectType {
+    pub fn to_byte( &self) -> u8 {
+        match *self {
+            ObjectType::PublicKey => 0x01,
+            ObjectType::PrivateKey => 0x02,
+            ObjectType::Key => 0x03,
+            ObjectType::Signed => 0x04,
+            ObjectType::SymEncrypted => 0x05,
+        }
+    }
+
+    pub fn from_byte( b : u8) -> Option<ObjectType> {
+        match b {
+            0x01 => Some( ObjectType::PublicKey),
+            0x02 => Some( ObjectType::PrivateKey),
+            0x03 => Some( ObjectType::Key),
+            0x04 => Some( ObjectType::Signed),
+            0x05 => Some( ObjectType::SymEncrypted),
+            _ => None
+        }
+    }
+}
+
+/// Check whether bytes start like an object in the binary wire format. JSON never starts with the version byte.
+pub fn is_binary( bs : &[u8]) -> bool {
+    bs.first() == Some( &VERSION)
+}
+
+/// Encode an object's fields.
+pub fn encode( typ : ObjectType, algorithm : u8, fields : &[&[u8]]) -> Vec<u8> {
+    let mut out = vec![ VERSION, typ.to_byte(), algorithm];
+    for field in fields {
+        out.extend( &(field.len() as u32).to_be_bytes());
+        out.extend( *field);
+    }
+    out
+}
+
+/// Decode an object of the given type with exactly `count` fields. Returns its algorithm tag and fields.
+pub fn decode( typ : ObjectType, count : usize, bs : &[u8]) -> Result<(u8, Vec<Vec<u8>>), &'static str> {
+    (bs.len() >= 3).ok_or( "Truncated binary object.")?;
+    (bs[0] == VERSION).ok_or( "Unsupported binary format version.")?;
+    (ObjectType::from_byte( bs[1]) == Some( typ)).ok_or( "Unexpected binary object type.")?;
+
+    let mut position = 3;
+    let mut fields = Vec::with_capacity( count);
+    for _ in 0 .. count {
+        let length = bs.get( position .. position + LENGTHSIZE).ok_or( "Truncated binary object.")?;
+        let length = length.iter().fold( 0usize, |acc, b| (acc << 8) | *b as usize);
+        position += LENGTHSIZE;
+
+        let end = position.checked_add( length).ok_or( "Truncated binary object.")?;
+        fields.push( bs.get( position .. end).ok_or( "Truncated binary object.")?.to_vec());
+        position = end;
+    }
+    (position == bs.len()).ok_or( "Trailing data after binary object.")?;
+
+    Ok( (bs[2], fields))
+}
+
+fn encode_key<K>( typ : ObjectType, key : &K) -> Vec<u8> where K : EncodePSF + ToAlgorithm, K::Algorithm : AlgorithmTag {
+    encode( typ, AlgorithmTag::to_algorithm_tag( &ToAlgorithm::to_algorithm( key)), &[ &EncodePSF::encode_psf( key)])
+}
+
+fn decode_key<K>( typ : ObjectType, bs : &[u8]) -> Result<K, &'static str> where K : DecodePSF, K::Algorithm : AlgorithmTag {
+    let (tag, fields) = decode( typ, 1, bs)?;
+    let alg = AlgorithmTag::from_algorithm_tag( tag).ok_or( "Unknown algorithm tag.")?;
+    DecodePSF::decode_psf( &alg, &fields[0])
+}
+
+impl EncodeBinary for aa::PublicKey {
+    fn encode_binary( key : &aa::PublicKey) -> Vec<u8> {
+        encode_key( ObjectType::PublicKey, key)
+    }
+}
+
+impl DecodeBinary for aa::PublicKey {
+    fn decode_binary( bs : &[u8]) -> Result<aa::PublicKey, &'static str> {
+        decode_key( ObjectType::PublicKey, bs)
+    }
+}
+
+impl EncodeBinary for aa::PrivateKey {
+    fn encode_binary( key : &aa::PrivateKey) -> Vec<u8> {
+        encode_key( ObjectType::PrivateKey, key)
+    }
+}
+
+impl DecodeBinary for aa::PrivateKey {
+    fn decode_binary( bs : &[u8]) -> Result<aa::PrivateKey, &'static str> {
+        decode_key( ObjectType::PrivateKey, bs)
+    }
+}
+
+impl EncodeBinary for ae::PublicKey {
+    fn encode_binary( key : &ae::PublicKey) -> Vec<u8> {
+        encode_key( ObjectType::PublicKey, key)
+    }
+}
+
+impl DecodeBinary for ae::PublicKey {
+    fn decode_binary( bs : &[u8]) -> Result<ae::PublicKey, &'static str> {
+        decode_key( ObjectType::PublicKey, bs)
+    }
+}
+
+impl EncodeBinary for ae::PrivateKey {
+    fn encode_binary( key : &ae::PrivateKey) -> Vec<u8> {
+        encode_key( ObjectType::PrivateKey, key)
+    }
+}
+
+impl DecodeBinary for ae::PrivateKey {
+    fn decode_binary( bs : &[u8]) -> Result<ae::PrivateKey, &'static str> {
+        decode_key( ObjectType::PrivateKey, bs)
+    }
+}
+
+impl EncodeBinary for se::Key {
+    fn encode_binary( key : &se::Key) -> Vec<u8> {
+        encode_key( ObjectType::Key, key)
+    }
+}
+
+impl DecodeBinary for se::Key {
+    fn decode_binary( bs : &[u8]) -> Result<se::Key, &'static str> {
+        decode_key( ObjectType::Key, bs)
+    }
+}
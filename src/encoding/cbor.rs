@@ -0,0 +1,231 @@
+// Concise Binary Object Representation following RFC 8949: https://www.rfc-editor.org/rfc/rfc8949
+// Values are encoded deterministically (section 4.2). Only definite lengths are accepted when decoding.
+
+use boolinator::Boolinator;
+
+// Limit nesting so malicious input cannot overflow the stack.
+const MAXDEPTH : usize = 16;
+
+/// CBOR data item. Floating point numbers are not supported.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Value {
+    Unsigned( u64),
+    /// The negative integer `-1 - n`, so that every integer CBOR can represent can be built.
+    Negative( u64),
+    Bytes( Vec<u8>),
+    Text( String),
+    Array( Vec<Value>),
+    Map( Vec<(Value, Value)>),
+    Tag( u64, Box<Value>),
+    Bool( bool),
+    Null,
+}
+
+impl Value {
+    pub fn integer( i : i64) -> Value {
+        if i >= 0 {
+            Value::Unsigned( i as u64)
+        }
+        else {
+            Value::Negative( !i as u64)
+        }
+    }
+
+    /// Look up a key of a map.
+    pub fn get( &self, key : &Value) -> Option<&Value> {
+        match *self {
+            Value::Map( ref entries) => entries.iter().find(|&&(ref k, _)| k == key).map(|&(_, ref v)| v),
+            _ => None,
+        }
+    }
+
+    /// Look up a field of a map by its text key.
+    pub fn field( &self, name : &str) -> Result<&Value, &'static str> {
+        self.get( &Value::Text( name.to_string())).ok_or( "Missing CBOR field.")
+    }
+
+    pub fn as_bytes( &self) -> Option<&[u8]> {
+        match *self {
+            Value::Bytes( ref bs) => Some( bs),
+            _ => None,
+        }
+    }
+
+    pub fn as_text( &self) -> Option<&str> {
+        match *self {
+            Value::Text( ref s) => Some( s),
+            _ => None,
+        }
+    }
+
+    /// Integer value, if it fits in an `i64`.
+    pub fn as_integer( &self) -> Option<i64> {
+        match *self {
+            Value::Unsigned( n) if n <= ::std::i64::MAX as u64 => Some( n as i64),
+            Value::Negative( n) if n <= ::std::i64::MAX as u64 => Some( !(n as i64)),
+            _ => None,
+        }
+    }
+}
+
+/// Encode a value. Map entries are sorted by their encoded keys.
+pub fn encode( v : &Value) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_value( &mut out, v);
+    out
+}
+
+/// Decode exactly one value, rejecting trailing data, duplicate map keys, and arguments not in their shortest form.
+pub fn decode( bs : &[u8]) -> Result<Value, &'static str> {
+    let (v, length) = read_value( bs, 0)?;
+    (length == bs.len()).ok_or( "Trailing data after CBOR value.")?;
+    Ok( v)
+}
+
+fn write_head( out : &mut Vec<u8>, major : u8, n : u64) {
+    let major = major << 5;
+    if n < 24 {
+        out.push( major | n as u8);
+    }
+    else if n <= 0xff {
+        out.push( major | 24);
+        out.push( n as u8);
+    }
+    else if n <= 0xffff {
+        out.push( major | 25);
+        out.extend( &(n as u16).to_be_bytes());
+    }
+    else if n <= 0xffffffff {
+        out.push( major | 26);
+        out.extend( &(n as u32).to_be_bytes());
+    }
+    else {
+        out.push( major | 27);
+        out.extend( &n.to_be_bytes());
+    }
+}
+
+fn write_value( out : &mut Vec<u8>, v : &Value) {
+    match *v {
+        Value::Unsigned( n) => write_head( out, 0, n),
+        Value::Negative( n) => write_head( out, 1, n),
+        Value::Bytes( ref bs) => {
+            write_head( out, 2, bs.len() as u64);
+            out.extend( bs);
+        }
+        Value::Text( ref s) => {
+            write_head( out, 3, s.len() as u64);
+            out.extend( s.as_bytes());
+        }
+        Value::Array( ref a) => {
+            write_head( out, 4, a.len() as u64);
+            for v in a {
+                write_value( out, v);
+            }
+        }
+        Value::Map( ref m) => {
+            let mut entries : Vec<(Vec<u8>, &Value)> = m.iter().map(|&(ref k, ref v)| (encode( k), v)).collect();
+            entries.sort_by( |a, b| a.0.cmp( &b.0));
+
+            write_head( out, 5, entries.len() as u64);
+            for (k, v) in entries {
+                out.extend( k);
+                write_value( out, v);
+            }
+        }
+        Value::Tag( tag, ref v) => {
+            write_head( out, 6, tag);
+            write_value( out, v);
+        }
+        Value::Bool( false) => out.push( 0xf4),
+        Value::Bool( true) => out.push( 0xf5),
+        Value::Null => out.push( 0xf6),
+    }
+}
+
+// Read the major type and argument at the start of the input. Returns them with the length of the head.
+fn read_head( bs : &[u8]) -> Result<(u8, u8, u64, usize), &'static str> {
+    let initial = *bs.get( 0).ok_or( "Truncated CBOR value.")?;
+    let major = initial >> 5;
+    let info = initial & 0x1f;
+
+    let length = match info {
+        0 ..= 23 => return Ok( (major, info, info as u64, 1)),
+        24 => 1,
+        25 => 2,
+        26 => 4,
+        27 => 8,
+        _ => return Err( "Unsupported CBOR encoding."),
+    };
+    (bs.len() > length).ok_or( "Truncated CBOR value.")?;
+    let n = bs[1 .. 1 + length].iter().fold( 0u64, |acc, b| (acc << 8) | *b as u64);
+
+    // Arguments must use the shortest head.
+    let minimal = match length {
+        1 => n >= 24,
+        2 => n > 0xff,
+        4 => n > 0xffff,
+        _ => n > 0xffffffff,
+    };
+    minimal.ok_or( "Non-deterministic CBOR encoding.")?;
+
+    Ok( (major, info, n, 1 + length))
+}
+
+fn read_value( bs : &[u8], depth : usize) -> Result<(Value, usize), &'static str> {
+    (depth < MAXDEPTH).ok_or( "CBOR value is nested too deeply.")?;
+
+    let (major, info, n, head) = read_head( bs)?;
+    match major {
+        0 => Ok( (Value::Unsigned( n), head)),
+        1 => Ok( (Value::Negative( n), head)),
+        2 | 3 => {
+            let end = head.checked_add( n as usize).ok_or( "Truncated CBOR value.")?;
+            let data = bs.get( head .. end).ok_or( "Truncated CBOR value.")?;
+            if major == 2 {
+                Ok( (Value::Bytes( data.to_vec()), end))
+            }
+            else {
+                let s = String::from_utf8( data.to_vec()).map_err(|_| "Invalid CBOR text.")?;
+                Ok( (Value::Text( s), end))
+            }
+        }
+        4 => {
+            // Every item takes at least one byte, which bounds the allocation.
+            ((n as usize) <= bs.len()).ok_or( "Truncated CBOR value.")?;
+            let mut position = head;
+            let mut items = Vec::with_capacity( n as usize);
+            for _ in 0 .. n {
+                let (v, length) = read_value( &bs[position ..], depth + 1)?;
+                items.push( v);
+                position += length;
+            }
+            Ok( (Value::Array( items), position))
+        }
+        5 => {
+            ((n as usize) <= bs.len()).ok_or( "Truncated CBOR value.")?;
+            let mut position = head;
+            let mut entries : Vec<(Value, Value)> = Vec::with_capacity( n as usize);
+            for _ in 0 .. n {
+                let (k, length) = read_value( &bs[position ..], depth + 1)?;
+                entries.iter().all(|&(ref e, _)| *e != k).ok_or( "Duplicate CBOR map key.")?;
+                position += length;
+
+                let (v, length) = read_value( &bs[position ..], depth + 1)?;
+                entries.push( (k, v));
+                position += length;
+            }
+            Ok( (Value::Map( entries), position))
+        }
+        6 => {
+            let (v, length) = read_value( &bs[head ..], depth + 1)?;
+            Ok( (Value::Tag( n, Box::new( v)), head + length))
+        }
+        _ => match info {
+            20 => Ok( (Value::Bool( false), head)),
+            21 => Ok( (Value::Bool( true), head)),
+            22 => Ok( (Value::Null, head)),
+            _ => Err( "Unsupported CBOR simple value."),
+        }
+    }
+}
@@ -0,0 +1,275 @@
+// CBOR Object Signing and Encryption following RFC 9052 and RFC 9053: https://www.rfc-editor.org/rfc/rfc9052
+
+use boolinator::Boolinator;
+use crypto_abstract::{ToAlgorithm, ToPublicKey};
+use crypto_abstract::asym::auth;
+use ring::aead;
+use ring::rand::{SecureRandom, SystemRandom};
+
+use asym::auth as aa;
+use asym::enc as ae;
+use encoding::{DecodeCbor, EncodeCbor};
+use encoding::cbor;
+use encoding::cbor::Value;
+use internal;
+use internal::{DecodePSF, EncodePSF, ToIdentifier};
+use sym::enc as se;
+
+/// CBOR tag of COSE_Sign1 messages.
+pub const TAGSIGN1 : u64 = 18;
+/// CBOR tag of COSE_Encrypt0 messages.
+pub const TAGENCRYPT0 : u64 = 16;
+
+// Header parameters.
+const HEADERALG : i64 = 1;
+const HEADERKID : i64 = 4;
+const HEADERIV : i64 = 5;
+
+// Algorithms.
+const ALGEDDSA : i64 = -8;
+const ALGA256GCM : i64 = 3;
+
+// Key parameters.
+const KEYKTY : i64 = 1;
+const KEYALG : i64 = 3;
+const KEYCRV : i64 = -1;
+const KEYX : i64 = -2;
+const KEYD : i64 = -4;
+const KEYK : i64 = -1;
+
+const KTYOKP : i64 = 1;
+const KTYSYMMETRIC : i64 = 4;
+const CRVX25519 : i64 = 4;
+const CRVED25519 : i64 = 6;
+
+const IVLENGTH : usize = 12;
+const TAGLENGTH : usize = 16;
+
+/// Sign a payload as a tagged COSE_Sign1 message. The signer's identifier is its key id.
+pub fn sign1( key : &aa::PrivateKey, payload : &[u8], external_aad : &[u8]) -> Result<Vec<u8>, &'static str> {
+    let protected = cbor::encode( &Value::Map( vec![ (int( HEADERALG), int( sign_algorithm( &ToAlgorithm::to_algorithm( key))))]));
+    let tbs = sig_structure( &protected, external_aad, payload);
+    let signature = auth::sign( key, &tbs).map_err(|_| "Error signing content.")?;
+
+    let unprotected = Value::Map( vec![ (int( HEADERKID), Value::Bytes( ToIdentifier::to_identifier( key).as_bytes().to_vec()))]);
+    let message = Value::Array( vec![ Value::Bytes( protected), unprotected, Value::Bytes( payload.to_vec()), Value::Bytes( EncodePSF::encode_psf( &signature))]);
+
+    Ok( cbor::encode( &Value::Tag( TAGSIGN1, Box::new( message))))
+}
+
+/// Verify a COSE_Sign1 message, tagged or untagged, and return its payload.
+pub fn verify1( key : &aa::PublicKey, message : &[u8], external_aad : &[u8]) -> Result<Vec<u8>, &'static str> {
+    let parts = message_parts( message, TAGSIGN1)?;
+    let (protected, payload, signature) = match parts.as_slice() {
+        &[Value::Bytes( ref protected), Value::Map( _), ref payload, Value::Bytes( ref signature)] => (protected, payload, signature),
+        _ => return Err( "Invalid COSE_Sign1 message."),
+    };
+    let payload = payload.as_bytes().ok_or( "Detached payloads are not supported.")?;
+
+    let alg = protected_algorithm( protected)?;
+    (alg == sign_algorithm( &ToAlgorithm::to_algorithm( key))).ok_or( "Algorithms do not match.")?;
+
+    let signature = DecodePSF::decode_psf( &ToAlgorithm::to_algorithm( key), signature)?;
+    auth::verify( key, &sig_structure( protected, external_aad, payload), &signature).ok_or( "Invalid signature.")?;
+
+    Ok( payload.to_vec())
+}
+
+/// Encrypt a payload as a tagged COSE_Encrypt0 message with a random IV. The key's identifier is its key id.
+pub fn encrypt0( rng : &SystemRandom, key : &se::Key, plaintext : &[u8], external_aad : &[u8]) -> Result<Vec<u8>, &'static str> {
+    let protected = cbor::encode( &Value::Map( vec![ (int( HEADERALG), int( encrypt_algorithm( &ToAlgorithm::to_algorithm( key))))]));
+    let mut iv = [0u8; IVLENGTH];
+    rng.fill( &mut iv).map_err(|_| "Error generating IV.")?;
+
+    let sealing = aead::SealingKey::new( &aead::AES_256_GCM, &EncodePSF::encode_psf( key)).map_err(|_| "Invalid key.")?;
+    let mut ciphertext = plaintext.to_vec();
+    ciphertext.extend( &[0u8; TAGLENGTH]);
+    aead::seal_in_place( &sealing, &iv, &enc_structure( &protected, external_aad), &mut ciphertext, TAGLENGTH).map_err(|_| "Error encrypting content.")?;
+
+    let unprotected = Value::Map( vec![
+        (int( HEADERKID), Value::Bytes( ToIdentifier::to_identifier( key).as_bytes().to_vec())),
+        (int( HEADERIV), Value::Bytes( iv.to_vec())),
+    ]);
+    let message = Value::Array( vec![ Value::Bytes( protected), unprotected, Value::Bytes( ciphertext)]);
+
+    Ok( cbor::encode( &Value::Tag( TAGENCRYPT0, Box::new( message))))
+}
+
+/// Decrypt a COSE_Encrypt0 message, tagged or untagged.
+pub fn decrypt0( key : &se::Key, message : &[u8], external_aad : &[u8]) -> Result<Vec<u8>, &'static str> {
+    let parts = message_parts( message, TAGENCRYPT0)?;
+    let (protected, unprotected, ciphertext) = match parts.as_slice() {
+        &[Value::Bytes( ref protected), ref unprotected, Value::Bytes( ref ciphertext)] => (protected, unprotected, ciphertext),
+        _ => return Err( "Invalid COSE_Encrypt0 message."),
+    };
+
+    let alg = protected_algorithm( protected)?;
+    (alg == encrypt_algorithm( &ToAlgorithm::to_algorithm( key))).ok_or( "Algorithms do not match.")?;
+
+    let iv = unprotected.get( &int( HEADERIV)).and_then( Value::as_bytes).ok_or( "Missing IV.")?;
+    (iv.len() == IVLENGTH).ok_or( "IV is wrong length.")?;
+
+    let opening = aead::OpeningKey::new( &aead::AES_256_GCM, &EncodePSF::encode_psf( key)).map_err(|_| "Invalid key.")?;
+    let mut plaintext = ciphertext.clone();
+    let length = aead::open_in_place( &opening, iv, &enc_structure( protected, external_aad), 0, &mut plaintext).map_err(|_| "Could not decrypt ciphertext.")?.len();
+    plaintext.truncate( length);
+
+    Ok( plaintext)
+}
+
+fn int( i : i64) -> Value {
+    Value::integer( i)
+}
+
+fn sign_algorithm( alg : &aa::Algorithm) -> i64 {
+    match *alg {
+        aa::Algorithm::AAEd25519 => ALGEDDSA,
+    }
+}
+
+fn encrypt_algorithm( alg : &se::Algorithm) -> i64 {
+    match *alg {
+        se::Algorithm::SEAesGcm256 => ALGA256GCM,
+    }
+}
+
+fn sig_structure( protected : &[u8], external_aad : &[u8], payload : &[u8]) -> Vec<u8> {
+    cbor::encode( &Value::Array( vec![ Value::Text( "Signature1".to_string()), Value::Bytes( protected.to_vec()), Value::Bytes( external_aad.to_vec()), Value::Bytes( payload.to_vec())]))
+}
+
+fn enc_structure( protected : &[u8], external_aad : &[u8]) -> Vec<u8> {
+    cbor::encode( &Value::Array( vec![ Value::Text( "Encrypt0".to_string()), Value::Bytes( protected.to_vec()), Value::Bytes( external_aad.to_vec())]))
+}
+
+fn message_parts( message : &[u8], tag : u64) -> Result<Vec<Value>, &'static str> {
+    let message = match cbor::decode( message)? {
+        Value::Tag( t, v) => {
+            (t == tag).ok_or( "Unexpected COSE message type.")?;
+            *v
+        }
+        v => v,
+    };
+
+    match message {
+        Value::Array( parts) => Ok( parts),
+        _ => Err( "Invalid COSE message."),
+    }
+}
+
+// The algorithm must be protected.
+fn protected_algorithm( protected : &[u8]) -> Result<i64, &'static str> {
+    (!protected.is_empty()).ok_or( "Missing protected header.")?;
+    let header = cbor::decode( protected)?;
+    header.get( &int( HEADERALG)).and_then( Value::as_integer).ok_or( "Missing protected algorithm.")
+}
+
+fn okp_key( crv : i64, alg : Option<i64>, x : Vec<u8>, d : Option<Vec<u8>>) -> Vec<u8> {
+    let mut entries = vec![ (int( KEYKTY), int( KTYOKP)), (int( KEYCRV), int( crv)), (int( KEYX), Value::Bytes( x))];
+    if let Some( alg) = alg {
+        entries.push( (int( KEYALG), int( alg)));
+    }
+    if let Some( d) = d {
+        entries.push( (int( KEYD), Value::Bytes( d)));
+    }
+    cbor::encode( &Value::Map( entries))
+}
+
+// Check an OKP key's type, curve, and algorithm if both it and the expected algorithm are present. Returns its public and private parameters.
+fn decode_okp_key( bs : &[u8], crv : i64, alg : Option<i64>) -> Result<(Option<Vec<u8>>, Option<Vec<u8>>), &'static str> {
+    let key = cbor::decode( bs)?;
+    (key.get( &int( KEYKTY)) == Some( &int( KTYOKP))).ok_or( "COSE key is not an OKP key.")?;
+    (key.get( &int( KEYCRV)) == Some( &int( crv))).ok_or( "COSE key is of a different curve.")?;
+    if let (Some( a), Some( alg)) = (key.get( &int( KEYALG)), alg) {
+        (*a == int( alg)).ok_or( "COSE key is of a different algorithm.")?;
+    }
+
+    Ok( (optional_bytes( &key, KEYX)?, optional_bytes( &key, KEYD)?))
+}
+
+fn optional_bytes( key : &Value, label : i64) -> Result<Option<Vec<u8>>, &'static str> {
+    match key.get( &int( label)) {
+        None => Ok( None),
+        Some( v) => v.as_bytes().map(|bs| Some( bs.to_vec())).ok_or( "Invalid COSE key parameter."),
+    }
+}
+
+impl EncodeCbor for aa::PublicKey {
+    fn encode_cbor( key : &aa::PublicKey) -> Vec<u8> {
+        okp_key( CRVED25519, Some( ALGEDDSA), EncodePSF::encode_psf( key), None)
+    }
+}
+
+impl DecodeCbor for aa::PublicKey {
+    fn decode_cbor( bs : &[u8]) -> Result<aa::PublicKey, &'static str> {
+        let (x, _) = decode_okp_key( bs, CRVED25519, Some( ALGEDDSA))?;
+        DecodePSF::decode_psf( &aa::Algorithm::AAEd25519, &x.ok_or( "Missing COSE key x.")?)
+    }
+}
+
+impl EncodeCbor for aa::PrivateKey {
+    fn encode_cbor( key : &aa::PrivateKey) -> Vec<u8> {
+        let public = EncodePSF::encode_psf( &ToPublicKey::to_public_key( key));
+        okp_key( CRVED25519, Some( ALGEDDSA), public, Some( internal::asym::auth::private_key_seed( key).to_vec()))
+    }
+}
+
+impl DecodeCbor for aa::PrivateKey {
+    fn decode_cbor( bs : &[u8]) -> Result<aa::PrivateKey, &'static str> {
+        let (x, d) = decode_okp_key( bs, CRVED25519, Some( ALGEDDSA))?;
+        let key = aa::gen_from_seed( &aa::Algorithm::AAEd25519, &d.ok_or( "Missing COSE key d.")?)?;
+
+        // The public key is optional, but must match if present.
+        x.map_or( true, |x| x == EncodePSF::encode_psf( &ToPublicKey::to_public_key( &key))).ok_or( "COSE key x does not match d.")?;
+        Ok( key)
+    }
+}
+
+impl EncodeCbor for ae::PublicKey {
+    fn encode_cbor( key : &ae::PublicKey) -> Vec<u8> {
+        okp_key( CRVX25519, None, EncodePSF::encode_psf( key), None)
+    }
+}
+
+impl DecodeCbor for ae::PublicKey {
+    fn decode_cbor( bs : &[u8]) -> Result<ae::PublicKey, &'static str> {
+        let (x, _) = decode_okp_key( bs, CRVX25519, None)?;
+        DecodePSF::decode_psf( &ae::Algorithm::AEX25519, &x.ok_or( "Missing COSE key x.")?)
+    }
+}
+
+impl EncodeCbor for ae::PrivateKey {
+    fn encode_cbor( key : &ae::PrivateKey) -> Vec<u8> {
+        let public = EncodePSF::encode_psf( &ToPublicKey::to_public_key( key));
+        okp_key( CRVX25519, None, public, Some( EncodePSF::encode_psf( key)))
+    }
+}
+
+impl DecodeCbor for ae::PrivateKey {
+    fn decode_cbor( bs : &[u8]) -> Result<ae::PrivateKey, &'static str> {
+        let (x, d) = decode_okp_key( bs, CRVX25519, None)?;
+        let key = ae::gen_from_seed( &ae::Algorithm::AEX25519, &d.ok_or( "Missing COSE key d.")?)?;
+
+        x.map_or( true, |x| x == EncodePSF::encode_psf( &ToPublicKey::to_public_key( &key))).ok_or( "COSE key x does not match d.")?;
+        Ok( key)
+    }
+}
+
+impl EncodeCbor for se::Key {
+    fn encode_cbor( key : &se::Key) -> Vec<u8> {
+        let alg = encrypt_algorithm( &ToAlgorithm::to_algorithm( key));
+        cbor::encode( &Value::Map( vec![ (int( KEYKTY), int( KTYSYMMETRIC)), (int( KEYALG), int( alg)), (int( KEYK), Value::Bytes( EncodePSF::encode_psf( key)))]))
+    }
+}
+
+impl DecodeCbor for se::Key {
+    fn decode_cbor( bs : &[u8]) -> Result<se::Key, &'static str> {
+        let key = cbor::decode( bs)?;
+        (key.get( &int( KEYKTY)) == Some( &int( KTYSYMMETRIC))).ok_or( "COSE key is not a symmetric key.")?;
+        if let Some( alg) = key.get( &int( KEYALG)) {
+            (*alg == int( ALGA256GCM)).ok_or( "COSE key is of a different algorithm.")?;
+        }
+
+        let k = key.get( &int( KEYK)).and_then( Value::as_bytes).ok_or( "Missing COSE key k.")?;
+        DecodePSF::decode_psf( &se::Algorithm::SEAesGcm256, &k.to_vec())
+    }
+}
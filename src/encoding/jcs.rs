@@ -0,0 +1,243 @@
+// JSON Canonicalization Scheme following RFC 8785: https://www.rfc-editor.org/rfc/rfc8785
+
+use boolinator::Boolinator;
+use serde::Serialize;
+use serde::ser;
+use serde::ser::Serializer;
+use serde_json;
+use serde_json::{Number, Value};
+use std::error;
+use std::fmt;
+
+/// Canonical JSON encoding of a serializable value.
+pub fn to_canonical<T>( o : &T) -> Result<Vec<u8>, &'static str> where T : Serialize {
+    o.serialize( FiniteCheck).map_err(|e| e.0)?;
+    let v = serde_json::to_value( o).map_err(|_| "Error generating json.")?;
+    canonicalize( &v)
+}
+
+/// Canonical JSON encoding of a JSON value.
+pub fn canonicalize( v : &Value) -> Result<Vec<u8>, &'static str> {
+    let mut out = String::new();
+    write_value( &mut out, v)?;
+    Ok( out.into_bytes())
+}
+
+/// Check whether bytes are the canonical encoding of the JSON they contain.
+pub fn is_canonical( bs : &[u8]) -> bool {
+    match serde_json::from_slice::<Value>( bs) {
+        Ok( v) => canonicalize( &v).map( |c| c == bs).unwrap_or( false),
+        Err( _) => false,
+    }
+}
+
+fn write_value( out : &mut String, v : &Value) -> Result<(), &'static str> {
+    match *v {
+        Value::Null => out.push_str( "null"),
+        Value::Bool( b) => out.push_str( if b { "true" } else { "false" }),
+        Value::Number( ref n) => out.push_str( &number( n)?),
+        Value::String( ref s) => write_string( out, s),
+        Value::Array( ref a) => {
+            out.push( '[');
+            for (i, v) in a.iter().enumerate() {
+                if i > 0 {
+                    out.push( ',');
+                }
+                write_value( out, v)?;
+            }
+            out.push( ']');
+        }
+        Value::Object( ref o) => {
+            // Properties are sorted by their UTF-16 code units.
+            let mut properties : Vec<(Vec<u16>, &String, &Value)> = o.iter().map(|(k, v)| (k.encode_utf16().collect(), k, v)).collect();
+            properties.sort_by( |a, b| a.0.cmp( &b.0));
+
+            out.push( '{');
+            for (i, &(_, k, v)) in properties.iter().enumerate() {
+                if i > 0 {
+                    out.push( ',');
+                }
+                write_string( out, k);
+                out.push( ':');
+                write_value( out, v)?;
+            }
+            out.push( '}');
+        }
+    }
+
+    Ok(())
+}
+
+fn write_string( out : &mut String, s : &str) {
+    out.push( '"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str( "\\\""),
+            '\\' => out.push_str( "\\\\"),
+            '\u{8}' => out.push_str( "\\b"),
+            '\u{c}' => out.push_str( "\\f"),
+            '\n' => out.push_str( "\\n"),
+            '\r' => out.push_str( "\\r"),
+            '\t' => out.push_str( "\\t"),
+            c if (c as u32) < 0x20 => out.push_str( &format!( "\\u{:04x}", c as u32)),
+            c => out.push( c),
+        }
+    }
+    out.push( '"');
+}
+
+// Numbers are serialized like ECMAScript's Number.prototype.toString. Integers are rejected instead of being rounded to a different value, unless they are already the canonical form of a double.
+fn number( n : &Number) -> Result<String, &'static str> {
+    let f = if let Some( u) = n.as_u64() {
+        let f = u as f64;
+        let exact = f < 18446744073709551616.0 && f as u64 == u;
+        (exact || format_double( f) == u.to_string()).ok_or( "Integer cannot be represented exactly.")?;
+        f
+    }
+    else if let Some( i) = n.as_i64() {
+        let f = i as f64;
+        let exact = f < 9223372036854775808.0 && f as i64 == i;
+        (exact || format_double( f) == i.to_string()).ok_or( "Integer cannot be represented exactly.")?;
+        f
+    }
+    else {
+        n.as_f64().ok_or( "Invalid number.")?
+    };
+    f.is_finite().ok_or( "Number is not finite.")?;
+
+    Ok( format_double( f))
+}
+
+fn format_double( f : f64) -> String {
+    if f == 0.0 {
+        return "0".to_string()
+    }
+
+    let sign = if f < 0.0 { "-" } else { "" };
+
+    let (digits, exponent) = shortest_digits( f.abs());
+
+    let k = digits.len() as i32;
+    let n = exponent + 1;
+
+    let s = if k <= n && n <= 21 {
+        format!( "{}{}", digits, "0".repeat( (n - k) as usize))
+    }
+    else if 0 < n && n <= 21 {
+        format!( "{}.{}", &digits[.. n as usize], &digits[n as usize ..])
+    }
+    else if -6 < n && n <= 0 {
+        format!( "0.{}{}", "0".repeat( (-n) as usize), digits)
+    }
+    else {
+        let e = if n - 1 < 0 { format!( "-{}", 1 - n) } else { format!( "+{}", n - 1) };
+        if k == 1 {
+            format!( "{}e{}", digits, e)
+        }
+        else {
+            format!( "{}.{}e{}", &digits[.. 1], &digits[1 ..], e)
+        }
+    };
+
+    format!( "{}{}", sign, s)
+}
+
+// Shortest round trip digits and exponent. Rust's shortest formatting gives the number of digits, but rounds up when the double is halfway between two candidates. ECMAScript takes the candidate closest to the double, and the even one on ties, which is what formatting to that many digits does.
+fn shortest_digits( f : f64) -> (String, i32) {
+    let (digits, exponent) = split_scientific( &format!( "{:e}", f));
+    let (closest, closest_exponent) = split_scientific( &format!( "{:.*e}", digits.len() - 1, f));
+
+    match format!( "{}e{}", closest, closest_exponent - closest.len() as i32 + 1).parse::<f64>() {
+        Ok( g) if g == f => (closest, closest_exponent),
+        _ => (digits, exponent),
+    }
+}
+
+fn split_scientific( s : &str) -> (String, i32) {
+    let mut parts = s.split( 'e');
+    let mantissa = parts.next().unwrap_or( "0");
+    let exponent = parts.next().and_then(|e| e.parse().ok()).unwrap_or( 0);
+    let digits : String = mantissa.chars().filter(|c| *c != '.').collect();
+
+    (digits.trim_end_matches( '0').to_string(), exponent)
+}
+
+// serde_json::to_value turns non-finite floats into null before they reach a Value::Number, so typed values are checked by serializing them once without output.
+struct FiniteCheck;
+
+#[derive(Debug)]
+struct CheckError( &'static str);
+
+impl fmt::Display for CheckError {
+    fn fmt( &self, f : &mut fmt::Formatter) -> fmt::Result {
+        f.write_str( self.0)
+    }
+}
+
+impl error::Error for CheckError {}
+
+impl ser::Error for CheckError {
+    fn custom<T>( _ : T) -> CheckError where T : fmt::Display {
+        CheckError( "Error generating json.")
+    }
+}
+
+macro_rules! accept {
+    ( $( $f:ident( $( $t:ty),*);)*) => { $( fn $f( self, $( _ : $t),*) -> Result<(), CheckError> { Ok(()) })* }
+}
+
+macro_rules! compound {
+    ( $( $tr:ident :: $f:ident( $( $t:ty),*);)*) => { $(
+        impl ser::$tr for FiniteCheck {
+            type Ok = ();
+            type Error = CheckError;
+            fn $f<T : ?Sized>( &mut self, $( _ : $t,)* v : &T) -> Result<(), CheckError> where T : Serialize { v.serialize( FiniteCheck) }
+            fn end( self) -> Result<(), CheckError> { Ok(()) }
+        }
+    )* }
+}
+
+impl Serializer for FiniteCheck {
+    type Ok = ();
+    type Error = CheckError;
+    type SerializeSeq = FiniteCheck;
+    type SerializeTuple = FiniteCheck;
+    type SerializeTupleStruct = FiniteCheck;
+    type SerializeTupleVariant = FiniteCheck;
+    type SerializeMap = FiniteCheck;
+    type SerializeStruct = FiniteCheck;
+    type SerializeStructVariant = FiniteCheck;
+
+    accept!{
+        serialize_bool( bool); serialize_i8( i8); serialize_i16( i16); serialize_i32( i32); serialize_i64( i64);
+        serialize_u8( u8); serialize_u16( u16); serialize_u32( u32); serialize_u64( u64); serialize_char( char);
+        serialize_str( &str); serialize_bytes( &[u8]); serialize_none(); serialize_unit(); serialize_unit_struct( &'static str);
+        serialize_unit_variant( &'static str, u32, &'static str);
+    }
+
+    fn serialize_f32( self, f : f32) -> Result<(), CheckError> { self.serialize_f64( f as f64) }
+    fn serialize_f64( self, f : f64) -> Result<(), CheckError> { f.is_finite().ok_or( CheckError( "Number is not finite.")) }
+    fn serialize_some<T : ?Sized>( self, v : &T) -> Result<(), CheckError> where T : Serialize { v.serialize( self) }
+    fn serialize_newtype_struct<T : ?Sized>( self, _ : &'static str, v : &T) -> Result<(), CheckError> where T : Serialize { v.serialize( self) }
+    fn serialize_newtype_variant<T : ?Sized>( self, _ : &'static str, _ : u32, _ : &'static str, v : &T) -> Result<(), CheckError> where T : Serialize { v.serialize( self) }
+    fn serialize_seq( self, _ : Option<usize>) -> Result<FiniteCheck, CheckError> { Ok( self) }
+    fn serialize_tuple( self, _ : usize) -> Result<FiniteCheck, CheckError> { Ok( self) }
+    fn serialize_tuple_struct( self, _ : &'static str, _ : usize) -> Result<FiniteCheck, CheckError> { Ok( self) }
+    fn serialize_tuple_variant( self, _ : &'static str, _ : u32, _ : &'static str, _ : usize) -> Result<FiniteCheck, CheckError> { Ok( self) }
+    fn serialize_map( self, _ : Option<usize>) -> Result<FiniteCheck, CheckError> { Ok( self) }
+    fn serialize_struct( self, _ : &'static str, _ : usize) -> Result<FiniteCheck, CheckError> { Ok( self) }
+    fn serialize_struct_variant( self, _ : &'static str, _ : u32, _ : &'static str, _ : usize) -> Result<FiniteCheck, CheckError> { Ok( self) }
+}
+
+compound!{
+    SerializeSeq::serialize_element(); SerializeTuple::serialize_element(); SerializeTupleStruct::serialize_field(); SerializeTupleVariant::serialize_field();
+    SerializeStruct::serialize_field( &'static str); SerializeStructVariant::serialize_field( &'static str);
+}
+
+impl ser::SerializeMap for FiniteCheck {
+    type Ok = ();
+    type Error = CheckError;
+    fn serialize_key<T : ?Sized>( &mut self, k : &T) -> Result<(), CheckError> where T : Serialize { k.serialize( FiniteCheck) }
+    fn serialize_value<T : ?Sized>( &mut self, v : &T) -> Result<(), CheckError> where T : Serialize { v.serialize( FiniteCheck) }
+    fn end( self) -> Result<(), CheckError> { Ok(()) }
+}
@@ -0,0 +1,179 @@
+/// ASCII armor.
+pub mod armor;
+pub mod bech32;
+/// Compact binary wire format.
+pub mod binary;
+/// Concise binary object representation (RFC8949).
+pub mod cbor;
+/// CBOR object signing and encryption (RFC9052).
+pub mod cose;
+/// Canonical JSON (RFC8785).
+pub mod jcs;
+pub mod multibase;
+/// Compact token strings.
+pub mod token;
+
+use boolinator::Boolinator;
+use crypto_abstract::ToAlgorithm;
+use crypto_abstract::asym::auth as aa;
+use crypto_abstract::asym::enc as ae;
+
+use internal::{AlgorithmId, DecodePSF, EncodePSF, IdentifierType, PKAIdentifier};
+
+/// Human-readable prefix of Bech32m identifiers.
+pub const IDENTIFIERPREFIX : &'static str = "pkaid";
+
+/// Human-readable prefix of Bech32m public keys.
+pub const PUBLICKEYPREFIX : &'static str = "pkapub";
+
+/// Bech32m encoding, ex `pkaid1...` or `pkapub1...`.
+pub trait EncodeBech32 {
+    fn encode_bech32( &Self) -> String;
+}
+
+pub trait DecodeBech32 {
+    fn decode_bech32( &str) -> Result<Self, &'static str> where Self : Sized;
+}
+
+/// Multibase encoding of multicodec prefixed keys, ex `z6Mk...`, as used by did:key.
+pub trait EncodeMultibase {
+    fn encode_multibase( &Self) -> String;
+}
+
+pub trait DecodeMultibase {
+    fn decode_multibase( &str) -> Result<Self, &'static str> where Self : Sized;
+}
+
+/// CBOR encoding. Keys are encoded as COSE_Key objects.
+pub trait EncodeCbor {
+    fn encode_cbor( &Self) -> Vec<u8>;
+}
+
+pub trait DecodeCbor {
+    fn decode_cbor( &[u8]) -> Result<Self, &'static str> where Self : Sized;
+}
+
+/// Compact binary encoding with a one byte algorithm tag.
+pub trait EncodeBinary {
+    fn encode_binary( &Self) -> Vec<u8>;
+}
+
+pub trait DecodeBinary {
+    fn decode_binary( &[u8]) -> Result<Self, &'static str> where Self : Sized;
+}
+
+/// ASCII armor, ex `-----BEGIN PKAUTH PUBLIC KEY-----`, for pasting into emails and tickets.
+pub trait EncodeArmor {
+    fn encode_armor( &Self) -> String;
+}
+
+pub trait DecodeArmor {
+    fn decode_armor( &str) -> Result<Self, &'static str> where Self : Sized;
+}
+
+/// Format of encoded objects.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Binary,
+}
+
+impl EncodeBech32 for PKAIdentifier {
+    fn encode_bech32( identifier : &PKAIdentifier) -> String {
+        bech32::encode( IDENTIFIERPREFIX, identifier.as_bytes())
+    }
+}
+
+impl DecodeBech32 for PKAIdentifier {
+    fn decode_bech32( s : &str) -> Result<PKAIdentifier, &'static str> {
+        let (hrp, data) = bech32::decode( s)?;
+        (hrp == IDENTIFIERPREFIX).ok_or( "Not a Bech32 identifier.")?;
+
+        PKAIdentifier::from_bytes( &data)
+    }
+}
+
+impl EncodeBech32 for aa::PublicKey {
+    fn encode_bech32( key : &aa::PublicKey) -> String {
+        encode_bech32_public_key( key)
+    }
+}
+
+impl DecodeBech32 for aa::PublicKey {
+    fn decode_bech32( s : &str) -> Result<aa::PublicKey, &'static str> {
+        decode_bech32_public_key( s)
+    }
+}
+
+impl EncodeBech32 for ae::PublicKey {
+    fn encode_bech32( key : &ae::PublicKey) -> String {
+        encode_bech32_public_key( key)
+    }
+}
+
+impl DecodeBech32 for ae::PublicKey {
+    fn decode_bech32( s : &str) -> Result<ae::PublicKey, &'static str> {
+        decode_bech32_public_key( s)
+    }
+}
+
+// Public keys are prefixed with the type byte of their versioned identifiers.
+fn encode_bech32_public_key<K>( key : &K) -> String where K : EncodePSF + ToAlgorithm, for<'a> IdentifierType : From<&'a K::Algorithm> {
+    let typ = IdentifierType::from( &ToAlgorithm::to_algorithm( key));
+
+    let mut data = vec![typ.to_byte()];
+    data.extend( EncodePSF::encode_psf( key));
+
+    bech32::encode( PUBLICKEYPREFIX, &data)
+}
+
+fn decode_bech32_public_key<K>( s : &str) -> Result<K, &'static str> where K : DecodePSF, K::Algorithm : AlgorithmId {
+    let (hrp, data) = bech32::decode( s)?;
+    (hrp == PUBLICKEYPREFIX).ok_or( "Not a Bech32 public key.")?;
+
+    let (typ, psf) = data.split_first().ok_or( "Bech32 public key is empty.")?;
+    let typ = IdentifierType::from_byte( *typ).ok_or( "Unknown key type.")?;
+    let alg = AlgorithmId::from_algorithm_id( typ.algorithm_id()).ok_or( "Public key is of a different algorithm.")?;
+
+    DecodePSF::decode_psf( &alg, &psf.to_vec())
+}
+
+impl EncodeMultibase for aa::PublicKey {
+    fn encode_multibase( key : &aa::PublicKey) -> String {
+        let code = match ToAlgorithm::to_algorithm( key) {
+            aa::Algorithm::AAEd25519 => multibase::ED25519PUB,
+        };
+        multibase::encode( code, &EncodePSF::encode_psf( key))
+    }
+}
+
+impl DecodeMultibase for aa::PublicKey {
+    fn decode_multibase( s : &str) -> Result<aa::PublicKey, &'static str> {
+        let (code, psf) = multibase::decode( s)?;
+        let alg = match code {
+            multibase::ED25519PUB => aa::Algorithm::AAEd25519,
+            _ => return Err( "Unsupported multicodec key type.")
+        };
+        DecodePSF::decode_psf( &alg, &psf)
+    }
+}
+
+impl EncodeMultibase for ae::PublicKey {
+    fn encode_multibase( key : &ae::PublicKey) -> String {
+        let code = match ToAlgorithm::to_algorithm( key) {
+            ae::Algorithm::AEX25519 => multibase::X25519PUB,
+        };
+        multibase::encode( code, &EncodePSF::encode_psf( key))
+    }
+}
+
+impl DecodeMultibase for ae::PublicKey {
+    fn decode_multibase( s : &str) -> Result<ae::PublicKey, &'static str> {
+        let (code, psf) = multibase::decode( s)?;
+        let alg = match code {
+            multibase::X25519PUB => ae::Algorithm::AEX25519,
+            _ => return Err( "Unsupported multicodec key type.")
+        };
+        DecodePSF::decode_psf( &alg, &psf)
+    }
+}
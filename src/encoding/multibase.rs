@@ -0,0 +1,56 @@
+// Multibase (base58btc) strings of multicodec prefixed keys, as used by did:key: https://github.com/multiformats/multibase
+
+use boolinator::Boolinator;
+use rust_base58::base58::{FromBase58, ToBase58};
+
+const BASE58BTC : char = 'z';
+
+/// Multicodec code of Ed25519 public keys.
+pub const ED25519PUB : u64 = 0xed;
+/// Multicodec code of X25519 public keys.
+pub const X25519PUB : u64 = 0xec;
+
+fn encode_varint( n : u64) -> Vec<u8> {
+    let mut n = n;
+    let mut out = Vec::new();
+    while n >= 0x80 {
+        out.push( (n as u8 & 0x7f) | 0x80);
+        n >>= 7;
+    }
+    out.push( n as u8);
+    out
+}
+
+// Returns the value and the number of bytes read. Rejects non-minimal encodings.
+fn decode_varint( bs : &[u8]) -> Result<(u64, usize), &'static str> {
+    let mut n : u64 = 0;
+    for (i, b) in bs.iter().enumerate().take( 9) {
+        n |= ((b & 0x7f) as u64) << (7 * i);
+        if b & 0x80 == 0 {
+            (i == 0 || *b != 0).ok_or( "Non-minimal multicodec varint.")?;
+            return Ok( (n, i + 1))
+        }
+    }
+    Err( "Invalid multicodec varint.")
+}
+
+/// Encode bytes tagged with a multicodec code as a base58btc multibase string.
+pub fn encode( code : u64, data : &[u8]) -> String {
+    let mut bs = encode_varint( code);
+    bs.extend( data);
+
+    let mut s = String::with_capacity( 1 + bs.len() * 138 / 100 + 1);
+    s.push( BASE58BTC);
+    s.push_str( &bs.to_base58());
+    s
+}
+
+/// Decode a base58btc multibase string to its multicodec code and bytes.
+pub fn decode( s : &str) -> Result<(u64, Vec<u8>), &'static str> {
+    (s.starts_with( BASE58BTC)).ok_or( "Unsupported multibase encoding.")?;
+
+    let bs = s[1 ..].from_base58().map_err(|_| "Invalid Base58 encoding.")?;
+    let (code, length) = decode_varint( &bs)?;
+
+    Ok( (code, bs[length ..].to_vec()))
+}
@@ -0,0 +1,38 @@
+// Compact token strings for HTTP headers and query strings, ex `pka1.aa-ed25519.<content>.<signature>`.
+// Parts are separated by dots and binary parts are unpadded base64url.
+
+use base64;
+use boolinator::Boolinator;
+
+/// Prefix and version of tokens.
+pub const PREFIX : &'static str = "pka1";
+
+/// Encode a token from its algorithm identifier and binary parts.
+pub fn encode( algorithm : &str, parts : &[&[u8]]) -> String {
+    let mut token = format!( "{}.{}", PREFIX, algorithm);
+    for part in parts {
+        token.push( '.');
+        token.push_str( &base64::encode_config( part, base64::URL_SAFE_NO_PAD));
+    }
+    token
+}
+
+/// Decode a token with exactly `count` binary parts. Returns its algorithm identifier and parts.
+pub fn decode( token : &str, count : usize) -> Result<(&str, Vec<Vec<u8>>), &'static str> {
+    let mut segments = token.split( '.');
+    (segments.next() == Some( PREFIX)).ok_or( "Token must start with pka1.")?;
+    let algorithm = segments.next().ok_or( "Token is missing its algorithm.")?;
+
+    let parts = segments.map( decode_part).collect::<Result<Vec<Vec<u8>>, &'static str>>()?;
+    (parts.len() == count).ok_or( "Token has the wrong number of parts.")?;
+
+    Ok( (algorithm, parts))
+}
+
+// Only accept the canonical unpadded encoding, so every value has exactly one token.
+fn decode_part( s : &str) -> Result<Vec<u8>, &'static str> {
+    s.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_').ok_or( "Invalid token character.")?;
+    let bs = base64::decode_config( s, base64::URL_SAFE_NO_PAD).map_err(|_| "Invalid token base64url encoding.")?;
+    (base64::encode_config( &bs, base64::URL_SAFE_NO_PAD) == s).ok_or( "Non-canonical token base64url encoding.")?;
+    Ok( bs)
+}
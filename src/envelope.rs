@@ -0,0 +1,203 @@
+// Sign-then-encrypt envelopes. The signed data names the recipient, so a recipient cannot re-encrypt a signed message to someone else as if it had been sent to them: http://world.std.com/~dtd/sign_encrypt/sign_encrypt7.html
+
+use boolinator::Boolinator;
+use crypto_abstract::{ToAlgorithm, ToPublicKey};
+use ring::rand::SystemRandom;
+use serde::de;
+use serde::de::{Deserialize, Deserializer, DeserializeOwned, MapAccess, Visitor};
+use serde::ser::{Serialize, Serializer, SerializeStruct};
+use serde_json;
+use serde_json::Value;
+use std::fmt;
+
+use asym::auth as aa;
+use asym::enc as ae;
+use asym::enc::hpke;
+use encoding::{DecodeCbor, EncodeCbor};
+use encoding::cbor;
+use encoding::cbor::Value as CborValue;
+use internal::{PKAIdentifier, PKAJ, ToIdentifier, deserialize_base64url, is_strict, serialize_base64url};
+use metadata::{Clock, PKAKey, Usage};
+use policy::Policy;
+
+const INFO : &'static [u8] = b"PKAuth envelope";
+
+/// Content signed by a sender and encrypted to a recipient.
+pub struct PKAEnvelope {
+    key : hpke::EncapsulatedKey,
+    ciphertext : Vec<u8>,
+}
+
+impl ToAlgorithm for PKAEnvelope {
+    type Algorithm = hpke::Algorithm;
+
+    fn to_algorithm( &self) -> Self::Algorithm {
+        self.key.algorithm()
+    }
+}
+
+impl Serialize for PKAEnvelope {
+    fn serialize<S>( &self, serializer : S) -> Result<S::Ok, S::Error> where S : Serializer {
+        let mut s = serializer.serialize_struct( "PKAEnvelope", 2)?;
+
+        s.serialize_field( "key", &self.key)?;
+        s.serialize_field( "ciphertext", &serialize_base64url( &self.ciphertext))?;
+
+        s.end()
+    }
+}
+
+impl<'d> Deserialize<'d> for PKAEnvelope {
+    fn deserialize<D>( deserializer : D) -> Result<PKAEnvelope, D::Error> where D : Deserializer<'d> {
+        struct V;
+
+        const FIELDS : &'static [&'static str] = &["key", "ciphertext"];
+
+        impl<'d> Visitor<'d> for V {
+            type Value = PKAEnvelope;
+
+            fn expecting( &self, formatter : &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str( "JSON PKAEnvelope")
+            }
+
+            fn visit_map<U>( self, mut map : U) -> Result<PKAEnvelope, U::Error> where U : MapAccess<'d> {
+                let mut key = None;
+                let mut ciphertext = None;
+
+                while let Some(k) = map.next_key::<String>()? {
+                    match k.as_str() {
+                        "key" => {
+                            key.is_none().ok_or( de::Error::duplicate_field( "key"))?;
+                            key = Some( map.next_value()?);
+                        }
+                        "ciphertext" => {
+                            ciphertext.is_none().ok_or( de::Error::duplicate_field( "ciphertext"))?;
+                            ciphertext = Some( map.next_value()?);
+                        }
+                        k => {
+                            // Skip unknown fields, unless deserializing strictly.
+                            (!is_strict()).ok_or( de::Error::unknown_field( k, FIELDS))?;
+                            map.next_value::<de::IgnoredAny>()?;
+                        }
+                    }
+                }
+
+                let key = key.ok_or_else(|| de::Error::missing_field( "key"))?;
+                let ciphertext : String = ciphertext.ok_or_else(|| de::Error::missing_field( "ciphertext"))?;
+                let ciphertext = deserialize_base64url( &ciphertext).map_err( de::Error::custom)?;
+
+                Ok( PKAEnvelope{ key : key, ciphertext : ciphertext})
+            }
+        }
+
+        deserializer.deserialize_struct( "PKAEnvelope", FIELDS, V)
+    }
+}
+
+// The encapsulated key is nested as a map, like in JSON.
+impl EncodeCbor for PKAEnvelope {
+    fn encode_cbor( envelope : &PKAEnvelope) -> Vec<u8> {
+        cbor::encode( &CborValue::Map( vec![
+            (CborValue::Text( "key".to_string()), envelope.key.to_cbor_value()),
+            (CborValue::Text( "ciphertext".to_string()), CborValue::Bytes( envelope.ciphertext.clone())),
+        ]))
+    }
+}
+
+impl DecodeCbor for PKAEnvelope {
+    fn decode_cbor( bs : &[u8]) -> Result<PKAEnvelope, &'static str> {
+        let v = cbor::decode( bs)?;
+        let key = hpke::EncapsulatedKey::from_cbor_value( v.field( "key")?)?;
+        let ciphertext = v.field( "ciphertext")?.as_bytes().ok_or( "Invalid ciphertext.")?.to_vec();
+
+        Ok( PKAEnvelope{ key : key, ciphertext : ciphertext})
+    }
+}
+
+pub fn seal<T>( rng : &SystemRandom, alg : &hpke::Algorithm, sender : &aa::PrivateKey, recipient : &ae::PublicKey, o : &T) -> Result<PKAEnvelope, &'static str> where T : Serialize {
+    let r = serde_json::to_vec( &o).map_err(|_| "Error generating json.")?;
+    seal_content( rng, alg, sender, recipient, r)
+}
+
+pub fn open<T>( recipient : &ae::PrivateKey, envelope : PKAEnvelope) -> Result<(PKAIdentifier, T), &'static str> where T : DeserializeOwned {
+    let (sender, bs) = open_content( recipient, envelope)?;
+    let o = serde_json::from_slice( &bs).map_err(|_| "Invalid json encoding.")?;
+    Ok( (sender, o))
+}
+
+/// Open an envelope, rejecting algorithms the policy does not allow for decryption and verification.
+pub fn open_with_policy<T>( policy : &Policy, recipient : &ae::PrivateKey, envelope : PKAEnvelope) -> Result<(PKAIdentifier, T), &'static str> where T : DeserializeOwned {
+    let (sender, bs) = open_content_with_policy( policy, recipient, envelope)?;
+    let o = serde_json::from_slice( &bs).map_err(|_| "Invalid json encoding.")?;
+    Ok( (sender, o))
+}
+
+/// Open an envelope, rejecting recipient keys that are expired at the clock's time or are not for encryption.
+pub fn open_with_metadata<T, C>( clock : &C, recipient : &PKAKey<ae::PrivateKey>, envelope : PKAEnvelope) -> Result<(PKAIdentifier, T), &'static str> where T : DeserializeOwned, C : Clock {
+    let (sender, bs) = open_content_with_metadata( clock, recipient, envelope)?;
+    let o = serde_json::from_slice( &bs).map_err(|_| "Invalid json encoding.")?;
+    Ok( (sender, o))
+}
+
+/// Sign the content along with the recipient's identifier, then encrypt it and the sender's public key to the recipient.
+pub fn seal_content( rng : &SystemRandom, alg : &hpke::Algorithm, sender : &aa::PrivateKey, recipient : &ae::PublicKey, content : Vec<u8>) -> Result<PKAEnvelope, &'static str> {
+    let signed = aa::sign( sender, &json!({
+        "recipient": ToIdentifier::to_identifier( recipient),
+        "content": serialize_base64url( &content),
+    }))?;
+
+    let sender = ToPublicKey::to_public_key( sender);
+    let inner = serde_json::to_vec( &json!({
+        "sender": PKAJ{ pkaj : &sender},
+        "signed": signed,
+    })).map_err(|_| "Error generating json.")?;
+
+    let (key, ciphertext) = hpke::seal( rng, alg, recipient, &hpke::SenderMode::Base, INFO, &[], &inner)?;
+    Ok( PKAEnvelope{ key : key, ciphertext : ciphertext})
+}
+
+/// Decrypt an envelope and verify its signature. Returns the sender's identifier, which the caller must check is a sender it trusts.
+pub fn open_content( recipient : &ae::PrivateKey, envelope : PKAEnvelope) -> Result<(PKAIdentifier, Vec<u8>), &'static str> {
+    let inner = hpke::open( &envelope.key, recipient, &hpke::ReceiverMode::Base, INFO, &[], &envelope.ciphertext)?;
+    verify_inner( None, recipient, &inner)
+}
+
+pub fn open_content_with_policy( policy : &Policy, recipient : &ae::PrivateKey, envelope : PKAEnvelope) -> Result<(PKAIdentifier, Vec<u8>), &'static str> {
+    let inner = hpke::open_with_policy( policy, &envelope.key, recipient, &hpke::ReceiverMode::Base, INFO, &[], &envelope.ciphertext)?;
+    verify_inner( Some( policy), recipient, &inner)
+}
+
+pub fn open_content_with_metadata<C>( clock : &C, recipient : &PKAKey<ae::PrivateKey>, envelope : PKAEnvelope) -> Result<(PKAIdentifier, Vec<u8>), &'static str> where C : Clock {
+    recipient.metadata.check( clock, Usage::Encrypt)?;
+    open_content( &recipient.key, envelope)
+}
+
+fn verify_inner( policy : Option<&Policy>, recipient : &ae::PrivateKey, inner : &[u8]) -> Result<(PKAIdentifier, Vec<u8>), &'static str> {
+    let mut inner : Value = serde_json::from_slice( inner).map_err(|_| "Invalid envelope encoding.")?;
+
+    let sender : PKAJ<aa::PublicKey> = serde_json::from_value( inner["sender"].take()).map_err(|_| "Invalid sender public key.")?;
+    let signed : aa::PKASigned = serde_json::from_value( inner["signed"].take()).map_err(|_| "Invalid signed content.")?;
+    let payload : Value = match policy {
+        Some( policy) => aa::verify_with_policy( policy, &sender.pkaj, signed)?,
+        None => aa::verify( &sender.pkaj, signed)?,
+    };
+
+    // Check that the sender signed the content for this recipient.
+    let intended : PKAIdentifier = payload["recipient"].as_str().ok_or( "Missing recipient identifier.")?.parse()?;
+    intended.identifies( &ToPublicKey::to_public_key( recipient)).ok_or( "Envelope was signed for a different recipient.")?;
+
+    let content = payload["content"].as_str().ok_or( "Missing content.")?;
+    let content = deserialize_base64url( &content.to_string())?;
+
+    Ok( (ToIdentifier::to_identifier( &sender.pkaj), content))
+}
+
+pub fn seal_bs<T>( rng : &SystemRandom, alg : &hpke::Algorithm, sender : &aa::PrivateKey, recipient : &ae::PublicKey, o : &T) -> Result<Vec<u8>, &'static str> where T : Serialize {
+    let envelope = seal( rng, alg, sender, recipient, o)?;
+    serde_json::to_vec( &envelope).map_err(|_| "Error generating json.")
+}
+
+pub fn open_bs<T>( recipient : &ae::PrivateKey, envelope : &Vec<u8>) -> Result<(PKAIdentifier, T), &'static str> where T : DeserializeOwned {
+    let envelope = serde_json::from_slice( envelope).map_err(|_| "Invalid encoding.")?;
+    open( recipient, envelope)
+}
@@ -5,19 +5,28 @@ use boolinator::Boolinator;
 use crypto_abstract::{ToPublicKey};
 // use crypto_abstract::internal::asym::auth::ed25519;
 use crypto_abstract::asym::auth::{PublicKey, PrivateKey, Algorithm, Signature};
+use curve25519_dalek::edwards::CompressedEdwardsY;
+use ring::signature::Ed25519KeyPair;
 use serde::ser::{Serialize, Serializer, SerializeStruct};
 use serde::de;
 use serde::de::{Deserialize, Deserializer, MapAccess, Visitor};
 use std::fmt;
+use untrusted::Input;
 
-use {ToIdentifier, AlgorithmId, ToAlgorithm};
-use internal::{PKAIdentifier, generate_identifier, EncodePSF, DecodePSF, PKAJ, serialize_psf, deserialize_psf};
+use {ToIdentifier, ToVersionedIdentifier, AlgorithmId, ToAlgorithm};
+use internal::{AlgorithmStrength, AlgorithmTag, IdentifierType, KeyError, Validate, DecodeKey, PKAIdentifier, generate_identifier, generate_versioned_identifier, EncodePSF, DecodePSF, PKAJ, PKAJField, serialize_psf, deserialize_psf, is_strict};
+
+impl PKAJField for PublicKey {
+    fn pkaj_field() -> &'static str {
+        "public_key"
+    }
+}
 
 impl<'a> Serialize for PKAJ<&'a PublicKey> {
     fn serialize<S>( &self, serializer : S) -> Result<S::Ok, S::Error> where S : Serializer {
         let mut o = serializer.serialize_struct( "PublicKey", 2)?;
         
-        o.serialize_field( "public_key", &serialize_psf( self.pkaj))?;
+        o.serialize_field( <PublicKey as PKAJField>::pkaj_field(), &serialize_psf( self.pkaj))?;
         o.serialize_field( "algorithm", AlgorithmId::to_algorithm_id( &ToAlgorithm::to_algorithm( self.pkaj)))?;
 
         o.end()
@@ -50,10 +59,10 @@ impl<'d> Deserialize<'d> for PKAJ<PublicKey> {
                             public_key.is_none().ok_or( de::Error::duplicate_field( "public_key"))?;
                             public_key = Some( map.next_value()?);
                         }
-                        _k => {
-                            // Skip unknown fields.
-                            let _ : Result<(),U::Error> = map.next_value();
-                            // Err(de::Error::unknown_field(k, FIELDS))?;
+                        k => {
+                            // Skip unknown fields, unless deserializing strictly.
+                            (!is_strict()).ok_or( de::Error::unknown_field( k, FIELDS))?;
+                            map.next_value::<de::IgnoredAny>()?;
                         }
                     }
                 }
@@ -72,11 +81,17 @@ impl<'d> Deserialize<'d> for PKAJ<PublicKey> {
     }
 }
 
+impl PKAJField for PrivateKey {
+    fn pkaj_field() -> &'static str {
+        "private_key"
+    }
+}
+
 impl<'a> Serialize for PKAJ<&'a PrivateKey> {
     fn serialize<S>( &self, serializer : S) -> Result<S::Ok, S::Error> where S : Serializer {
         let mut o = serializer.serialize_struct( "PrivateKey", 2)?;
 
-        o.serialize_field( "private_key", &serialize_psf( self.pkaj))?;
+        o.serialize_field( <PrivateKey as PKAJField>::pkaj_field(), &serialize_psf( self.pkaj))?;
         o.serialize_field( "algorithm", AlgorithmId::to_algorithm_id( &ToAlgorithm::to_algorithm( self.pkaj)))?;
 
         o.end()
@@ -109,10 +124,10 @@ impl<'d> Deserialize<'d> for PKAJ<PrivateKey> {
                             algorithm.is_none().ok_or( de::Error::duplicate_field( "algorithm"))?;
                             algorithm = Some( map.next_value()?);
                         }
-                        _k => {
-                            // Skip unknown fields.
-                            let _ : Result<(),U::Error> = map.next_value();
-                            // Err(de::Error::unknown_field(k, FIELDS))?;
+                        k => {
+                            // Skip unknown fields, unless deserializing strictly.
+                            (!is_strict()).ok_or( de::Error::unknown_field( k, FIELDS))?;
+                            map.next_value::<de::IgnoredAny>()?;
                         }
                     }
                 }
@@ -131,6 +146,14 @@ impl<'d> Deserialize<'d> for PKAJ<PrivateKey> {
     }
 }
 
+impl<'a> From<&'a Algorithm> for IdentifierType {
+    fn from( alg : &'a Algorithm) -> IdentifierType {
+        match *alg {
+            Algorithm::AAEd25519 => IdentifierType::AAEd25519,
+        }
+    }
+}
+
 impl ToIdentifier for PublicKey {
     fn to_identifier( key : &PublicKey) -> PKAIdentifier {
         let serialized = EncodePSF::encode_psf( key);
@@ -139,12 +162,27 @@ impl ToIdentifier for PublicKey {
     }
 }
 
+impl ToVersionedIdentifier for PublicKey {
+    fn to_versioned_identifier( key : &PublicKey) -> PKAIdentifier {
+        let typ = IdentifierType::from( &ToAlgorithm::to_algorithm( key));
+        let serialized = EncodePSF::encode_psf( key);
+
+        generate_versioned_identifier( typ, serialized)
+    }
+}
+
 impl ToIdentifier for PrivateKey {
     fn to_identifier( key : &PrivateKey) -> PKAIdentifier {
         ToIdentifier::to_identifier( &ToPublicKey::to_public_key( key))
     }
 }
 
+impl ToVersionedIdentifier for PrivateKey {
+    fn to_versioned_identifier( key : &PrivateKey) -> PKAIdentifier {
+        ToVersionedIdentifier::to_versioned_identifier( &ToPublicKey::to_public_key( key))
+    }
+}
+
 const PRIVATEKEYPOSITION : usize = 16;
 const PRIVATEKEYLENGTH : usize = 32;
 const PUBLICKEYPOSITION : usize = 53;
@@ -191,9 +229,17 @@ impl DecodePSF for PrivateKey {
     type Algorithm = Algorithm;
 
     fn decode_psf( alg : &Algorithm, psf : &Vec<u8>) -> Result<PrivateKey, &'static str> where Self : Sized {
+        Ok( DecodeKey::decode_key( alg, psf)?)
+    }
+}
+
+impl DecodeKey for PrivateKey {
+    type Algorithm = Algorithm;
+
+    fn decode_key( alg : &Algorithm, psf : &Vec<u8>) -> Result<PrivateKey, KeyError> where Self : Sized {
         match *alg {
             Algorithm::AAEd25519 => {
-                (psf.len() == PUBLICKEYLENGTH + PRIVATEKEYLENGTH).ok_or("Private key is wrong length.")?;
+                (psf.len() == PUBLICKEYLENGTH + PRIVATEKEYLENGTH).ok_or( KeyError::WrongLength)?;
 
                 // Prefill key with pkcs8 constants (used by ring).
                 let mut key : [u8; 85] = [ 
@@ -220,13 +266,62 @@ impl DecodePSF for PrivateKey {
                         *place = *element;
                     }
                 }
-                
-                Ok( PrivateKey::AAEd25519( key))
+
+                let key = PrivateKey::AAEd25519( key);
+                key.validate()?;
+                Ok( key)
+            }
+        }
+    }
+}
+
+impl Validate for PublicKey {
+    fn validate( &self) -> Result<(), KeyError> {
+        match *self {
+            PublicKey::AAEd25519( key) => {
+                let point = CompressedEdwardsY( key).decompress().ok_or( KeyError::InvalidPoint)?;
+                (!point.is_small_order()).ok_or( KeyError::SmallOrder)
+            }
+        }
+    }
+}
+
+// The embedded public key `A` must be derived from the seed `k`.
+impl Validate for PrivateKey {
+    fn validate( &self) -> Result<(), KeyError> {
+        match ToAlgorithm::to_algorithm( self) {
+            Algorithm::AAEd25519 => {
+                let psf = EncodePSF::encode_psf( self);
+                let pair = Ed25519KeyPair::from_seed_unchecked( Input::from( &psf[0 .. PRIVATEKEYLENGTH])).map_err(|_| KeyError::PublicKeyMismatch)?;
+                (pair.public_key_bytes() == &psf[PRIVATEKEYLENGTH ..]).ok_or( KeyError::PublicKeyMismatch)
             }
         }
     }
 }
 
+/// Build a private key from a 32 byte seed (`k` for Ed25519).
+pub fn private_key_from_seed( alg : &Algorithm, seed : &[u8]) -> Result<PrivateKey, &'static str> {
+    match *alg {
+        Algorithm::AAEd25519 => {
+            (seed.len() == PRIVATEKEYLENGTH).ok_or( "Seed is wrong length.")?;
+
+            let pair = Ed25519KeyPair::from_seed_unchecked( Input::from( seed)).map_err(|_| "Invalid seed.")?;
+
+            let mut psf = seed.to_vec();
+            psf.extend( pair.public_key_bytes());
+
+            DecodePSF::decode_psf( alg, &psf)
+        }
+    }
+}
+
+/// Extract the 32 byte seed (`k` for Ed25519) of a private key.
+pub fn private_key_seed( key : &PrivateKey) -> Vec<u8> {
+    let mut psf = EncodePSF::encode_psf( key);
+    psf.truncate( PRIVATEKEYLENGTH);
+    psf
+}
+
 // JP: Should we get these constants from crypto-abstract? I don't want to expose them there though.
 
 impl EncodePSF for PublicKey {
@@ -248,16 +343,26 @@ impl DecodePSF for PublicKey {
     type Algorithm = Algorithm;
 
     fn decode_psf( alg : &Algorithm, psf : &Vec<u8>) -> Result<PublicKey, &'static str> where Self : Sized {
+        Ok( DecodeKey::decode_key( alg, psf)?)
+    }
+}
+
+impl DecodeKey for PublicKey {
+    type Algorithm = Algorithm;
+
+    fn decode_key( alg : &Algorithm, psf : &Vec<u8>) -> Result<PublicKey, KeyError> where Self : Sized {
         match *alg {
             Algorithm::AAEd25519 => {
-                (psf.len() == PUBLICKEYLENGTH).ok_or( "Public key is wrong length")?;
+                (psf.len() == PUBLICKEYLENGTH).ok_or( KeyError::WrongLength)?;
 
                 let mut public_key = [0u8; PUBLICKEYLENGTH];
                 for (place, element) in public_key.iter_mut().zip( psf.into_iter()) {
                     *place = *element;
                 }
 
-                Ok( PublicKey::AAEd25519( public_key))
+                let public_key = PublicKey::AAEd25519( public_key);
+                public_key.validate()?;
+                Ok( public_key)
             }
         }
     }
@@ -317,3 +422,32 @@ impl AlgorithmId for Algorithm {
     }
 }
 
+impl AlgorithmStrength for Algorithm {
+    fn key_bits( alg : &Algorithm) -> usize {
+        match *alg {
+            Algorithm::AAEd25519 => 256
+        }
+    }
+
+    fn is_aead( alg : &Algorithm) -> bool {
+        match *alg {
+            Algorithm::AAEd25519 => false
+        }
+    }
+}
+
+impl AlgorithmTag for Algorithm {
+    fn to_algorithm_tag( alg : &Algorithm) -> u8 {
+        match *alg {
+            Algorithm::AAEd25519 => 0x01
+        }
+    }
+
+    fn from_algorithm_tag( tag : u8) -> Option<Self> {
+        match tag {
+            0x01 => Some( Algorithm::AAEd25519),
+            _ => None
+        }
+    }
+}
+
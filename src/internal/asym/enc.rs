@@ -1,22 +1,35 @@
 
 use boolinator::Boolinator;
+use crypto_abstract::ToPublicKey;
+use crypto_abstract::asym::auth;
 use crypto_abstract::asym::enc;
+use curve25519_dalek::edwards::CompressedEdwardsY;
+use curve25519_dalek::montgomery::MontgomeryPoint;
+use curve25519_dalek::scalar::Scalar;
 use crypto_abstract::asym::enc::{PublicKey, Algorithm, PrivateKey}; // , CipherText};
 use ring::agreement::{ReusablePrivateKey, X25519};
+use ring::digest::{digest, SHA512};
 use serde::ser::{Serialize, Serializer, SerializeStruct};
 use serde::de;
 use serde::de::{Deserialize, Deserializer, MapAccess, Visitor};
 use std::fmt;
 use untrusted::Input;
 
-use internal::{AlgorithmId, EncodePSF, DecodePSF, PKAJ, serialize_psf, deserialize_psf, u8_to_fixed_length_32}; // ToIdentifier, PKAIdentifier, 
+use internal::asym::auth::private_key_seed;
+use internal::{AlgorithmId, AlgorithmStrength, AlgorithmTag, KeyError, Validate, DecodeKey, EncodePSF, DecodePSF, PKAJ, PKAJField, serialize_psf, deserialize_psf, is_strict, u8_to_fixed_length_32, ToIdentifier, ToVersionedIdentifier, IdentifierType, PKAIdentifier, generate_identifier, generate_versioned_identifier};
 use ToAlgorithm;
 
+impl PKAJField for PublicKey {
+    fn pkaj_field() -> &'static str {
+        "public_key"
+    }
+}
+
 impl<'a> Serialize for PKAJ<&'a PublicKey> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S:Serializer {
         let mut o = serializer.serialize_struct("PublicKey", 2)?;
 
-        o.serialize_field( "public_key", &serialize_psf( self.pkaj))?;
+        o.serialize_field( <PublicKey as PKAJField>::pkaj_field(), &serialize_psf( self.pkaj))?;
         o.serialize_field( "algorithm", AlgorithmId::to_algorithm_id( &ToAlgorithm::to_algorithm( self.pkaj)))?;
 
         o.end()
@@ -49,10 +62,10 @@ impl<'d> Deserialize<'d> for PKAJ<PublicKey> {
                             key.is_none().ok_or( de::Error::duplicate_field( "key"))?;
                             key = Some( map.next_value()?);
                         }
-                        _k => {
-                            // Skip unknown fields.
-                            let _ : Result<(),U::Error> = map.next_value();
-                            // Err(de::Error::unknown_field(k, FIELDS))?;
+                        k => {
+                            // Skip unknown fields, unless deserializing strictly.
+                            (!is_strict()).ok_or( de::Error::unknown_field( k, FIELDS))?;
+                            map.next_value::<de::IgnoredAny>()?;
                         }
                     }
                 }
@@ -71,6 +84,43 @@ impl<'d> Deserialize<'d> for PKAJ<PublicKey> {
     }
 }
 
+impl<'a> From<&'a Algorithm> for IdentifierType {
+    fn from( alg : &'a Algorithm) -> IdentifierType {
+        match *alg {
+            Algorithm::AEX25519 => IdentifierType::AEX25519,
+        }
+    }
+}
+
+impl ToIdentifier for PublicKey {
+    fn to_identifier( key : &PublicKey) -> PKAIdentifier {
+        let serialized = EncodePSF::encode_psf( key);
+
+        generate_identifier( serialized)
+    }
+}
+
+impl ToVersionedIdentifier for PublicKey {
+    fn to_versioned_identifier( key : &PublicKey) -> PKAIdentifier {
+        let typ = IdentifierType::from( &ToAlgorithm::to_algorithm( key));
+        let serialized = EncodePSF::encode_psf( key);
+
+        generate_versioned_identifier( typ, serialized)
+    }
+}
+
+impl ToIdentifier for PrivateKey {
+    fn to_identifier( key : &PrivateKey) -> PKAIdentifier {
+        ToIdentifier::to_identifier( &ToPublicKey::to_public_key( key))
+    }
+}
+
+impl ToVersionedIdentifier for PrivateKey {
+    fn to_versioned_identifier( key : &PrivateKey) -> PKAIdentifier {
+        ToVersionedIdentifier::to_versioned_identifier( &ToPublicKey::to_public_key( key))
+    }
+}
+
 impl EncodePSF for PublicKey {
     fn encode_psf( key : &PublicKey) -> Vec<u8> {
         match *key {
@@ -86,16 +136,100 @@ impl DecodePSF for PublicKey {
     type Algorithm = enc::Algorithm;
 
     fn decode_psf( alg : &Algorithm, psf : &Vec<u8>) -> Result<PublicKey, &'static str> where Self : Sized {
+        Ok( DecodeKey::decode_key( alg, psf)?)
+    }
+}
+
+impl DecodeKey for PublicKey {
+    type Algorithm = enc::Algorithm;
+
+    fn decode_key( alg : &Algorithm, psf : &Vec<u8>) -> Result<PublicKey, KeyError> where Self : Sized {
         match alg {
             &Algorithm::AEX25519 => {
-                let key = u8_to_fixed_length_32( psf).ok_or("Public key is wrong length.")?;
-                
-                // TODO: test this XXX
-                Ok( PublicKey::AEX25519( key))
+                let key = u8_to_fixed_length_32( psf).ok_or( KeyError::WrongLength)?;
+
+                let key = PublicKey::AEX25519( key);
+                key.validate()?;
+                Ok( key)
             }
         }
     }
 }
+
+// Points of small order, on the curve or its twist, are cleared by the cofactor.
+impl Validate for PublicKey {
+    fn validate( &self) -> Result<(), KeyError> {
+        match *self {
+            PublicKey::AEX25519( key) => {
+                let cleared = &MontgomeryPoint( key) * &Scalar::from( 8u8);
+                (cleared.to_bytes() != [0u8; 32]).ok_or( KeyError::SmallOrder)
+            }
+        }
+    }
+}
+
+// Every 32 byte scalar is a valid X25519 private key.
+impl Validate for PrivateKey {
+    fn validate( &self) -> Result<(), KeyError> {
+        Ok(())
+    }
+}
+
+/// Build a private key from a 32 byte seed (the X25519 scalar).
+pub fn private_key_from_seed( alg : &Algorithm, seed : &[u8]) -> Result<PrivateKey, &'static str> {
+    match alg {
+        &Algorithm::AEX25519 => {
+            (seed.len() == 32).ok_or( "Seed is wrong length.")?;
+
+            DecodePSF::decode_psf( alg, &seed.to_vec())
+        }
+    }
+}
+
+/// Convert an Ed25519 private key to the X25519 private key with the same secret scalar.
+pub fn private_key_from_ed25519( key : &auth::PrivateKey) -> Result<PrivateKey, &'static str> {
+    match ToAlgorithm::to_algorithm( key) {
+        auth::Algorithm::AAEd25519 => {
+            // The scalar is the clamped first half of SHA512(seed), as in RFC8032.
+            let h = digest( &SHA512, &private_key_seed( key));
+            let mut scalar = u8_to_fixed_length_32( &h.as_ref()[0 .. 32]).ok_or( "Invalid Ed25519 scalar.")?;
+            scalar[0] &= 248;
+            scalar[31] &= 127;
+            scalar[31] |= 64;
+
+            private_key_from_seed( &Algorithm::AEX25519, &scalar)
+        }
+    }
+}
+
+/// Convert an Ed25519 public key to an X25519 public key with the birational map u = (1 + y) / (1 - y).
+pub fn public_key_from_ed25519( key : &auth::PublicKey) -> Result<PublicKey, &'static str> {
+    match *key {
+        auth::PublicKey::AAEd25519( key) => {
+            let point = CompressedEdwardsY( key).decompress().ok_or( "Invalid Ed25519 public key.")?;
+            (!point.is_small_order()).ok_or( "Ed25519 public key has small order.")?;
+
+            Ok( PublicKey::AEX25519( point.to_montgomery().to_bytes()))
+        }
+    }
+}
+
+/// X25519 shared secret of a private key and a peer's public key. Rejects the all-zero result of small order peer keys.
+pub fn agree_x25519( key : &PrivateKey, peer : &PublicKey) -> Result<[u8; 32], &'static str> {
+    match (key, peer) {
+        (&PrivateKey::AEX25519( ref key), &PublicKey::AEX25519( peer)) => {
+            let scalar = u8_to_fixed_length_32( key.private_key_bytes()).ok_or( "Private key is wrong length.")?;
+            let shared = MontgomeryPoint( peer).mul_clamped( scalar).to_bytes();
+
+            // Constant time check for the all-zero shared secret.
+            let zero = shared.iter().fold( 0u8, |acc, b| acc | b) == 0;
+            (!zero).ok_or( "Peer public key has small order.")?;
+
+            Ok( shared)
+        }
+    }
+}
+
 impl AlgorithmId for Algorithm {
     fn to_algorithm_id( alg : &Algorithm) -> &'static str {
         match *alg {
@@ -112,11 +246,47 @@ impl AlgorithmId for Algorithm {
     }
 }
 
+// X25519 keys only encrypt with an AEAD, through HPKE, boxes or age.
+impl AlgorithmStrength for Algorithm {
+    fn key_bits( alg : &Algorithm) -> usize {
+        match *alg {
+            Algorithm::AEX25519 => 256
+        }
+    }
+
+    fn is_aead( alg : &Algorithm) -> bool {
+        match *alg {
+            Algorithm::AEX25519 => true
+        }
+    }
+}
+
+impl AlgorithmTag for Algorithm {
+    fn to_algorithm_tag( alg : &Algorithm) -> u8 {
+        match *alg {
+            Algorithm::AEX25519 => 0x02
+        }
+    }
+
+    fn from_algorithm_tag( tag : u8) -> Option<Self> {
+        match tag {
+            0x02 => Some( Algorithm::AEX25519),
+            _ => None
+        }
+    }
+}
+
+impl PKAJField for PrivateKey {
+    fn pkaj_field() -> &'static str {
+        "private_key"
+    }
+}
+
 impl<'a> Serialize for PKAJ<&'a PrivateKey> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S:Serializer {
         let mut o = serializer.serialize_struct("PrivateKey", 2)?;
 
-        o.serialize_field( "private_key", &serialize_psf( self.pkaj))?;
+        o.serialize_field( <PrivateKey as PKAJField>::pkaj_field(), &serialize_psf( self.pkaj))?;
         o.serialize_field( "algorithm", AlgorithmId::to_algorithm_id( &ToAlgorithm::to_algorithm( self.pkaj)))?;
 
         o.end()
@@ -149,10 +319,10 @@ impl<'d> Deserialize<'d> for PKAJ<PrivateKey> {
                             key.is_none().ok_or( de::Error::duplicate_field( "key"))?;
                             key = Some( map.next_value()?);
                         }
-                        _k => {
-                            // Skip unknown fields.
-                            let _ : Result<(),U::Error> = map.next_value();
-                            // Err(de::Error::unknown_field(k, FIELDS))?;
+                        k => {
+                            // Skip unknown fields, unless deserializing strictly.
+                            (!is_strict()).ok_or( de::Error::unknown_field( k, FIELDS))?;
+                            map.next_value::<de::IgnoredAny>()?;
                         }
                     }
                 }
@@ -186,12 +356,18 @@ impl DecodePSF for PrivateKey {
     type Algorithm = enc::Algorithm;
 
     fn decode_psf( alg : &Algorithm, psf : &Vec<u8>) -> Result<PrivateKey, &'static str> where Self : Sized {
+        Ok( DecodeKey::decode_key( alg, psf)?)
+    }
+}
+
+// Every 32 byte scalar is a valid key, so ring only rejects the length.
+impl DecodeKey for PrivateKey {
+    type Algorithm = enc::Algorithm;
+
+    fn decode_key( alg : &Algorithm, psf : &Vec<u8>) -> Result<PrivateKey, KeyError> where Self : Sized {
         match alg {
             &Algorithm::AEX25519 => {
-                // JP: Validate length and copy?
-                // let key = u8_to_fixed_length_32( psf).ok_or("Private key is wrong length.")?;
-                    
-                let key = ReusablePrivateKey::from_bytes( &X25519, Input::from( &psf)).or( Err("Invalid private key."))?;
+                let key = ReusablePrivateKey::from_bytes( &X25519, Input::from( &psf)).or( Err( KeyError::WrongLength))?;
 
                 // TODO: test this XXX
                 Ok( PrivateKey::AEX25519( key))
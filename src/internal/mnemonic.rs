@@ -0,0 +1,96 @@
+// Mnemonic phrases compatible with BIP39: https://github.com/bitcoin/bips/blob/master/bip-0039.mediawiki
+
+use boolinator::Boolinator;
+use ring::digest::{digest, SHA256};
+
+use internal::AlgorithmId;
+
+// BIP39 English wordlist (sorted).
+const WORDLIST : &'static str = include_str!( "english.txt");
+const BITSPERWORD : usize = 11;
+
+fn wordlist() -> Vec<&'static str> {
+    WORDLIST.lines().collect()
+}
+
+/// Encode entropy (16 to 32 bytes, a multiple of 4) as BIP39 words.
+pub fn entropy_to_words( entropy : &[u8]) -> Result<Vec<&'static str>, &'static str> {
+    (entropy.len() >= 16 && entropy.len() <= 32 && entropy.len() % 4 == 0).ok_or( "Entropy is wrong length.")?;
+
+    // Append the first ENT/32 bits of SHA256(entropy) as a checksum.
+    let checksum = digest( &SHA256, entropy).as_ref()[0];
+    let checksum_length = entropy.len() / 4;
+    let mut bits = to_bits( entropy);
+    bits.extend( to_bits( &[checksum]).into_iter().take( checksum_length));
+
+    let wordlist = wordlist();
+    let words = bits.chunks( BITSPERWORD).map(|chunk| {
+        let i = chunk.iter().fold( 0, |acc, b| (acc << 1) | (*b as usize));
+        wordlist[i]
+    }).collect();
+
+    Ok( words)
+}
+
+/// Decode BIP39 words back to their entropy, validating the checksum.
+pub fn words_to_entropy( words : &[&str]) -> Result<Vec<u8>, &'static str> {
+    (words.len() >= 12 && words.len() <= 24 && words.len() % 3 == 0).ok_or( "Mnemonic has the wrong number of words.")?;
+
+    let wordlist = wordlist();
+    let mut bits = Vec::with_capacity( words.len() * BITSPERWORD);
+    for word in words {
+        let i = wordlist.binary_search( word).map_err(|_| "Unknown mnemonic word.")?;
+        for j in (0 .. BITSPERWORD).rev() {
+            bits.push( (i >> j) & 1 == 1);
+        }
+    }
+
+    let checksum_length = bits.len() / 33;
+    let entropy_length = bits.len() - checksum_length;
+    let entropy : Vec<u8> = bits[0 .. entropy_length].chunks( 8).map(|byte| {
+        byte.iter().fold( 0, |acc, b| (acc << 1) | (*b as u8))
+    }).collect();
+
+    let checksum = digest( &SHA256, &entropy).as_ref()[0];
+    (to_bits( &[checksum])[0 .. checksum_length] == bits[entropy_length ..]).ok_or( "Invalid mnemonic checksum.")?;
+
+    Ok( entropy)
+}
+
+fn to_bits( bytes : &[u8]) -> Vec<bool> {
+    let mut bits = Vec::with_capacity( bytes.len() * 8);
+    for byte in bytes {
+        for j in (0 .. 8).rev() {
+            bits.push( (byte >> j) & 1 == 1);
+        }
+    }
+    bits
+}
+
+/// Encode a key seed as a mnemonic phrase, prefixed with its algorithm identifier.
+/// The words after the prefix are a standard BIP39 mnemonic of the seed.
+pub fn encode_mnemonic<A>( algorithm : &A, seed : &[u8]) -> Result<String, &'static str> where A : AlgorithmId {
+    let words = entropy_to_words( seed)?;
+
+    let mut phrase = AlgorithmId::to_algorithm_id( algorithm).to_owned();
+    for word in words {
+        phrase.push( ' ');
+        phrase.push_str( word);
+    }
+
+    Ok( phrase)
+}
+
+/// Decode a mnemonic phrase to its algorithm and seed. Case and whitespace are ignored.
+pub fn decode_mnemonic<A>( phrase : &str) -> Result<(A, Vec<u8>), &'static str> where A : AlgorithmId {
+    let phrase = phrase.to_lowercase();
+    let mut tokens = phrase.split_whitespace();
+
+    let algorithm = tokens.next().ok_or( "Empty mnemonic.")?;
+    let algorithm = AlgorithmId::from_algorithm_id( algorithm).ok_or( "Invalid mnemonic algorithm identifier.")?;
+
+    let words : Vec<&str> = tokens.collect();
+    let seed = words_to_entropy( &words)?;
+
+    Ok( (algorithm, seed))
+}
@@ -1,13 +1,22 @@
 
 pub mod asym;
+pub mod mnemonic;
 pub mod sym;
 
 use base64;
 use boolinator::Boolinator;
+use ring::constant_time::verify_slices_are_equal;
 use ring::digest::{digest, SHA256};
 use ripemd160::{Ripemd160, Digest};
-use rust_base58::base58::{ToBase58};
-// use serde::ser::{Serialize, Serializer};
+use rust_base58::base58::{FromBase58, ToBase58};
+use serde::de;
+use serde::de::{Deserialize, Deserializer, DeserializeOwned};
+use serde::ser::{Serialize, Serializer};
+use serde_json;
+use std::cell::Cell;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
 
 /// Newtype wrapper for JSON in PKAuth form since we can't create `Serialize` instances due
 /// to orphan instances.
@@ -15,7 +24,166 @@ pub struct PKAJ<T> {
     pub pkaj : T
 }
 
-pub type PKAIdentifier = String;
+thread_local! {
+    static STRICT : Cell<bool> = Cell::new( false);
+}
+
+const IDENTIFIERLENGTH : usize = 20;
+const CHECKSUMLENGTH : usize = 4;
+
+/// Type of key named by a versioned identifier. Its byte is prefixed to the identifier, like a Base58Check version byte.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IdentifierType {
+    AAEd25519,
+    AEX25519,
+    SEAesGcm256,
+}
+
+impl IdentifierType {
+    pub fn to_byte( &self) -> u8 {
+        match *self {
+            IdentifierType::AAEd25519 => 0x01,
+            IdentifierType::AEX25519 => 0x02,
+            IdentifierType::SEAesGcm256 => 0x03,
+        }
+    }
+
+    pub fn from_byte( b : u8) -> Option<IdentifierType> {
+        match b {
+            0x01 => Some( IdentifierType::AAEd25519),
+            0x02 => Some( IdentifierType::AEX25519),
+            0x03 => Some( IdentifierType::SEAesGcm256),
+            _ => None
+        }
+    }
+
+    /// Algorithm identifier of the key type.
+    pub fn algorithm_id( &self) -> &'static str {
+        match *self {
+            IdentifierType::AAEd25519 => "aa-ed25519",
+            IdentifierType::AEX25519 => "ae-x25519",
+            IdentifierType::SEAesGcm256 => "se-aesgcm256",
+        }
+    }
+
+    pub fn from_algorithm_id( id : &str) -> Option<IdentifierType> {
+        match id {
+            "aa-ed25519" => Some( IdentifierType::AAEd25519),
+            "ae-x25519" => Some( IdentifierType::AEX25519),
+            "se-aesgcm256" => Some( IdentifierType::SEAesGcm256),
+            _ => None
+        }
+    }
+}
+
+/// Identifier of a key.
+///
+/// Legacy identifiers are the Base58 encoding of RIPEMD160(SHA256(PSF of the key)) followed by a 4 byte checksum.
+/// Versioned identifiers prefix a type byte to the PSF before hashing, and to the hash before the checksum and encoding.
+#[derive(Clone)]
+pub struct PKAIdentifier {
+    identifier : Vec<u8>
+}
+
+impl PKAIdentifier {
+    /// Raw bytes of the identifier, including the type byte and checksum.
+    pub fn as_bytes( &self) -> &[u8] {
+        &self.identifier
+    }
+
+    /// Load an identifier from its raw bytes, checking its type byte and checksum.
+    pub fn from_bytes( identifier : &[u8]) -> Result<PKAIdentifier, &'static str> {
+        let hash_length = if identifier.len() == IDENTIFIERLENGTH + CHECKSUMLENGTH {
+            IDENTIFIERLENGTH
+        }
+        else if identifier.len() == 1 + IDENTIFIERLENGTH + CHECKSUMLENGTH {
+            IdentifierType::from_byte( identifier[0]).ok_or( "Unknown identifier type.")?;
+            1 + IDENTIFIERLENGTH
+        }
+        else {
+            return Err( "Identifier is wrong length.")
+        };
+
+        let checksum = checksum_identifier( &identifier[0 .. hash_length].to_vec());
+        verify_slices_are_equal( &checksum, &identifier[hash_length ..]).map_err(|_| "Invalid identifier checksum.")?;
+
+        Ok( PKAIdentifier{ identifier : identifier.to_vec()})
+    }
+
+    /// Type of key this identifier names. Legacy identifiers do not record it.
+    pub fn key_type( &self) -> Option<IdentifierType> {
+        if self.is_legacy() {
+            None
+        }
+        else {
+            IdentifierType::from_byte( self.identifier[0])
+        }
+    }
+
+    pub fn is_legacy( &self) -> bool {
+        self.identifier.len() == IDENTIFIERLENGTH + CHECKSUMLENGTH
+    }
+
+    /// Check whether this identifier names the key, using the scheme the identifier was generated with.
+    pub fn identifies<K>( &self, key : &K) -> bool where K : ToIdentifier + ToVersionedIdentifier {
+        if self.is_legacy() {
+            *self == ToIdentifier::to_identifier( key)
+        }
+        else {
+            *self == ToVersionedIdentifier::to_versioned_identifier( key)
+        }
+    }
+}
+
+impl FromStr for PKAIdentifier {
+    type Err = &'static str;
+
+    fn from_str( s : &str) -> Result<PKAIdentifier, &'static str> {
+        let identifier = s.from_base58().map_err(|_| "Invalid Base58 encoding.")?;
+
+        PKAIdentifier::from_bytes( &identifier)
+    }
+}
+
+impl fmt::Display for PKAIdentifier {
+    fn fmt( &self, f : &mut fmt::Formatter) -> fmt::Result {
+        f.write_str( &self.identifier.to_base58())
+    }
+}
+
+impl fmt::Debug for PKAIdentifier {
+    fn fmt( &self, f : &mut fmt::Formatter) -> fmt::Result {
+        write!( f, "PKAIdentifier({})", self)
+    }
+}
+
+// Compare in constant time.
+impl PartialEq for PKAIdentifier {
+    fn eq( &self, other : &PKAIdentifier) -> bool {
+        verify_slices_are_equal( &self.identifier, &other.identifier).is_ok()
+    }
+}
+
+impl Eq for PKAIdentifier {}
+
+impl Hash for PKAIdentifier {
+    fn hash<H>( &self, state : &mut H) where H : Hasher {
+        self.identifier.hash( state)
+    }
+}
+
+impl Serialize for PKAIdentifier {
+    fn serialize<S>( &self, serializer : S) -> Result<S::Ok, S::Error> where S : Serializer {
+        serializer.serialize_str( &self.to_string())
+    }
+}
+
+impl<'d> Deserialize<'d> for PKAIdentifier {
+    fn deserialize<D>( deserializer : D) -> Result<PKAIdentifier, D::Error> where D : Deserializer<'d> {
+        let s = String::deserialize( deserializer)?;
+        s.parse().map_err( de::Error::custom)
+    }
+}
 
 // JP: Can we revert back to this version of serialize_psf?
 // pub fn serialize_psf<S,T>( o : &T, serializer : S) -> Result<S::Ok, S::Error> where S : Serializer, T : EncodePSF {
@@ -32,7 +200,7 @@ pub fn serialize_psf<T>( o : &T) -> String where T : EncodePSF {
 
 // JP: How do we return an error (de::Error::custom)?
 pub fn deserialize_psf<T>( algorithm : &T::Algorithm, s : &String) -> Result<T,&'static str> where T : DecodePSF {
-    let ciphertext = base64::decode_config( &s, base64::URL_SAFE).map_err(|_| "invalid Base64Url encoding")?;
+    let ciphertext = deserialize_base64url( s)?;
     DecodePSF::decode_psf( algorithm, &ciphertext)
 }
 
@@ -40,8 +208,47 @@ pub fn serialize_base64url( bs : &Vec<u8>) -> String {
     base64::encode_config( &bs, base64::URL_SAFE)
 }
 
+/// Decode base64url. When deserializing strictly, only the padded encoding `serialize_base64url` produces is accepted.
 pub fn deserialize_base64url( s : &String) -> Result<Vec<u8>,&'static str> {
-    base64::decode_config( &s, base64::URL_SAFE).map_err(|_| "invalid Base64Url encoding")
+    let bs = base64::decode_config( &s, base64::URL_SAFE).map_err(|_| "invalid Base64Url encoding")?;
+    (!is_strict() || serialize_base64url( &bs) == *s).ok_or( "non-canonical Base64Url encoding")?;
+    Ok( bs)
+}
+
+/// Whether the current thread is deserializing strictly.
+pub fn is_strict() -> bool {
+    STRICT.with(|s| s.get())
+}
+
+// Restores the previous strictness when dropped, even if deserialization panics.
+struct StrictGuard {
+    previous : bool
+}
+
+impl Drop for StrictGuard {
+    fn drop( &mut self) {
+        let previous = self.previous;
+        STRICT.with(|s| s.set( previous));
+    }
+}
+
+/// Run a function with strict deserialization enabled on the current thread.
+pub fn strict<F, R>( f : F) -> R where F : FnOnce() -> R {
+    let _guard = StrictGuard{ previous : STRICT.with(|s| s.replace( true))};
+    f()
+}
+
+/// Deserialize json strictly, rejecting unknown fields, non-canonical base64url, and trailing data other than whitespace. Deserialization is lenient by default.
+pub fn from_slice_strict<T>( bs : &[u8]) -> Result<T, &'static str> where T : DeserializeOwned {
+    let mut stream = serde_json::Deserializer::from_slice( bs).into_iter::<T>();
+    let o = strict(|| stream.next()).ok_or( "Missing json value.")?.map_err(|_| "Invalid json encoding.")?;
+    bs[stream.byte_offset() ..].iter().all(|b| *b == b' ' || *b == b'\t' || *b == b'\n' || *b == b'\r').ok_or( "Trailing data after json value.")?;
+    Ok( o)
+}
+
+/// Deserialize a json string strictly.
+pub fn from_str_strict<T>( s : &str) -> Result<T, &'static str> where T : DeserializeOwned {
+    from_slice_strict( s.as_bytes())
 }
 
 // impl<T> Serialize for PSF<T> {
@@ -74,10 +281,76 @@ pub trait AlgorithmId {
     fn from_algorithm_id( &str) -> Option<Self> where Self : Sized;
 }
 
+/// One byte algorithm tag used by the binary wire format. Tags match the type bytes of versioned identifiers.
+pub trait AlgorithmTag {
+    fn to_algorithm_tag( &Self) -> u8;
+    fn from_algorithm_tag( u8) -> Option<Self> where Self : Sized;
+}
+
+/// Security properties of an algorithm, which policies check.
+pub trait AlgorithmStrength {
+    /// Size of the algorithm's keys in bits.
+    fn key_bits( &Self) -> usize;
+    /// Whether messages encrypted with the algorithm's keys are authenticated. Signature algorithms do not encrypt, so they are not.
+    fn is_aead( &Self) -> bool;
+}
+
+/// Reason a key is invalid.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyError {
+    /// The key is not a point on its curve.
+    InvalidPoint,
+    /// The public key has small order, so it would leak or fix shared secrets.
+    SmallOrder,
+    /// The public key embedded in a private key is not derived from its secret.
+    PublicKeyMismatch,
+    /// The key's encoding is the wrong length for its algorithm.
+    WrongLength,
+}
+
+impl KeyError {
+    pub fn as_str( &self) -> &'static str {
+        match *self {
+            KeyError::InvalidPoint => "Public key is not a valid curve point.",
+            KeyError::SmallOrder => "Public key has small order.",
+            KeyError::PublicKeyMismatch => "Public key does not match private key.",
+            KeyError::WrongLength => "Key is wrong length.",
+        }
+    }
+}
+
+impl fmt::Display for KeyError {
+    fn fmt( &self, f : &mut fmt::Formatter) -> fmt::Result {
+        f.write_str( self.as_str())
+    }
+}
+
+impl From<KeyError> for &'static str {
+    fn from( e : KeyError) -> &'static str {
+        e.as_str()
+    }
+}
+
+/// Check the consistency of a loaded key. Keys are validated when decoded, but keys built by other means can be checked with this.
+pub trait Validate {
+    fn validate( &self) -> Result<(), KeyError>;
+}
+
 pub trait ToIdentifier {
+    /// Legacy identifier, compatible with other PKAuth implementations.
     fn to_identifier( &Self) -> PKAIdentifier;
 }
 
+pub trait ToVersionedIdentifier {
+    /// Versioned identifier, which records the type of key.
+    fn to_versioned_identifier( &Self) -> PKAIdentifier;
+}
+
+/// Name of the field that holds a key's PSF in its PKAJ object, ex `public_key`. The other field is `algorithm`.
+pub trait PKAJField {
+    fn pkaj_field() -> &'static str;
+}
+
 pub trait EncodePSF {
     fn encode_psf( &Self) -> Vec<u8> where Self : Sized;
 }
@@ -87,11 +360,28 @@ pub trait DecodePSF {
     fn decode_psf( &Self::Algorithm, &Vec<u8>) -> Result<Self,&'static str> where Self : Sized;
 }
 
+/// Decode and validate a key, returning the reason it is invalid. Keys implement `DecodePSF` with this.
+pub trait DecodeKey {
+    type Algorithm;
+    fn decode_key( &Self::Algorithm, &Vec<u8>) -> Result<Self, KeyError> where Self : Sized;
+}
+
 pub fn generate_identifier( raw : Vec<u8>) -> PKAIdentifier {
     let mut hash = ripemd160( &sha256( &raw));
     let checksum = checksum_identifier( &hash);
     hash.extend( checksum);
-    hash.to_base58()
+    PKAIdentifier{ identifier : hash}
+}
+
+pub fn generate_versioned_identifier( typ : IdentifierType, raw : Vec<u8>) -> PKAIdentifier {
+    let mut prefixed = vec![ typ.to_byte()];
+    prefixed.extend( raw);
+
+    let mut identifier = vec![ typ.to_byte()];
+    identifier.extend( ripemd160( &sha256( &prefixed)));
+    let checksum = checksum_identifier( &identifier);
+    identifier.extend( checksum);
+    PKAIdentifier{ identifier : identifier}
 }
 
 fn checksum_identifier( ident : &Vec<u8>) -> Vec<u8> {
@@ -111,7 +401,7 @@ fn ripemd160( d : &Vec<u8>) -> Vec<u8> {
 }
 
 // JP: Move this somewhere upstream to remove duplication?
-fn u8_to_fixed_length_32( data : &[u8]) -> Option<[u8; 32]> {
+pub fn u8_to_fixed_length_32( data : &[u8]) -> Option<[u8; 32]> {
     // Check length.
     (data.len() == 32).as_option()?;
 
@@ -7,15 +7,21 @@ use serde::de;
 use serde::de::{Deserialize, Deserializer, MapAccess, Visitor};
 use std::fmt;
 
-use internal::{ToIdentifier, PKAIdentifier, AlgorithmId, EncodePSF, generate_identifier, DecodePSF, PKAJ, serialize_psf, deserialize_psf, u8_to_fixed_length_32};
+use internal::{ToIdentifier, ToVersionedIdentifier, IdentifierType, PKAIdentifier, AlgorithmId, AlgorithmStrength, AlgorithmTag, KeyError, Validate, DecodeKey, EncodePSF, generate_identifier, generate_versioned_identifier, DecodePSF, PKAJ, PKAJField, serialize_psf, deserialize_psf, is_strict, u8_to_fixed_length_32};
 
 use ToAlgorithm;
 
+impl PKAJField for Key {
+    fn pkaj_field() -> &'static str {
+        "key"
+    }
+}
+
 impl<'a> Serialize for PKAJ<&'a Key> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S:Serializer {
         let mut o = serializer.serialize_struct("Key", 2)?;
 
-        o.serialize_field( "key", &serialize_psf( self.pkaj))?;
+        o.serialize_field( <Key as PKAJField>::pkaj_field(), &serialize_psf( self.pkaj))?;
         o.serialize_field( "algorithm", AlgorithmId::to_algorithm_id( &ToAlgorithm::to_algorithm( self.pkaj)))?;
 
         o.end()
@@ -49,10 +55,10 @@ impl<'d> Deserialize<'d> for PKAJ<Key> {
                             key.is_none().ok_or( de::Error::duplicate_field( "key"))?;
                             key = Some( map.next_value()?);
                         }
-                        _k => {
-                            // Skip unknown fields.
-                            let _ : Result<(),U::Error> = map.next_value();
-                            // Err(de::Error::unknown_field(k, FIELDS))?;
+                        k => {
+                            // Skip unknown fields, unless deserializing strictly.
+                            (!is_strict()).ok_or( de::Error::unknown_field( k, FIELDS))?;
+                            map.next_value::<de::IgnoredAny>()?;
                         }
                     }
                 }
@@ -71,6 +77,14 @@ impl<'d> Deserialize<'d> for PKAJ<Key> {
     }
 }
 
+impl<'a> From<&'a Algorithm> for IdentifierType {
+    fn from( alg : &'a Algorithm) -> IdentifierType {
+        match *alg {
+            Algorithm::SEAesGcm256 => IdentifierType::SEAesGcm256,
+        }
+    }
+}
+
 impl ToIdentifier for Key {
     fn to_identifier( key : &Key) -> PKAIdentifier {
         let serialized = EncodePSF::encode_psf( key);
@@ -78,6 +92,14 @@ impl ToIdentifier for Key {
     }
 }
 
+impl ToVersionedIdentifier for Key {
+    fn to_versioned_identifier( key : &Key) -> PKAIdentifier {
+        let typ = IdentifierType::from( &ToAlgorithm::to_algorithm( key));
+        let serialized = EncodePSF::encode_psf( key);
+        generate_versioned_identifier( typ, serialized)
+    }
+}
+
 impl EncodePSF for Key {
     fn encode_psf( key : &Key) -> Vec<u8> {
         match *key {
@@ -92,9 +114,17 @@ impl DecodePSF for Key {
     type Algorithm = enc::Algorithm;
 
     fn decode_psf( alg : &Algorithm, psf : &Vec<u8>) -> Result<Key, &'static str> where Self : Sized {
+        Ok( DecodeKey::decode_key( alg, psf)?)
+    }
+}
+
+impl DecodeKey for Key {
+    type Algorithm = enc::Algorithm;
+
+    fn decode_key( alg : &Algorithm, psf : &Vec<u8>) -> Result<Key, KeyError> where Self : Sized {
         match alg {
             &Algorithm::SEAesGcm256 => {
-                let key = u8_to_fixed_length_32( psf).ok_or("Key is wrong length.")?;
+                let key = u8_to_fixed_length_32( psf).ok_or( KeyError::WrongLength)?;
 
                 // TODO: test this XXX
                 Ok( Key::SEAesGcm256( key))
@@ -103,6 +133,18 @@ impl DecodePSF for Key {
     }
 }
 
+// Every 32 byte key is valid.
+impl Validate for Key {
+    fn validate( &self) -> Result<(), KeyError> {
+        Ok(())
+    }
+}
+
+/// Build a key from a 32 byte seed.
+pub fn key_from_seed( alg : &Algorithm, seed : &[u8]) -> Result<Key, &'static str> {
+    DecodePSF::decode_psf( alg, &seed.to_vec())
+}
+
 impl AlgorithmId for Algorithm {
     fn to_algorithm_id( alg : &Algorithm) -> &'static str {
         match *alg {
@@ -118,6 +160,35 @@ impl AlgorithmId for Algorithm {
     }
 }
 
+impl AlgorithmStrength for Algorithm {
+    fn key_bits( alg : &Algorithm) -> usize {
+        match *alg {
+            Algorithm::SEAesGcm256 => 256
+        }
+    }
+
+    fn is_aead( alg : &Algorithm) -> bool {
+        match *alg {
+            Algorithm::SEAesGcm256 => true
+        }
+    }
+}
+
+impl AlgorithmTag for Algorithm {
+    fn to_algorithm_tag( alg : &Algorithm) -> u8 {
+        match *alg {
+            Algorithm::SEAesGcm256 => 0x03
+        }
+    }
+
+    fn from_algorithm_tag( tag : u8) -> Option<Self> {
+        match tag {
+            0x03 => Some( Algorithm::SEAesGcm256),
+            _ => None
+        }
+    }
+}
+
 // Can't have orphans.
 // impl Serialize for Algorithm {
 //     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S:Serializer {
@@ -1,14 +1,19 @@
 #![deny(warnings)]
 
 extern crate base64;
+extern crate blake2;
 extern crate boolinator;
 extern crate crypto_abstract;
+extern crate crypto_box;
+extern crate curve25519_dalek;
 extern crate ring;
 extern crate ripemd160;
 extern crate rust_base58;
+extern crate scrypt;
 extern crate serde;
 // #[macro_use]
 // extern crate serde_derive;
+#[macro_use]
 extern crate serde_json;
 extern crate untrusted;
 
@@ -21,9 +26,36 @@ pub mod asym;
 /// Symmetric cryptography.
 pub mod sym;
 
+/// Deterministic key derivation.
+pub mod derive;
+
+/// Shamir secret sharing of keys.
+pub mod shamir;
+
+/// Text encodings of keys and identifiers.
+pub mod encoding;
+
+/// did:key identifiers and DID documents.
+pub mod did;
+
+/// Sign-then-encrypt envelopes.
+pub mod envelope;
+
+/// Algorithm policies.
+pub mod policy;
+
+/// Key metadata.
+pub mod metadata;
+
+/// Public key certificates.
+pub mod certificate;
+
+/// Key revocation.
+pub mod revocation;
+
 // Re-exports.
 pub use crypto_abstract::{ToAlgorithm, ToPublicKey};
-pub use internal::{AlgorithmId, ToIdentifier, PKAJ}; //, EncodePSF, DecodePSF, serialize_psf, deserialize_psf, PKAJ}; // decode_psf', deserializePSF' extractDomainName
+pub use internal::{AlgorithmId, AlgorithmStrength, AlgorithmTag, DecodeKey, IdentifierType, KeyError, PKAIdentifier, ToIdentifier, ToVersionedIdentifier, Validate, PKAJ, PKAJField}; //, EncodePSF, DecodePSF, serialize_psf, deserialize_psf, PKAJ}; // decode_psf', deserializePSF' extractDomainName
 
 // #[cfg(test)]
 // mod tests {
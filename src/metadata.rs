@@ -0,0 +1,208 @@
+// Optional metadata of PKAJ keys: creation time, expiry, usage, and a label.
+
+use boolinator::Boolinator;
+use serde::de;
+use serde::de::{Deserialize, Deserializer, DeserializeOwned, MapAccess, Visitor};
+use serde::ser::{Serialize, SerializeMap, Serializer};
+use serde_json;
+use serde_json::{Map, Value};
+use std::fmt;
+use std::marker::PhantomData;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crypto_abstract::ToAlgorithm;
+use internal::{AlgorithmId, EncodePSF, PKAJ, PKAJField, serialize_psf};
+
+/// Purposes a key may be used for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Usage {
+    Sign,
+    Encrypt,
+    Auth,
+    /// Issuing certificates for other keys.
+    Certify,
+}
+
+impl Usage {
+    pub fn to_usage_id( &self) -> &'static str {
+        match *self {
+            Usage::Sign => "sign",
+            Usage::Encrypt => "encrypt",
+            Usage::Auth => "auth",
+            Usage::Certify => "certify",
+        }
+    }
+
+    pub fn from_usage_id( id : &str) -> Option<Usage> {
+        match id {
+            "sign" => Some( Usage::Sign),
+            "encrypt" => Some( Usage::Encrypt),
+            "auth" => Some( Usage::Auth),
+            "certify" => Some( Usage::Certify),
+            _ => None
+        }
+    }
+}
+
+impl Serialize for Usage {
+    fn serialize<S>( &self, serializer : S) -> Result<S::Ok, S::Error> where S : Serializer {
+        serializer.serialize_str( self.to_usage_id())
+    }
+}
+
+impl<'d> Deserialize<'d> for Usage {
+    fn deserialize<D>( deserializer : D) -> Result<Usage, D::Error> where D : Deserializer<'d> {
+        let id = String::deserialize( deserializer)?;
+        Usage::from_usage_id( &id).ok_or( de::Error::custom( "Unknown key usage."))
+    }
+}
+
+/// Key metadata. Times are seconds since the Unix epoch. A key without usages may be used for anything.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Metadata {
+    pub created : Option<u64>,
+    pub expires : Option<u64>,
+    pub usage : Option<Vec<Usage>>,
+    pub label : Option<String>,
+}
+
+impl Metadata {
+    /// Check that a key is valid at the clock's current time and may be used for `usage`.
+    pub fn check<C>( &self, clock : &C, usage : Usage) -> Result<(), &'static str> where C : Clock {
+        let now = clock.now();
+        self.created.map_or( true, |created| created <= now).ok_or( "Key is not valid yet.")?;
+        self.expires.map_or( true, |expires| now < expires).ok_or( "Key has expired.")?;
+        self.usage.as_ref().map_or( true, |usages| usages.contains( &usage)).ok_or( "Key usage does not allow this operation.")?;
+
+        Ok(())
+    }
+
+    fn serialize_entries<M>( &self, o : &mut M) -> Result<(), M::Error> where M : SerializeMap {
+        if let Some( created) = self.created {
+            o.serialize_entry( "created", &created)?;
+        }
+        if let Some( expires) = self.expires {
+            o.serialize_entry( "expires", &expires)?;
+        }
+        if let Some( ref usage) = self.usage {
+            o.serialize_entry( "usage", usage)?;
+        }
+        if let Some( ref label) = self.label {
+            o.serialize_entry( "label", label)?;
+        }
+        Ok(())
+    }
+
+    fn remove_from( o : &mut Map<String, Value>) -> Result<Metadata, &'static str> {
+        let created = match o.remove( "created") {
+            None => None,
+            Some( v) => Some( v.as_u64().ok_or( "Invalid key creation time.")?),
+        };
+        let expires = match o.remove( "expires") {
+            None => None,
+            Some( v) => Some( v.as_u64().ok_or( "Invalid key expiry time.")?),
+        };
+        let usage = match o.remove( "usage") {
+            None => None,
+            Some( v) => {
+                let ids = v.as_array().ok_or( "Invalid key usage.")?;
+                let mut usage = Vec::new();
+                for id in ids {
+                    let u = id.as_str().and_then( Usage::from_usage_id).ok_or( "Unknown key usage.")?;
+                    (!usage.contains( &u)).ok_or( "Duplicate key usage.")?;
+                    usage.push( u);
+                }
+                Some( usage)
+            }
+        };
+        let label = match o.remove( "label") {
+            None => None,
+            Some( v) => Some( v.as_str().ok_or( "Invalid key label.")?.to_string()),
+        };
+
+        match (created, expires) {
+            (Some( created), Some( expires)) => (created < expires).ok_or( "Key expires before it is created.")?,
+            _ => (),
+        }
+
+        Ok( Metadata{ created : created, expires : expires, usage : usage, label : label})
+    }
+}
+
+/// Source of the current time, so that expiry can be checked against a fixed time.
+pub trait Clock {
+    /// Seconds since the Unix epoch.
+    fn now( &self) -> u64;
+}
+
+/// Clock that reads the system time.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now( &self) -> u64 {
+        SystemTime::now().duration_since( UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or( 0)
+    }
+}
+
+/// Clock that always returns the same time.
+pub struct FixedClock( pub u64);
+
+impl Clock for FixedClock {
+    fn now( &self) -> u64 {
+        self.0
+    }
+}
+
+/// A key with its metadata. In JSON, the metadata fields follow the fields of the key's unchanged PKAJ object.
+pub struct PKAKey<T> {
+    pub key : T,
+    pub metadata : Metadata,
+}
+
+impl<T> PKAKey<T> {
+    pub fn new( key : T, metadata : Metadata) -> PKAKey<T> {
+        PKAKey{ key : key, metadata : metadata}
+    }
+}
+
+impl<T> Serialize for PKAKey<T> where T : EncodePSF + PKAJField + ToAlgorithm, T::Algorithm : AlgorithmId {
+    fn serialize<S>( &self, serializer : S) -> Result<S::Ok, S::Error> where S : Serializer {
+        let mut o = serializer.serialize_map( None)?;
+
+        o.serialize_entry( T::pkaj_field(), &serialize_psf( &self.key))?;
+        o.serialize_entry( "algorithm", AlgorithmId::to_algorithm_id( &ToAlgorithm::to_algorithm( &self.key)))?;
+        self.metadata.serialize_entries( &mut o)?;
+
+        o.end()
+    }
+}
+
+impl<'d, T> Deserialize<'d> for PKAKey<T> where PKAJ<T> : DeserializeOwned {
+    fn deserialize<D>( deserializer : D) -> Result<PKAKey<T>, D::Error> where D : Deserializer<'d> {
+        struct V<T>( PhantomData<T>);
+
+        impl<'d, T> Visitor<'d> for V<T> where PKAJ<T> : DeserializeOwned {
+            type Value = PKAKey<T>;
+
+            fn expecting( &self, formatter : &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str( "JSON PKAKey")
+            }
+
+            fn visit_map<U>( self, mut map : U) -> Result<PKAKey<T>, U::Error> where U : MapAccess<'d> {
+                let mut o = Map::new();
+                while let Some(k) = map.next_key::<String>()? {
+                    (!o.contains_key( &k)).ok_or_else(|| de::Error::custom( format!( "duplicate field `{}`", k)))?;
+                    let v : Value = map.next_value()?;
+                    o.insert( k, v);
+                }
+                let metadata = Metadata::remove_from( &mut o).map_err( de::Error::custom)?;
+
+                // The remaining fields are the key's, so strict deserialization still rejects unknown fields.
+                let key : PKAJ<T> = serde_json::from_value( Value::Object( o)).map_err( de::Error::custom)?;
+                Ok( PKAKey{ key : key.pkaj, metadata : metadata})
+            }
+        }
+
+        deserializer.deserialize_map( V( PhantomData))
+    }
+}
@@ -0,0 +1,137 @@
+// Policies restrict the algorithms accepted when verifying, decrypting, and loading keys.
+
+use boolinator::Boolinator;
+use crypto_abstract::ToAlgorithm;
+use serde::de::DeserializeOwned;
+use serde_json;
+use serde_json::Value;
+use std::collections::HashMap;
+
+use internal::{AlgorithmId, AlgorithmStrength, PKAJ, from_slice_strict, strict};
+
+/// Operations restricted by a policy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Operation {
+    Verify,
+    Decrypt,
+}
+
+/// Allowlist of algorithm identifiers for each operation, with a minimum key size, whether decryption must be authenticated, and deprecated algorithms.
+pub struct Policy {
+    allowed : HashMap<Operation, Vec<String>>,
+    deprecated : Vec<String>,
+    minimum_key_bits : usize,
+    require_aead : bool,
+    on_deprecated : Option<Box<dyn Fn( &str)>>,
+}
+
+/// Allows the current algorithms: `aa-ed25519` for verification, and `se-aesgcm256`, `ae-x25519` and the HPKE suites for decryption.
+impl Default for Policy {
+    fn default() -> Policy {
+        Policy::new()
+            .allow( Operation::Verify, "aa-ed25519")
+            .allow( Operation::Decrypt, "se-aesgcm256")
+            .allow( Operation::Decrypt, "ae-x25519")
+            .allow( Operation::Decrypt, "hpke-x25519-aesgcm128")
+            .allow( Operation::Decrypt, "hpke-x25519-aesgcm256")
+            .allow( Operation::Decrypt, "hpke-x25519-chacha20poly1305")
+    }
+}
+
+impl Policy {
+    /// Policy that allows nothing.
+    pub fn new() -> Policy {
+        Policy{ allowed : HashMap::new(), deprecated : Vec::new(), minimum_key_bits : 0, require_aead : false, on_deprecated : None}
+    }
+
+    pub fn allow( mut self, operation : Operation, algorithm : &str) -> Policy {
+        {
+            let allowed = self.allowed.entry( operation).or_insert_with( Vec::new);
+            if !allowed.iter().any(|a| a == algorithm) {
+                allowed.push( algorithm.to_string());
+            }
+        }
+        self
+    }
+
+    pub fn forbid( mut self, operation : Operation, algorithm : &str) -> Policy {
+        if let Some( allowed) = self.allowed.get_mut( &operation) {
+            allowed.retain(|a| a != algorithm);
+        }
+        self
+    }
+
+    /// Deprecated algorithms are still allowed, but reported to the deprecation handler when used.
+    pub fn deprecate( mut self, algorithm : &str) -> Policy {
+        self.deprecated.push( algorithm.to_string());
+        self
+    }
+
+    pub fn minimum_key_bits( mut self, bits : usize) -> Policy {
+        self.minimum_key_bits = bits;
+        self
+    }
+
+    /// Only decrypt with algorithms that authenticate their messages.
+    pub fn require_aead( mut self) -> Policy {
+        self.require_aead = true;
+        self
+    }
+
+    pub fn on_deprecated<F>( mut self, handler : F) -> Policy where F : Fn( &str) + 'static {
+        self.on_deprecated = Some( Box::new( handler));
+        self
+    }
+
+    pub fn is_allowed( &self, operation : Operation, algorithm : &str) -> bool {
+        self.allowed.get( &operation).map_or( false, |allowed| allowed.iter().any(|a| a == algorithm))
+    }
+
+    /// Check that an algorithm is allowed for an operation.
+    pub fn check<A>( &self, operation : Operation, algorithm : &A) -> Result<(), &'static str> where A : AlgorithmId + AlgorithmStrength {
+        self.is_allowed( operation, AlgorithmId::to_algorithm_id( algorithm)).ok_or( "Algorithm is not allowed by policy.")?;
+        (operation != Operation::Decrypt || !self.require_aead || AlgorithmStrength::is_aead( algorithm)).ok_or( "Algorithm is not authenticated encryption.")?;
+        self.check_algorithm( algorithm)
+    }
+
+    /// Check that a key's algorithm is allowed for some operation.
+    pub fn check_key<A>( &self, algorithm : &A) -> Result<(), &'static str> where A : AlgorithmId + AlgorithmStrength {
+        let id = AlgorithmId::to_algorithm_id( algorithm);
+        self.allowed.values().any(|allowed| allowed.iter().any(|a| a == id)).ok_or( "Key algorithm is not allowed by policy.")?;
+        self.check_algorithm( algorithm)
+    }
+
+    /// Load a PKAJ key, rejecting forbidden algorithms before the key is decoded.
+    pub fn load_key<K>( &self, json : &[u8]) -> Result<K, &'static str> where PKAJ<K> : DeserializeOwned, K : ToAlgorithm, K::Algorithm : AlgorithmId + AlgorithmStrength {
+        let v : Value = serde_json::from_slice( json).map_err(|_| "Invalid json encoding.")?;
+        self.load_key_value( v)
+    }
+
+    /// Load a PKAJ key strictly, see `internal::from_slice_strict`.
+    pub fn load_key_strict<K>( &self, json : &[u8]) -> Result<K, &'static str> where PKAJ<K> : DeserializeOwned, K : ToAlgorithm, K::Algorithm : AlgorithmId + AlgorithmStrength {
+        let v : Value = from_slice_strict( json)?;
+        strict(|| self.load_key_value( v))
+    }
+
+    fn load_key_value<K>( &self, v : Value) -> Result<K, &'static str> where PKAJ<K> : DeserializeOwned, K : ToAlgorithm, K::Algorithm : AlgorithmId + AlgorithmStrength {
+        let algorithm = v.get( "algorithm").and_then( Value::as_str).ok_or( "Missing key algorithm.")?;
+        let algorithm : K::Algorithm = AlgorithmId::from_algorithm_id( algorithm).ok_or( "Unknown key algorithm.")?;
+        self.check_key( &algorithm)?;
+
+        let key : PKAJ<K> = serde_json::from_value( v).map_err(|_| "Invalid key.")?;
+        Ok( key.pkaj)
+    }
+
+    fn check_algorithm<A>( &self, algorithm : &A) -> Result<(), &'static str> where A : AlgorithmId + AlgorithmStrength {
+        (AlgorithmStrength::key_bits( algorithm) >= self.minimum_key_bits).ok_or( "Key is smaller than policy allows.")?;
+
+        let id = AlgorithmId::to_algorithm_id( algorithm);
+        if self.deprecated.iter().any(|a| a == id) {
+            if let Some( ref handler) = self.on_deprecated {
+                handler( id);
+            }
+        }
+
+        Ok(())
+    }
+}
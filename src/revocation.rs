@@ -0,0 +1,379 @@
+// Revocation of keys, by signed revocation lists from an issuer or by revocation certificates self-signed by the revoked key.
+
+use boolinator::Boolinator;
+use serde::de;
+use serde::de::{Deserialize, Deserializer, MapAccess, Visitor};
+use serde::ser::{Serialize, Serializer, SerializeStruct};
+use serde_json;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use asym::auth as aa;
+use crypto_abstract::ToPublicKey;
+use internal::{PKAIdentifier, PKAJ, ToIdentifier, is_strict};
+
+/// Reason a key was revoked.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Reason {
+    Unspecified,
+    KeyCompromise,
+    Superseded,
+    Retired,
+}
+
+impl Reason {
+    pub fn to_reason_id( &self) -> &'static str {
+        match *self {
+            Reason::Unspecified => "unspecified",
+            Reason::KeyCompromise => "key-compromise",
+            Reason::Superseded => "superseded",
+            Reason::Retired => "retired",
+        }
+    }
+
+    pub fn from_reason_id( id : &str) -> Option<Reason> {
+        match id {
+            "unspecified" => Some( Reason::Unspecified),
+            "key-compromise" => Some( Reason::KeyCompromise),
+            "superseded" => Some( Reason::Superseded),
+            "retired" => Some( Reason::Retired),
+            _ => None
+        }
+    }
+}
+
+impl Serialize for Reason {
+    fn serialize<S>( &self, serializer : S) -> Result<S::Ok, S::Error> where S : Serializer {
+        serializer.serialize_str( self.to_reason_id())
+    }
+}
+
+impl<'d> Deserialize<'d> for Reason {
+    fn deserialize<D>( deserializer : D) -> Result<Reason, D::Error> where D : Deserializer<'d> {
+        let id = String::deserialize( deserializer)?;
+        Reason::from_reason_id( &id).ok_or( de::Error::custom( "Unknown revocation reason."))
+    }
+}
+
+/// A revoked key. The time is in seconds since the Unix epoch.
+#[derive(Clone)]
+pub struct Revoked {
+    pub identifier : PKAIdentifier,
+    pub reason : Reason,
+    pub time : u64,
+}
+
+impl Serialize for Revoked {
+    fn serialize<S>( &self, serializer : S) -> Result<S::Ok, S::Error> where S : Serializer {
+        let mut s = serializer.serialize_struct( "Revoked", 3)?;
+
+        s.serialize_field( "identifier", &self.identifier)?;
+        s.serialize_field( "reason", &self.reason)?;
+        s.serialize_field( "time", &self.time)?;
+
+        s.end()
+    }
+}
+
+impl<'d> Deserialize<'d> for Revoked {
+    fn deserialize<D>( deserializer : D) -> Result<Revoked, D::Error> where D : Deserializer<'d> {
+        struct V;
+
+        const FIELDS : &'static [&'static str] = &["identifier", "reason", "time"];
+
+        impl<'d> Visitor<'d> for V {
+            type Value = Revoked;
+
+            fn expecting( &self, formatter : &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str( "JSON Revoked")
+            }
+
+            fn visit_map<U>( self, mut map : U) -> Result<Revoked, U::Error> where U : MapAccess<'d> {
+                let mut identifier = None;
+                let mut reason = None;
+                let mut time = None;
+
+                while let Some(k) = map.next_key::<String>()? {
+                    match k.as_str() {
+                        "identifier" => {
+                            identifier.is_none().ok_or( de::Error::duplicate_field( "identifier"))?;
+                            identifier = Some( map.next_value()?);
+                        }
+                        "reason" => {
+                            reason.is_none().ok_or( de::Error::duplicate_field( "reason"))?;
+                            reason = Some( map.next_value()?);
+                        }
+                        "time" => {
+                            time.is_none().ok_or( de::Error::duplicate_field( "time"))?;
+                            time = Some( map.next_value()?);
+                        }
+                        k => {
+                            // Skip unknown fields, unless deserializing strictly.
+                            (!is_strict()).ok_or( de::Error::unknown_field( k, FIELDS))?;
+                            map.next_value::<de::IgnoredAny>()?;
+                        }
+                    }
+                }
+
+                let identifier = identifier.ok_or_else(|| de::Error::missing_field( "identifier"))?;
+                let reason = reason.ok_or_else(|| de::Error::missing_field( "reason"))?;
+                let time = time.ok_or_else(|| de::Error::missing_field( "time"))?;
+
+                Ok( Revoked{ identifier : identifier, reason : reason, time : time})
+            }
+        }
+
+        deserializer.deserialize_struct( "Revoked", FIELDS, V)
+    }
+}
+
+/// Keys revoked by an issuer. A list is complete and replaces the issuer's earlier lists. Issuers increase the sequence number with every list they publish, so an older list cannot be replayed.
+pub struct RevocationList {
+    pub issuer : PKAIdentifier,
+    pub sequence : u64,
+    pub revoked : Vec<Revoked>,
+}
+
+impl RevocationList {
+    pub fn new( issuer : &aa::PublicKey, sequence : u64) -> RevocationList {
+        RevocationList{ issuer : ToIdentifier::to_identifier( issuer), sequence : sequence, revoked : Vec::new()}
+    }
+}
+
+impl Serialize for RevocationList {
+    fn serialize<S>( &self, serializer : S) -> Result<S::Ok, S::Error> where S : Serializer {
+        let mut s = serializer.serialize_struct( "RevocationList", 3)?;
+
+        s.serialize_field( "issuer", &self.issuer)?;
+        s.serialize_field( "sequence", &self.sequence)?;
+        s.serialize_field( "revoked", &self.revoked)?;
+
+        s.end()
+    }
+}
+
+impl<'d> Deserialize<'d> for RevocationList {
+    fn deserialize<D>( deserializer : D) -> Result<RevocationList, D::Error> where D : Deserializer<'d> {
+        struct V;
+
+        const FIELDS : &'static [&'static str] = &["issuer", "sequence", "revoked"];
+
+        impl<'d> Visitor<'d> for V {
+            type Value = RevocationList;
+
+            fn expecting( &self, formatter : &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str( "JSON RevocationList")
+            }
+
+            fn visit_map<U>( self, mut map : U) -> Result<RevocationList, U::Error> where U : MapAccess<'d> {
+                let mut issuer = None;
+                let mut sequence = None;
+                let mut revoked = None;
+
+                while let Some(k) = map.next_key::<String>()? {
+                    match k.as_str() {
+                        "issuer" => {
+                            issuer.is_none().ok_or( de::Error::duplicate_field( "issuer"))?;
+                            issuer = Some( map.next_value()?);
+                        }
+                        "sequence" => {
+                            sequence.is_none().ok_or( de::Error::duplicate_field( "sequence"))?;
+                            sequence = Some( map.next_value()?);
+                        }
+                        "revoked" => {
+                            revoked.is_none().ok_or( de::Error::duplicate_field( "revoked"))?;
+                            revoked = Some( map.next_value()?);
+                        }
+                        k => {
+                            // Skip unknown fields, unless deserializing strictly.
+                            (!is_strict()).ok_or( de::Error::unknown_field( k, FIELDS))?;
+                            map.next_value::<de::IgnoredAny>()?;
+                        }
+                    }
+                }
+
+                let issuer = issuer.ok_or_else(|| de::Error::missing_field( "issuer"))?;
+                let sequence = sequence.ok_or_else(|| de::Error::missing_field( "sequence"))?;
+                let revoked : Vec<Revoked> = revoked.ok_or_else(|| de::Error::missing_field( "revoked"))?;
+
+                let mut identifiers = HashSet::new();
+                for r in revoked.iter() {
+                    identifiers.insert( &r.identifier).ok_or( de::Error::custom( "Duplicate revoked identifier."))?;
+                }
+
+                Ok( RevocationList{ issuer : issuer, sequence : sequence, revoked : revoked})
+            }
+        }
+
+        deserializer.deserialize_struct( "RevocationList", FIELDS, V)
+    }
+}
+
+/// Revocation of a key, signed by the key itself. Key holders can generate one in advance and publish it if the key is lost.
+pub struct RevocationCertificate {
+    pub key : aa::PublicKey,
+    pub reason : Reason,
+    pub time : u64,
+}
+
+impl RevocationCertificate {
+    pub fn to_revoked( &self) -> Revoked {
+        Revoked{ identifier : ToIdentifier::to_identifier( &self.key), reason : self.reason, time : self.time}
+    }
+}
+
+impl Serialize for RevocationCertificate {
+    fn serialize<S>( &self, serializer : S) -> Result<S::Ok, S::Error> where S : Serializer {
+        let mut s = serializer.serialize_struct( "RevocationCertificate", 3)?;
+
+        s.serialize_field( "key", &PKAJ{ pkaj : &self.key})?;
+        s.serialize_field( "reason", &self.reason)?;
+        s.serialize_field( "time", &self.time)?;
+
+        s.end()
+    }
+}
+
+impl<'d> Deserialize<'d> for RevocationCertificate {
+    fn deserialize<D>( deserializer : D) -> Result<RevocationCertificate, D::Error> where D : Deserializer<'d> {
+        struct V;
+
+        const FIELDS : &'static [&'static str] = &["key", "reason", "time"];
+
+        impl<'d> Visitor<'d> for V {
+            type Value = RevocationCertificate;
+
+            fn expecting( &self, formatter : &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str( "JSON RevocationCertificate")
+            }
+
+            fn visit_map<U>( self, mut map : U) -> Result<RevocationCertificate, U::Error> where U : MapAccess<'d> {
+                let mut key = None;
+                let mut reason = None;
+                let mut time = None;
+
+                while let Some(k) = map.next_key::<String>()? {
+                    match k.as_str() {
+                        "key" => {
+                            key.is_none().ok_or( de::Error::duplicate_field( "key"))?;
+                            key = Some( map.next_value()?);
+                        }
+                        "reason" => {
+                            reason.is_none().ok_or( de::Error::duplicate_field( "reason"))?;
+                            reason = Some( map.next_value()?);
+                        }
+                        "time" => {
+                            time.is_none().ok_or( de::Error::duplicate_field( "time"))?;
+                            time = Some( map.next_value()?);
+                        }
+                        k => {
+                            // Skip unknown fields, unless deserializing strictly.
+                            (!is_strict()).ok_or( de::Error::unknown_field( k, FIELDS))?;
+                            map.next_value::<de::IgnoredAny>()?;
+                        }
+                    }
+                }
+
+                let key : PKAJ<aa::PublicKey> = key.ok_or_else(|| de::Error::missing_field( "key"))?;
+                let reason = reason.ok_or_else(|| de::Error::missing_field( "reason"))?;
+                let time = time.ok_or_else(|| de::Error::missing_field( "time"))?;
+
+                Ok( RevocationCertificate{ key : key.pkaj, reason : reason, time : time})
+            }
+        }
+
+        deserializer.deserialize_struct( "RevocationCertificate", FIELDS, V)
+    }
+}
+
+/// Sign a revocation list with the issuer's key.
+pub fn sign_list( issuer : &aa::PrivateKey, list : &RevocationList) -> Result<aa::PKASigned, &'static str> {
+    (list.issuer == ToIdentifier::to_identifier( &ToPublicKey::to_public_key( issuer))).ok_or( "Issuer identifier does not match issuer key.")?;
+    aa::sign( issuer, list)
+}
+
+pub fn verify_list( issuer : &aa::PublicKey, signed : aa::PKASigned) -> Result<RevocationList, &'static str> {
+    let list : RevocationList = aa::verify( issuer, signed)?;
+    (list.issuer == ToIdentifier::to_identifier( issuer)).ok_or( "Issuer identifier does not match issuer key.")?;
+
+    Ok( list)
+}
+
+/// Generate a revocation certificate for a key.
+pub fn revoke( key : &aa::PrivateKey, reason : Reason, time : u64) -> Result<aa::PKASigned, &'static str> {
+    aa::sign( key, &RevocationCertificate{ key : ToPublicKey::to_public_key( key), reason : reason, time : time})
+}
+
+/// Verify a revocation certificate with the key it revokes.
+pub fn verify_revocation( signed : aa::PKASigned) -> Result<RevocationCertificate, &'static str> {
+    let unverified : RevocationCertificate = serde_json::from_slice( signed.unverified_content()).map_err(|_| "Invalid json encoding.")?;
+    aa::verify( &unverified.key, signed)
+}
+
+/// Set of revoked keys that verification consults. Revocation certificates revoke a key everywhere, while an issuer's list only revokes the certificates that issuer signed.
+pub struct RevocationSet {
+    revoked : HashMap<PKAIdentifier, Revoked>,
+    lists : HashMap<PKAIdentifier, (u64, HashMap<PKAIdentifier, Revoked>)>,
+}
+
+impl RevocationSet {
+    pub fn new() -> RevocationSet {
+        RevocationSet{ revoked : HashMap::new(), lists : HashMap::new()}
+    }
+
+    /// Add an issuer's revocation list, replacing the issuer's earlier list. Lists must be added in increasing sequence order.
+    pub fn add_list( &mut self, issuer : &aa::PublicKey, signed : aa::PKASigned) -> Result<(), &'static str> {
+        let list = verify_list( issuer, signed)?;
+        self.lists.get( &list.issuer).map_or( true, |&(s, _)| s < list.sequence).ok_or( "Revocation list is not newer than one already added.")?;
+
+        let revoked = list.revoked.into_iter().map(|r| (r.identifier.clone(), r)).collect();
+        self.lists.insert( list.issuer, (list.sequence, revoked));
+
+        Ok(())
+    }
+
+    pub fn add_certificate( &mut self, signed : aa::PKASigned) -> Result<(), &'static str> {
+        let certificate = verify_revocation( signed)?;
+        let revoked = certificate.to_revoked();
+
+        // Keep the earliest revocation of a key.
+        let earlier = self.revoked.get( &revoked.identifier).map_or( false, |r| r.time <= revoked.time);
+        if !earlier {
+            self.revoked.insert( revoked.identifier.clone(), revoked);
+        }
+
+        Ok(())
+    }
+
+    /// Revocation of a key by a revocation certificate.
+    pub fn get( &self, identifier : &PKAIdentifier) -> Option<&Revoked> {
+        self.revoked.get( identifier)
+    }
+
+    /// Revocation of a key certified by `issuer`, either by the issuer's list or by a revocation certificate. The earliest one is returned.
+    pub fn get_issued( &self, issuer : &PKAIdentifier, identifier : &PKAIdentifier) -> Option<&Revoked> {
+        let listed = self.lists.get( issuer).and_then(|&(_, ref revoked)| revoked.get( identifier));
+        match (listed, self.get( identifier)) {
+            (Some( l), Some( c)) => Some( if l.time <= c.time { l } else { c }),
+            (l, c) => l.or( c),
+        }
+    }
+
+    pub fn is_revoked( &self, identifier : &PKAIdentifier) -> bool {
+        self.revoked.contains_key( identifier)
+    }
+
+    pub fn is_revoked_by( &self, issuer : &PKAIdentifier, identifier : &PKAIdentifier) -> bool {
+        self.get_issued( issuer, identifier).is_some()
+    }
+
+    /// Check that a key has not been revoked by a revocation certificate.
+    pub fn check( &self, identifier : &PKAIdentifier) -> Result<(), &'static str> {
+        (!self.is_revoked( identifier)).ok_or( "Key has been revoked.")
+    }
+
+    /// Check that a key certified by `issuer` has not been revoked.
+    pub fn check_issued( &self, issuer : &PKAIdentifier, identifier : &PKAIdentifier) -> Result<(), &'static str> {
+        (!self.is_revoked_by( issuer, identifier)).ok_or( "Key has been revoked.")
+    }
+}
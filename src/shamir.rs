@@ -0,0 +1,202 @@
+// Shamir secret sharing over GF(256), using the AES field polynomial x^8 + x^4 + x^3 + x + 1.
+
+use boolinator::Boolinator;
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::de;
+use serde::de::{Deserialize, Deserializer, MapAccess, Visitor};
+use serde::ser::{Serialize, Serializer, SerializeStruct};
+use std::fmt;
+
+use internal::{AlgorithmId, PKAIdentifier, deserialize_base64url, is_strict, serialize_base64url};
+
+/// One share of a secret key. Any `threshold` shares with distinct indices recombine to the key.
+pub struct Share {
+    identifier : PKAIdentifier,
+    algorithm : String,
+    threshold : u8,
+    index : u8,
+    share : Vec<u8>,
+}
+
+impl Share {
+    /// Identifier of the key this is a share of.
+    pub fn identifier( &self) -> &PKAIdentifier {
+        &self.identifier
+    }
+
+    pub fn algorithm_id( &self) -> &str {
+        &self.algorithm
+    }
+
+    pub fn threshold( &self) -> u8 {
+        self.threshold
+    }
+
+    pub fn index( &self) -> u8 {
+        self.index
+    }
+}
+
+/// Split a key's secret into `count` shares, any `threshold` of which recombine to it.
+pub fn split<A>( rng : &SystemRandom, algorithm : &A, identifier : PKAIdentifier, secret : &[u8], threshold : u8, count : u8) -> Result<Vec<Share>, &'static str> where A : AlgorithmId {
+    (threshold > 0).ok_or( "Threshold must be positive.")?;
+    (threshold <= count).ok_or( "Threshold is larger than the number of shares.")?;
+
+    // Coefficients of a random polynomial of degree threshold - 1 for each byte of the secret.
+    let degree = threshold as usize - 1;
+    let mut coefficients = vec![0u8; secret.len() * degree];
+    rng.fill( &mut coefficients).map_err(|_| "Error generating share polynomial.")?;
+
+    let algorithm = AlgorithmId::to_algorithm_id( algorithm);
+    let shares = (1 .. count as usize + 1).map(|x| {
+        let x = x as u8;
+        let share = secret.iter().enumerate().map(|(i, s)| {
+            let c = &coefficients[i * degree .. (i + 1) * degree];
+
+            // Horner's method, from the highest degree coefficient down to the secret.
+            let acc = c.iter().rev().fold( 0, |acc, c| gf_mul( acc, x) ^ c);
+            gf_mul( acc, x) ^ s
+        }).collect();
+
+        Share{ identifier : identifier.clone(), algorithm : algorithm.to_owned(), threshold : threshold, index : x, share : share}
+    }).collect();
+
+    Ok( shares)
+}
+
+/// Recombine shares to the algorithm, identifier and secret of the key.
+/// The caller must check that the key rebuilt from the secret matches the identifier.
+pub fn combine<A>( shares : &[Share]) -> Result<(A, PKAIdentifier, Vec<u8>), &'static str> where A : AlgorithmId {
+    let first = shares.first().ok_or( "No shares.")?;
+    (shares.len() >= first.threshold as usize).ok_or( "Not enough shares.")?;
+
+    let shares = &shares[0 .. first.threshold as usize];
+    for (i, share) in shares.iter().enumerate() {
+        (share.identifier == first.identifier && share.algorithm == first.algorithm && share.threshold == first.threshold).ok_or( "Shares are of different keys.")?;
+        (share.share.len() == first.share.len()).ok_or( "Shares are of different lengths.")?;
+        (share.index != 0).ok_or( "Invalid share index.")?;
+        shares[0 .. i].iter().all(|s| s.index != share.index).ok_or( "Duplicate share index.")?;
+    }
+
+    let algorithm = AlgorithmId::from_algorithm_id( &first.algorithm).ok_or( "Invalid algorithm identifier.")?;
+
+    // Lagrange interpolation at x = 0.
+    let mut secret = vec![0u8; first.share.len()];
+    for share in shares {
+        let basis = shares.iter().filter(|s| s.index != share.index).fold( 1, |acc, s| {
+            gf_mul( acc, gf_mul( s.index, gf_inv( s.index ^ share.index)))
+        });
+
+        for (b, y) in secret.iter_mut().zip( &share.share) {
+            *b ^= gf_mul( basis, *y);
+        }
+    }
+
+    Ok( (algorithm, first.identifier.clone(), secret))
+}
+
+// Multiplication in GF(256) without secret dependent branches.
+fn gf_mul( a : u8, b : u8) -> u8 {
+    let mut a = a;
+    let mut b = b;
+    let mut r = 0u8;
+    for _ in 0 .. 8 {
+        r ^= a & (0u8.wrapping_sub( b & 1));
+        let carry = 0u8.wrapping_sub( a >> 7);
+        a = (a << 1) ^ (carry & 0x1b);
+        b >>= 1;
+    }
+    r
+}
+
+// Inverse in GF(256) as a^254.
+fn gf_inv( a : u8) -> u8 {
+    let a2 = gf_mul( a, a);
+    let a4 = gf_mul( a2, a2);
+    let a8 = gf_mul( a4, a4);
+    let a16 = gf_mul( a8, a8);
+    let a32 = gf_mul( a16, a16);
+    let a64 = gf_mul( a32, a32);
+    let a128 = gf_mul( a64, a64);
+    gf_mul( a128, gf_mul( a64, gf_mul( a32, gf_mul( a16, gf_mul( a8, gf_mul( a4, a2))))))
+}
+
+impl Serialize for Share {
+    fn serialize<S>( &self, serializer : S) -> Result<S::Ok, S::Error> where S : Serializer {
+        let mut s = serializer.serialize_struct( "Share", 5)?;
+
+        s.serialize_field( "identifier", &self.identifier)?;
+        s.serialize_field( "threshold", &self.threshold)?;
+        s.serialize_field( "index", &self.index)?;
+        s.serialize_field( "share", &serialize_base64url( &self.share))?;
+        s.serialize_field( "algorithm", &self.algorithm)?;
+
+        s.end()
+    }
+}
+
+impl<'d> Deserialize<'d> for Share {
+    fn deserialize<D>( deserializer : D) -> Result<Share, D::Error> where D : Deserializer<'d> {
+        struct V;
+
+        const FIELDS : &'static [&'static str] = &["identifier", "threshold", "index", "share", "algorithm"];
+
+        impl<'d> Visitor<'d> for V {
+            type Value = Share;
+
+            fn expecting( &self, formatter : &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str( "JSON Share")
+            }
+
+            fn visit_map<U>( self, mut map : U) -> Result<Share, U::Error> where U : MapAccess<'d> {
+                let mut identifier = None;
+                let mut threshold = None;
+                let mut index = None;
+                let mut share = None;
+                let mut algorithm = None;
+
+                while let Some(k) = map.next_key::<String>()? {
+                    match k.as_str() {
+                        "identifier" => {
+                            identifier.is_none().ok_or( de::Error::duplicate_field( "identifier"))?;
+                            identifier = Some( map.next_value()?);
+                        }
+                        "threshold" => {
+                            threshold.is_none().ok_or( de::Error::duplicate_field( "threshold"))?;
+                            threshold = Some( map.next_value()?);
+                        }
+                        "index" => {
+                            index.is_none().ok_or( de::Error::duplicate_field( "index"))?;
+                            index = Some( map.next_value()?);
+                        }
+                        "share" => {
+                            share.is_none().ok_or( de::Error::duplicate_field( "share"))?;
+                            share = Some( map.next_value()?);
+                        }
+                        "algorithm" => {
+                            algorithm.is_none().ok_or( de::Error::duplicate_field( "algorithm"))?;
+                            algorithm = Some( map.next_value()?);
+                        }
+                        k => {
+                            // Skip unknown fields, unless deserializing strictly.
+                            (!is_strict()).ok_or( de::Error::unknown_field( k, FIELDS))?;
+                            map.next_value::<de::IgnoredAny>()?;
+                        }
+                    }
+                }
+
+                let identifier : PKAIdentifier = identifier.ok_or_else(|| de::Error::missing_field( "identifier"))?;
+                let threshold : u8 = threshold.ok_or_else(|| de::Error::missing_field( "threshold"))?;
+                let index : u8 = index.ok_or_else(|| de::Error::missing_field( "index"))?;
+                let share : String = share.ok_or_else(|| de::Error::missing_field( "share"))?;
+                let algorithm : String = algorithm.ok_or_else(|| de::Error::missing_field( "algorithm"))?;
+
+                let share = deserialize_base64url( &share).map_err( de::Error::custom)?;
+
+                Ok( Share{ identifier : identifier, algorithm : algorithm, threshold : threshold, index : index, share : share})
+            }
+        }
+
+        deserializer.deserialize_struct( "Share", FIELDS, V)
+    }
+}
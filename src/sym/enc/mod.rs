@@ -13,8 +13,21 @@ use serde::de;
 use serde::de::{Deserialize, Deserializer, DeserializeOwned, Visitor, MapAccess};
 use serde_json;
 use std::fmt;
-
+use std::str::FromStr;
+
+use derive::MasterSecret;
+use metadata::{Clock, PKAKey, Usage};
+use policy::{Operation, Policy};
+use encoding::{DecodeBinary, DecodeCbor, EncodeBinary, EncodeCbor, Format};
+use encoding::binary;
+use encoding::binary::ObjectType;
+use encoding::cbor;
+use encoding::cbor::Value as CborValue;
+use encoding::token;
+use shamir;
+use shamir::Share;
 // use internal::{PKAIdentifier};
+use internal;
 use internal::*;
 
 // #[derive(Serialize, Deserialize)]
@@ -65,10 +78,10 @@ impl<'d> Deserialize<'d> for PKASymEncrypted {
                             algorithm.is_none().ok_or( de::Error::duplicate_field("algorithm"))?;
                             algorithm = Some( map.next_value()?);
                         }
-                        _k => {
-                            // Skip unknown fields.
-                            let _ : Result<(),U::Error> = map.next_value();
-                            // Err(de::Error::unknown_field(k, FIELDS))?;
+                        k => {
+                            // Skip unknown fields, unless deserializing strictly.
+                            (!is_strict()).ok_or( de::Error::unknown_field( k, FIELDS))?;
+                            map.next_value::<de::IgnoredAny>()?;
                         }
                     }
                 }
@@ -101,6 +114,64 @@ impl Serialize for PKASymEncrypted {
     }
 }
 
+impl EncodeCbor for PKASymEncrypted {
+    fn encode_cbor( cipher : &PKASymEncrypted) -> Vec<u8> {
+        cbor::encode( &CborValue::Map( vec![
+            (CborValue::Text( "ciphertext".to_string()), CborValue::Bytes( EncodePSF::encode_psf( &cipher.ciphertext))),
+            (CborValue::Text( "algorithm".to_string()), CborValue::Text( AlgorithmId::to_algorithm_id( &ToAlgorithm::to_algorithm( cipher)).to_string())),
+        ]))
+    }
+}
+
+impl DecodeCbor for PKASymEncrypted {
+    fn decode_cbor( bs : &[u8]) -> Result<PKASymEncrypted, &'static str> {
+        let v = cbor::decode( bs)?;
+        let algorithm = v.field( "algorithm")?.as_text().ok_or( "Invalid algorithm.")?;
+        let algorithm = AlgorithmId::from_algorithm_id( algorithm).ok_or( "Invalid algorithm identifier.")?;
+        let ciphertext = v.field( "ciphertext")?.as_bytes().ok_or( "Invalid ciphertext.")?;
+        let ciphertext = DecodePSF::decode_psf( &algorithm, &ciphertext.to_vec())?;
+
+        Ok( PKASymEncrypted{ ciphertext : ciphertext})
+    }
+}
+
+impl EncodeBinary for PKASymEncrypted {
+    fn encode_binary( cipher : &PKASymEncrypted) -> Vec<u8> {
+        let alg = AlgorithmTag::to_algorithm_tag( &ToAlgorithm::to_algorithm( cipher));
+        binary::encode( ObjectType::SymEncrypted, alg, &[ &EncodePSF::encode_psf( &cipher.ciphertext)])
+    }
+}
+
+impl DecodeBinary for PKASymEncrypted {
+    fn decode_binary( bs : &[u8]) -> Result<PKASymEncrypted, &'static str> {
+        let (tag, fields) = binary::decode( ObjectType::SymEncrypted, 1, bs)?;
+        let algorithm = AlgorithmTag::from_algorithm_tag( tag).ok_or( "Unknown algorithm tag.")?;
+        let ciphertext = DecodePSF::decode_psf( &algorithm, &fields[0])?;
+
+        Ok( PKASymEncrypted{ ciphertext : ciphertext})
+    }
+}
+
+/// Token form, `pka1.<alg>.<ciphertext>`.
+impl fmt::Display for PKASymEncrypted {
+    fn fmt( &self, f : &mut fmt::Formatter) -> fmt::Result {
+        let alg = AlgorithmId::to_algorithm_id( &ToAlgorithm::to_algorithm( self));
+        f.write_str( &token::encode( alg, &[ &EncodePSF::encode_psf( &self.ciphertext)]))
+    }
+}
+
+impl FromStr for PKASymEncrypted {
+    type Err = &'static str;
+
+    fn from_str( s : &str) -> Result<PKASymEncrypted, &'static str> {
+        let (alg, parts) = token::decode( s, 1)?;
+        let algorithm = AlgorithmId::from_algorithm_id( alg).ok_or( "Invalid algorithm identifier.")?;
+        let ciphertext = DecodePSF::decode_psf( &algorithm, &parts[0])?;
+
+        Ok( PKASymEncrypted{ ciphertext : ciphertext})
+    }
+}
+
 // impl Serialize for PKASymEncrypted {
 //     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
 //         let mut s = serializer.serialize_struct("PKASymEncrypted", 3)?;
@@ -147,6 +218,43 @@ impl Serialize for PKASymEncrypted {
 // }
 
 
+/// Deterministically generate a key from a 32 byte seed.
+pub fn gen_from_seed( alg : &Algorithm, seed : &[u8]) -> Result<Key, &'static str> {
+    internal::sym::enc::key_from_seed( alg, seed)
+}
+
+/// Deterministically derive a key from a master secret.
+pub fn gen_from_master( master : &MasterSecret, alg : &Algorithm) -> Result<Key, &'static str> {
+    gen_from_seed( alg, &master.seed( AlgorithmId::to_algorithm_id( alg)))
+}
+
+/// Back up a key as a mnemonic phrase.
+pub fn to_mnemonic( key : &Key) -> Result<String, &'static str> {
+    let seed = EncodePSF::encode_psf( key);
+    mnemonic::encode_mnemonic( &ToAlgorithm::to_algorithm( key), &seed)
+}
+
+/// Restore a key from a mnemonic phrase.
+pub fn from_mnemonic( phrase : &str) -> Result<Key, &'static str> {
+    let (alg, seed) = mnemonic::decode_mnemonic( phrase)?;
+    gen_from_seed( &alg, &seed)
+}
+
+/// Split a key into `count` shares, any `threshold` of which recombine to it.
+pub fn split_shares( rng : &SystemRandom, key : &Key, threshold : u8, count : u8) -> Result<Vec<Share>, &'static str> {
+    let seed = EncodePSF::encode_psf( key);
+    shamir::split( rng, &ToAlgorithm::to_algorithm( key), ToIdentifier::to_identifier( key), &seed, threshold, count)
+}
+
+/// Recombine shares to a key, verifying its identifier.
+pub fn combine_shares( shares : &[Share]) -> Result<Key, &'static str> {
+    let (alg, identifier, seed) = shamir::combine( shares)?;
+    let key = gen_from_seed( &alg, &seed)?;
+    identifier.identifies( &key).ok_or( "Reconstructed key does not match its identifier.")?;
+
+    Ok( key)
+}
+
 // encrypt:
 // a -> bytestring -> PKAEncrypted
 // encryptContent:
@@ -166,6 +274,12 @@ pub fn decrypt<T>( key : &Key, cipher : PKASymEncrypted) -> Result<T, &'static s
     serde_json::from_slice( &d).map_err(|_| "Error parsing json.")
 }
 
+/// Decrypt content and deserialize it strictly, see `internal::from_slice_strict`.
+pub fn decrypt_strict<T>( key : &Key, cipher : PKASymEncrypted) -> Result<T, &'static str> where T : DeserializeOwned {
+    let d = decrypt_content( key, cipher)?;
+    internal::from_slice_strict( &d)
+}
+
 pub fn encrypt_content( rng : &SystemRandom, key : &Key, msg : Vec<u8>) -> Result<PKASymEncrypted, &'static str> {
     let ciphertext = enc::encrypt( &rng, &key, msg).map_err(|_| "Error encrypting content.")?;
 
@@ -185,23 +299,70 @@ pub fn decrypt_content( key : &Key, cipher : PKASymEncrypted) -> Result<Vec<u8>,
     enc::decrypt( &key, cipher.ciphertext).map_err(|_| "Error decrypting content.")
 }
 
+/// Decrypt content, rejecting algorithms the policy does not allow for decryption.
+pub fn decrypt_with_policy<T>( policy : &Policy, key : &Key, cipher : PKASymEncrypted) -> Result<T, &'static str> where T : DeserializeOwned {
+    let d = decrypt_content_with_policy( policy, key, cipher)?;
+    serde_json::from_slice( &d).map_err(|_| "Error parsing json.")
+}
+
+pub fn decrypt_content_with_policy( policy : &Policy, key : &Key, cipher : PKASymEncrypted) -> Result<Vec<u8>, &'static str> {
+    policy.check( Operation::Decrypt, &ToAlgorithm::to_algorithm( &cipher))?;
+    decrypt_content( key, cipher)
+}
+
+/// Decrypt content, rejecting keys that are expired at the clock's time or are not for encryption.
+pub fn decrypt_with_metadata<T, C>( clock : &C, key : &PKAKey<Key>, cipher : PKASymEncrypted) -> Result<T, &'static str> where T : DeserializeOwned, C : Clock {
+    let d = decrypt_content_with_metadata( clock, key, cipher)?;
+    serde_json::from_slice( &d).map_err(|_| "Error parsing json.")
+}
+
+pub fn decrypt_content_with_metadata<C>( clock : &C, key : &PKAKey<Key>, cipher : PKASymEncrypted) -> Result<Vec<u8>, &'static str> where C : Clock {
+    key.metadata.check( clock, Usage::Encrypt)?;
+    decrypt_content( &key.key, cipher)
+}
+
 pub fn encrypt_bs<T>( rng : &SystemRandom, key : &Key, o : &T) -> Result<Vec<u8>, &'static str> where T:Serialize {
     let r = serde_json::to_vec( &o).map_err(|_| "Error generating json.")?;
-    encrypt_content_bs( rng, key, r)
+    encrypt_content_bs( rng, key, r, &Format::Json)
 }
 
 pub fn decrypt_bs<T>( key : &Key, cipher : &Vec<u8>) -> Result<T, &'static str> where T:DeserializeOwned {
-    let se = serde_json::from_slice( cipher).map_err(|_| "Error decoding encrypted content.")?;
-    decrypt( key, se)
+    let d = decrypt_content_bs( key, cipher)?;
+    serde_json::from_slice( &d).map_err(|_| "Error parsing json.")
 }
 
-pub fn encrypt_content_bs( rng : &SystemRandom, key : &Key, msg : Vec<u8>) -> Result<Vec<u8>, &'static str> {
+pub fn encrypt_content_bs( rng : &SystemRandom, key : &Key, msg : Vec<u8>, format : &Format) -> Result<Vec<u8>, &'static str> {
     let encrypted = encrypt_content( rng, key, msg).map_err(|_| "Error encrypting content.")?;
 
-    serde_json::to_vec( &encrypted).map_err(|_| "Error converting encrypted content to json.")
+    match *format {
+        Format::Json => serde_json::to_vec( &encrypted).map_err(|_| "Error converting encrypted content to json."),
+        Format::Binary => Ok( EncodeBinary::encode_binary( &encrypted)),
+    }
 }
 
+/// Decrypt encrypted content in either format.
 pub fn decrypt_content_bs( key : &Key, cipher : &Vec<u8>) -> Result<Vec<u8>, &'static str> {
-    let se = serde_json::from_slice( cipher).map_err(|_| "Error decoding encrypted content.")?;
+    let se = if binary::is_binary( cipher) {
+        DecodeBinary::decode_binary( cipher)?
+    }
+    else {
+        serde_json::from_slice( cipher).map_err(|_| "Error decoding encrypted content.")?
+    };
     decrypt_content( key, se)
 }
+
+/// Decrypt content in either format, deserializing the json format strictly.
+pub fn decrypt_content_bs_strict( key : &Key, cipher : &Vec<u8>) -> Result<Vec<u8>, &'static str> {
+    let se = if binary::is_binary( cipher) {
+        DecodeBinary::decode_binary( cipher)?
+    }
+    else {
+        internal::from_slice_strict( cipher)?
+    };
+    decrypt_content( key, se)
+}
+
+pub fn decrypt_bs_strict<T>( key : &Key, cipher : &Vec<u8>) -> Result<T, &'static str> where T : DeserializeOwned {
+    let d = decrypt_content_bs_strict( key, cipher)?;
+    internal::from_slice_strict( &d)
+}
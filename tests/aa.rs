@@ -2,9 +2,11 @@ extern crate pkauth;
 extern crate ring;
 extern crate serde_json;
 
-use pkauth::{PKAJ, ToPublicKey};
+use pkauth::{PKAJ, ToIdentifier, ToPublicKey};
 use pkauth::asym::auth as aa;
+use pkauth::asym::auth::hd::{DerivationPath, ExtendedPrivateKey};
 use pkauth::internal;
+use pkauth::internal::EncodePSF;
 use ring::rand::{SystemRandom, SecureRandom};
 
 #[test]
@@ -181,3 +183,72 @@ fn aa_manual_tests() {
         );
 }
 
+
+fn slip10_test( seed : &str, path : &str, chain_code : &str, private : &str, public : &str) {
+    let master = ExtendedPrivateKey::from_seed( &aa::Algorithm::AAEd25519, &hex_to_u8( seed)).unwrap();
+    let path : DerivationPath = path.parse().unwrap();
+    let key = if path.indices().is_empty() { master } else { master.derive_path( &path).unwrap() };
+
+    assert_eq!( key.chain_code().to_vec(), hex_to_u8( chain_code));
+    assert_eq!( key.depth() as usize, path.indices().len());
+
+    let psf = EncodePSF::encode_psf( key.private_key());
+    assert_eq!( psf[0 .. 32].to_vec(), hex_to_u8( private));
+    assert_eq!( EncodePSF::encode_psf( &key.public_key()), hex_to_u8( public));
+
+    // Round trip through JSON.
+    let json = serde_json::to_string( &PKAJ{ pkaj : &key}).unwrap();
+    let restored : PKAJ<ExtendedPrivateKey> = serde_json::from_str( &json).unwrap();
+    let restored = restored.pkaj;
+    assert_eq!( restored.chain_code(), key.chain_code());
+    assert_eq!( restored.index(), key.index());
+    assert_eq!( restored.parent_identifier(), key.parent_identifier());
+    assert_eq!( ToIdentifier::to_identifier( &restored), ToIdentifier::to_identifier( &key));
+}
+
+#[test]
+fn aa_slip10_tests() {
+    // From SLIP-0010, test vector 1 for ed25519.
+    let seed = "000102030405060708090a0b0c0d0e0f";
+    slip10_test( seed, "m",
+        "90046a93de5380a72b5e45010748567d5ea02bbf6522f979e05c0d8d8ca9fffb",
+        "2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7",
+        "a4b2856bfec510abab89753fac1ac0e1112364e7d250545963f135f2a33188ed");
+    slip10_test( seed, "m/0'",
+        "8b59aa11380b624e81507a27fedda59fea6d0b779a778918a2fd3590e16e9c69",
+        "68e0fe46dfb67e368c75379acec591dad19df3cde26e63b93a8e704f1dade7a3",
+        "8c8a13df77a28f3445213a0f432fde644acaa215fc72dcdf300d5efaa85d350c");
+    slip10_test( seed, "m/0'/1'",
+        "a320425f77d1b5c2505a6b1b27382b37368ee640e3557c315416801243552f14",
+        "b1d0bad404bf35da785a64ca1ac54b2617211d2777696fbffaf208f746ae84f2",
+        "1932a5270f335bed617d5b935c80aedb1a35bd9fc1e31acafd5372c30f5c1187");
+    slip10_test( seed, "m/0'/1'/2'",
+        "2e69929e00b5ab250f49c3fb1c12f252de4fed2c1db88387094a0f8c4c9ccd6c",
+        "92a5b23c0b8a99e37d07df3fb9966917f5d06e02ddbd909c7e184371463e9fc9",
+        "ae98736566d30ed0e9d2f4486a64bc95740d89c7db33f52121f8ea8f76ff0fc1");
+    slip10_test( seed, "m/0'/1'/2'/2'",
+        "8f6d87f93d750e0efccda017d662a1b31a266e4a6f5993b15f5c1f07f74dd5cc",
+        "30d1dc7e5fc04c31219ab25a27ae00b50f6fd66622f6e9c913253d6511d1e662",
+        "8abae2d66361c879b900d204ad2cc4984fa2aa344dd7ddc46007329ac76c429c");
+    slip10_test( seed, "m/0'/1'/2'/2'/1000000000'",
+        "68789923a0cac2cd5a29172a475fe9e0fb14cd6adb5ad98a3fa70333e7afa230",
+        "8f94d394a8e8fd6b1bc2f3f49f5c47e385281d5c17e65324b0f62483e37e8793",
+        "3c24da049451555d51a7014a37337aa4e12d41e485abccfa46b47dfb2af54b7a");
+
+    // From SLIP-0010, test vector 2 for ed25519.
+    let seed = "fffcf9f6f3f0edeae7e4e1dedbd8d5d2cfccc9c6c3c0bdbab7b4b1aeaba8a5a29f9c999693908d8a8784817e7b7875726f6c696663605d5a5754514e4b484542";
+    slip10_test( seed, "m",
+        "ef70a74db9c3a5af931b5fe73ed8e1a53464133654fd55e7a66f8570b8e33c3b",
+        "171cb88b1b3c1db25add599712e36245d75bc65a1a5c9e18d76f9f2b1eab4012",
+        "8fe9693f8fa62a4305a140b9764c5ee01e455963744fe18204b4fb948249308a");
+    slip10_test( seed, "m/0H/2147483647H/1H/2147483646H/2H",
+        "5d70af781f3a37b829f0d060924d5e960bdc02e85423494afc0b1a41bbe196d4",
+        "551d333177df541ad876a60ea71f00447931c0a9da16f227c11ea080d7391b8d",
+        "47150c75db263559a70d5778bf36abbab30fb061ad69f69ece61a72b0cfa4fc0");
+
+    // Ed25519 only supports hardened children.
+    let master = ExtendedPrivateKey::from_seed( &aa::Algorithm::AAEd25519, &hex_to_u8( seed)).unwrap();
+    assert!( master.derive_path( &"m/44'/0".parse().unwrap()).is_err());
+    assert!( "44'/0'".parse::<DerivationPath>().is_err());
+    assert_eq!( "m/44H/0'/3h".parse::<DerivationPath>().unwrap().to_string(), "m/44'/0'/3'");
+}
@@ -0,0 +1,104 @@
+extern crate pkauth;
+extern crate ring;
+extern crate serde_json;
+
+use pkauth::{PKAJ, ToPublicKey};
+use pkauth::asym::auth as aa;
+use pkauth::asym::enc as ae;
+use pkauth::sym::enc as se;
+use ring::rand::SystemRandom;
+
+#[test]
+fn ae_convert_random_test() {
+    fn run() {
+        let rng = SystemRandom::new();
+        let key = aa::gen( &rng, &aa::Algorithm::AAEd25519).unwrap();
+        let pk = ToPublicKey::to_public_key( &key);
+
+        // Converting either half of the key pair gives the same X25519 public key.
+        let ae_key = ae::from_auth_private_key( &key).unwrap();
+        let ae_pk = ae::from_auth_public_key( &pk).unwrap();
+        assert_eq!( ToPublicKey::to_public_key( &ae_key), ae_pk);
+
+        // The converted key round trips like any other X25519 key.
+        let json = serde_json::to_string( &PKAJ{ pkaj : &ae_key}).unwrap();
+        let restored : PKAJ<ae::PrivateKey> = serde_json::from_str( &json).unwrap();
+        assert_eq!( ToPublicKey::to_public_key( &restored.pkaj), ae_pk);
+    }
+
+    for _ in 1 .. 100 {
+        run()
+    }
+}
+
+#[test]
+fn ae_convert_manual_tests() {
+    // From RFC8032.
+    let seed = [0x9d, 0x61, 0xb1, 0x9d, 0xef, 0xfd, 0x5a, 0x60, 0xba, 0x84, 0x4a, 0xf4, 0x92, 0xec, 0x2c, 0xc4, 0x44, 0x49, 0xc5, 0x69, 0x7b, 0x32, 0x69, 0x19, 0x70, 0x3b, 0xac, 0x03, 0x1c, 0xae, 0x7f, 0x60];
+    let key = aa::gen_from_seed( &aa::Algorithm::AAEd25519, &seed).unwrap();
+
+    let ae_key = ae::from_auth_private_key( &key).unwrap();
+    let json = serde_json::to_string( &PKAJ{ pkaj : &ae_key}).unwrap();
+    assert_eq!( json, "{\"private_key\":\"MHyDhk8oM8tCei7xwAoBPP3_J2jZgMCjpSDwBpBN6U8=\",\"algorithm\":\"ae-x25519\"}");
+
+    let ae_pk = ae::from_auth_public_key( &ToPublicKey::to_public_key( &key)).unwrap();
+    let json = serde_json::to_string( &PKAJ{ pkaj : &ae_pk}).unwrap();
+    assert_eq!( json, "{\"public_key\":\"2F4H7CKwrYgVN8L0TWYtGhQ8-DDFespDBdhcepD2ti4=\",\"algorithm\":\"ae-x25519\"}");
+
+    // The identity point and a point not on the curve are rejected when decoded.
+    assert!( serde_json::from_str::<PKAJ<aa::PublicKey>>( "{\"public_key\":\"AQAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=\",\"algorithm\":\"aa-ed25519\"}").is_err());
+    assert!( serde_json::from_str::<PKAJ<aa::PublicKey>>( "{\"public_key\":\"AgAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=\",\"algorithm\":\"aa-ed25519\"}").is_err());
+    let mut identity = [0u8; 32];
+    identity[0] = 1;
+    assert!( ae::from_auth_public_key( &aa::PublicKey::AAEd25519( identity)).is_err());
+}
+
+#[test]
+fn ae_agree_random_test() {
+    fn run() {
+        let rng = SystemRandom::new();
+        let alice = ae::gen( &rng, &ae::Algorithm::AEX25519).unwrap();
+        let bob = ae::gen( &rng, &ae::Algorithm::AEX25519).unwrap();
+        let context = ae::KdfContext{ salt : b"salt", info : b"info"};
+
+        let k1 = ae::agree( &alice, &ToPublicKey::to_public_key( &bob), &context).unwrap();
+        let k2 = ae::agree( &bob, &ToPublicKey::to_public_key( &alice), &context).unwrap();
+        assert_eq!( k1, k2);
+
+        // Keys agreed on encrypt for each other.
+        let encrypted = se::encrypt_content( &rng, &k1, b"hello".to_vec()).unwrap();
+        assert_eq!( se::decrypt_content( &k2, encrypted).unwrap(), b"hello".to_vec());
+
+        let other = ae::KdfContext{ salt : b"salt", info : b"other"};
+        let k3 = ae::agree( &alice, &ToPublicKey::to_public_key( &bob), &other).unwrap();
+        assert!( k1 != k3);
+
+        // Converted Ed25519 keys can agree too.
+        let signer = aa::gen( &rng, &aa::Algorithm::AAEd25519).unwrap();
+        let signer_pk = ae::from_auth_public_key( &ToPublicKey::to_public_key( &signer)).unwrap();
+        let k1 = ae::agree( &alice, &signer_pk, &context).unwrap();
+        let k2 = ae::agree( &ae::from_auth_private_key( &signer).unwrap(), &ToPublicKey::to_public_key( &alice), &context).unwrap();
+        assert_eq!( k1, k2);
+    }
+
+    for _ in 1 .. 100 {
+        run()
+    }
+}
+
+#[test]
+fn ae_agree_manual_tests() {
+    // Key pairs from RFC7748.
+    let alice : PKAJ<ae::PrivateKey> = serde_json::from_str( "{\"private_key\":\"dwdtCnMYpX08FsFyUbJmRd9ML4frwJkqsXf7pR25LCo=\",\"algorithm\":\"ae-x25519\"}").unwrap();
+    let bob : PKAJ<ae::PublicKey> = serde_json::from_str( "{\"public_key\":\"3p7bfXt9wbTTW2HC7OQ1Nz-DQ8hbeGdNrfx-FG-IK08=\",\"algorithm\":\"ae-x25519\"}").unwrap();
+
+    let context = ae::KdfContext{ salt : b"pkauth-test-salt", info : b"session 1"};
+    let key = ae::agree( &alice.pkaj, &bob.pkaj, &context).unwrap();
+    let key = serde_json::to_string( &PKAJ{ pkaj : &key}).unwrap();
+    assert_eq!( key, "{\"key\":\"69aj84RN5x3TmlyR1zpuIOqVJ81dcESUqfLXZZXfPT4=\",\"algorithm\":\"se-aesgcm256\"}");
+
+    // Small order points, which give an all-zero shared secret, are rejected when decoded.
+    assert!( serde_json::from_str::<PKAJ<ae::PublicKey>>( "{\"public_key\":\"AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=\",\"algorithm\":\"ae-x25519\"}").is_err());
+    assert!( serde_json::from_str::<PKAJ<ae::PublicKey>>( "{\"public_key\":\"AQAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=\",\"algorithm\":\"ae-x25519\"}").is_err());
+    assert!( ae::agree( &alice.pkaj, &ae::PublicKey::AEX25519( [0u8; 32]), &context).is_err());
+}
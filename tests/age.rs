@@ -0,0 +1,93 @@
+extern crate pkauth;
+extern crate ring;
+
+use pkauth::ToPublicKey;
+use pkauth::asym::enc as ae;
+use pkauth::asym::enc::age;
+use pkauth::asym::enc::age::{Identity, Recipient};
+use ring::rand::{SystemRandom, SecureRandom};
+
+#[test]
+fn age_random_test() {
+    fn run( length : usize) {
+        let rng = SystemRandom::new();
+        let alice = ae::gen( &rng, &ae::Algorithm::AEX25519).unwrap();
+        let bob = ae::gen( &rng, &ae::Algorithm::AEX25519).unwrap();
+        let alice_pk = ToPublicKey::to_public_key( &alice);
+        let bob_pk = ToPublicKey::to_public_key( &bob);
+
+        let recipient = age::to_recipient_string( &alice_pk);
+        assert!( recipient.starts_with( "age1"));
+        assert_eq!( age::from_recipient_string( &recipient).unwrap(), alice_pk);
+        let identity = age::to_identity_string( &alice);
+        assert!( identity.starts_with( "AGE-SECRET-KEY-1"));
+        assert_eq!( ToPublicKey::to_public_key( &age::from_identity_string( &identity).unwrap()), alice_pk);
+
+        let mut content = vec![0u8; length];
+        rng.fill( &mut content).unwrap();
+
+        let file = age::encrypt( &rng, &[Recipient::X25519( &alice_pk), Recipient::X25519( &bob_pk)], &content).unwrap();
+        assert_eq!( age::decrypt( &[Identity::X25519( &alice)], &file).unwrap(), content);
+        assert_eq!( age::decrypt( &[Identity::X25519( &bob)], &file).unwrap(), content);
+        assert_eq!( age::decrypt( &[Identity::X25519( &bob)], age::armor( &file).as_bytes()).unwrap(), content);
+
+        let file = age::encrypt( &rng, &[Recipient::X25519( &bob_pk)], &content).unwrap();
+        assert!( age::decrypt( &[Identity::X25519( &alice)], &file).is_err());
+
+        let mut tampered = file.clone();
+        let last = tampered.len() - 1;
+        tampered[last] ^= 1;
+        assert!( age::decrypt( &[Identity::X25519( &bob)], &tampered).is_err());
+        assert!( age::decrypt( &[Identity::X25519( &bob)], &file[.. file.len() - 1]).is_err());
+    }
+
+    for i in 1 .. 100 {
+        run( i * 1361)
+    }
+    run( 0);
+    run( 64 * 1024);
+    run( 2 * 64 * 1024);
+}
+
+#[test]
+fn age_manual_tests() {
+    // Generated by the age reference implementation.
+    let identity = age::from_identity_string( "AGE-SECRET-KEY-1D0G3Z6W0CEYTFXN79JE3M2293PXVKKW6TGGQUQZFQ6KLGNCK08LQ53LVL9").unwrap();
+    let recipient = age::from_recipient_string( "age1428sy2epaq7jh565r7k0fhphczug9wzrjgeh5sdpqmkgc8yrd4nsks2vvt").unwrap();
+    assert_eq!( ToPublicKey::to_public_key( &identity), recipient);
+    assert_eq!( age::to_recipient_string( &recipient), "age1428sy2epaq7jh565r7k0fhphczug9wzrjgeh5sdpqmkgc8yrd4nsks2vvt");
+    assert_eq!( age::to_identity_string( &identity), "AGE-SECRET-KEY-1D0G3Z6W0CEYTFXN79JE3M2293PXVKKW6TGGQUQZFQ6KLGNCK08LQ53LVL9");
+    assert!( age::from_recipient_string( "age1428sy2epaq7jh565r7k0fhphczug9wzrjgeh5sdpqmkgc8yrd4nsks2vvu").is_err());
+
+    // Armored, with an unknown stanza.
+    let armored = "-----BEGIN AGE ENCRYPTED FILE-----
+YWdlLWVuY3J5cHRpb24ub3JnL3YxCi0+IFgyNTUxOSBJU3UrTXhQYjQrNnA2b01r
+K0JqUnpwVU5jSDVkd3Z0ZnVYS1RYamNiY1FJClMzSENpUloxMCtUSHNtV2w3QlRy
+VGsyMFBrOWJPRC8yRjZqd214bTdhd0UKLT4gNVItZ3JlYXNlIElARDYjRgoyeTVw
+QjZpanhUeDMrMFdlUDY2aG1TZ0I1aC9GeDdHQnVkUG50ajdJd3FoZmxIajY3YzVt
+QzJtN0o3Q3BkTjZGCjNNVUxOVjdiR0doR245c29IOEU0blZpUnBXQk9yYzhYNUJD
+K1hzY29yRzFNWVBPZwotLS0gUEJxMWl3TThnL0k1TTh6M2hJOGFSaG9IWk9xRFNG
+K3hhSFh2MlJWQ2VJSQp7Fa2IYlengNssU8MNu/1k/Wv4u0bSQ0UT5feNf3c8akYD
+02iIoDajt9sp
+-----END AGE ENCRYPTED FILE-----
+";
+    assert_eq!( age::decrypt( &[Identity::X25519( &identity)], armored.as_bytes()).unwrap(), b"Hello, age!");
+    let file = age::dearmor( armored).unwrap();
+    assert_eq!( age::armor( &file), armored);
+    assert!( age::dearmor( &armored.replace( "02iIoDajt9sp", "02iIoDajt9t=")).is_err());
+
+    // Passphrase with work factor 10.
+    let file = hex( "6167652d656e6372797074696f6e2e6f72672f76310a2d3e2073637279707420495357646b777a4c32327267785556456d78557873412031300a556e597938396869447a48667641556874545172457277626c68375672666d7468716c6a772b2f2b3546510a2d2d2d204f5a71446d6b50747468456d53705538434f6c426c547a2b684333384a562f474c547564436d665a2f41300ae59f63cb87a633e7dc33a27967460c8019072bdbdc224a5a5d5c320427d08aa684765590b925a765ec11d868ffc1637fa336");
+    assert_eq!( age::decrypt( &[Identity::Scrypt( b"correct horse", 10)], &file).unwrap(), b"Hello, passphrase!");
+    assert!( age::decrypt( &[Identity::Scrypt( b"correct horse", 9)], &file).is_err());
+    assert!( age::decrypt( &[Identity::Scrypt( b"battery staple", 10)], &file).is_err());
+
+    let rng = SystemRandom::new();
+    let file = age::encrypt( &rng, &[Recipient::Scrypt( b"correct horse", 10)], b"Hello, passphrase!").unwrap();
+    assert_eq!( age::decrypt( &[Identity::Scrypt( b"correct horse", 10)], &file).unwrap(), b"Hello, passphrase!");
+    assert!( age::encrypt( &rng, &[Recipient::Scrypt( b"correct horse", 10), Recipient::X25519( &recipient)], b"").is_err());
+}
+
+fn hex( s : &str) -> Vec<u8> {
+    (0 .. s.len() / 2).map(|i| u8::from_str_radix( &s[2 * i .. 2 * i + 2], 16).unwrap()).collect()
+}
@@ -0,0 +1,89 @@
+extern crate pkauth;
+extern crate ring;
+
+use pkauth::{ToIdentifier, ToPublicKey};
+use pkauth::asym::auth as aa;
+use pkauth::asym::enc as ae;
+use pkauth::encoding::{DecodeArmor, EncodeArmor};
+use pkauth::encoding::armor;
+use pkauth::internal::EncodePSF;
+use pkauth::sym::enc as se;
+use ring::rand::{SystemRandom, SecureRandom};
+
+#[test]
+fn armor_random_test() {
+    fn run() {
+        let rng = SystemRandom::new();
+        let key = aa::gen( &rng, &aa::Algorithm::AAEd25519).unwrap();
+        let public_key = ToPublicKey::to_public_key( &key);
+        let ae_key = ae::gen( &rng, &ae::Algorithm::AEX25519).unwrap();
+        let se_key = se::gen( &rng, &se::Algorithm::SEAesGcm256).unwrap();
+
+        let mut content = [0u8; 200].to_vec();
+        rng.fill( &mut content).unwrap();
+
+        let decoded : aa::PublicKey = DecodeArmor::decode_armor( &EncodeArmor::encode_armor( &public_key)).unwrap();
+        assert_eq!( EncodePSF::encode_psf( &decoded), EncodePSF::encode_psf( &public_key));
+        let decoded : aa::PrivateKey = DecodeArmor::decode_armor( &EncodeArmor::encode_armor( &key)).unwrap();
+        assert_eq!( EncodePSF::encode_psf( &decoded), EncodePSF::encode_psf( &key));
+        let decoded : ae::PublicKey = DecodeArmor::decode_armor( &EncodeArmor::encode_armor( &ToPublicKey::to_public_key( &ae_key))).unwrap();
+        assert_eq!( EncodePSF::encode_psf( &decoded), EncodePSF::encode_psf( &ToPublicKey::to_public_key( &ae_key)));
+        let decoded : ae::PrivateKey = DecodeArmor::decode_armor( &EncodeArmor::encode_armor( &ae_key)).unwrap();
+        assert_eq!( EncodePSF::encode_psf( &decoded), EncodePSF::encode_psf( &ae_key));
+        let decoded : se::Key = DecodeArmor::decode_armor( &EncodeArmor::encode_armor( &se_key)).unwrap();
+        assert_eq!( EncodePSF::encode_psf( &decoded), EncodePSF::encode_psf( &se_key));
+
+        // Signed content survives reflowing and quoting in an email.
+        let signed = aa::sign_content( &key, content.clone()).unwrap();
+        let armored = EncodeArmor::encode_armor( &signed);
+        let damaged = format!( "Hi,\r\n\r\nhere it is:\r\n\r\n{}\r\nThanks!\r\n", armored.lines().map(|l| format!( "   {} ", l)).collect::<Vec<String>>().join( "\r\n"));
+        assert_eq!( aa::verify_content( &public_key, DecodeArmor::decode_armor( &damaged).unwrap()).unwrap(), content);
+
+        let encrypted = se::encrypt_content( &rng, &se_key, content.clone()).unwrap();
+        let armored = EncodeArmor::encode_armor( &encrypted);
+        assert_eq!( se::decrypt_content( &se_key, DecodeArmor::decode_armor( &armored).unwrap()).unwrap(), content);
+    }
+
+    for _ in 1 .. 100 {
+        run()
+    }
+}
+
+#[test]
+fn armor_manual_tests() {
+    // Armor produced by `gpg --enarmor`.
+    let (headers, data) = armor::decode( "PGP ARMORED FILE", "-----BEGIN PGP ARMORED FILE-----\nComment: Use \"gpg --dearmor\" for unpacking\n\naGVsbG8gYXJtb3I=\n=mfeo\n-----END PGP ARMORED FILE-----\n").unwrap();
+    assert_eq!( data, b"hello armor");
+    assert_eq!( headers, vec![ ("Comment".to_string(), "Use \"gpg --dearmor\" for unpacking".to_string())]);
+    assert!( armor::decode( "PGP ARMORED FILE", "-----BEGIN PGP ARMORED FILE-----\n\naGVsbG8gYXJtb3I=\n=mfeO\n-----END PGP ARMORED FILE-----\n").is_err());
+    assert!( armor::decode( "PGP ARMORED FILE", "-----BEGIN PGP ARMORED FILE-----\n\naGVsbG8gYXJtb3I=\n-----END PGP ARMORED FILE-----\n").is_err());
+    assert!( armor::decode( "PGP ARMORED FILE", "-----BEGIN PGP ARMORED FILE-----\n\naGVsbG8gYXJtb3I=\n=mfeo\n").is_err());
+
+    let key = aa::gen_from_seed( &aa::Algorithm::AAEd25519, &[9u8; 32]).unwrap();
+    let public_key = ToPublicKey::to_public_key( &key);
+    let armored = EncodeArmor::encode_armor( &public_key);
+    let lines : Vec<&str> = armored.lines().collect();
+    assert_eq!( lines[0], "-----BEGIN PKAUTH PUBLIC KEY-----");
+    assert_eq!( lines[1], "Type: public-key");
+    assert_eq!( lines[2], "Algorithm: aa-ed25519");
+    assert_eq!( lines[3], format!( "Identifier: {}", ToIdentifier::to_identifier( &public_key)));
+    assert_eq!( lines[4], "");
+    assert_eq!( lines[lines.len() - 1], "-----END PKAUTH PUBLIC KEY-----");
+
+    // Lines joined by a mail client.
+    let joined = format!( "{}\n\n{}\n{}\n", lines[0], lines[5 .. lines.len() - 1].join( " "), lines[lines.len() - 1]);
+    let decoded : aa::PublicKey = DecodeArmor::decode_armor( &joined).unwrap();
+    assert_eq!( EncodePSF::encode_psf( &decoded), EncodePSF::encode_psf( &public_key));
+
+    // Mismatched headers and types.
+    let other = ToPublicKey::to_public_key( &aa::gen_from_seed( &aa::Algorithm::AAEd25519, &[8u8; 32]).unwrap());
+    let wrong = armored.replace( &ToIdentifier::to_identifier( &public_key).to_string(), &ToIdentifier::to_identifier( &other).to_string());
+    assert!( <aa::PublicKey as DecodeArmor>::decode_armor( &wrong).is_err());
+    assert!( <aa::PublicKey as DecodeArmor>::decode_armor( &armored.replace( "aa-ed25519", "ae-x25519")).is_err());
+    assert!( <ae::PublicKey as DecodeArmor>::decode_armor( &armored).is_err());
+    assert!( <aa::PrivateKey as DecodeArmor>::decode_armor( &armored).is_err());
+
+    // Corrupted body.
+    let corrupted = armored.replacen( &lines[5][.. 8], &lines[5][.. 7], 1);
+    assert!( <aa::PublicKey as DecodeArmor>::decode_armor( &corrupted).is_err());
+}
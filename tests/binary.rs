@@ -0,0 +1,100 @@
+extern crate pkauth;
+extern crate ring;
+extern crate serde_json;
+
+use pkauth::ToPublicKey;
+use pkauth::asym::auth as aa;
+use pkauth::asym::enc as ae;
+use pkauth::encoding::{DecodeBinary, EncodeBinary, Format};
+use pkauth::internal::EncodePSF;
+use pkauth::sym::enc as se;
+use ring::rand::{SystemRandom, SecureRandom};
+
+#[test]
+fn binary_random_test() {
+    fn run() {
+        let rng = SystemRandom::new();
+        let key = aa::gen( &rng, &aa::Algorithm::AAEd25519).unwrap();
+        let public_key = ToPublicKey::to_public_key( &key);
+        let ae_key = ae::gen( &rng, &ae::Algorithm::AEX25519).unwrap();
+        let se_key = se::gen( &rng, &se::Algorithm::SEAesGcm256).unwrap();
+
+        let mut content = [0u8; 256].to_vec();
+        rng.fill( &mut content).unwrap();
+
+        // Keys.
+        let decoded : aa::PublicKey = DecodeBinary::decode_binary( &EncodeBinary::encode_binary( &public_key)).unwrap();
+        assert_eq!( EncodePSF::encode_psf( &decoded), EncodePSF::encode_psf( &public_key));
+        let decoded : aa::PrivateKey = DecodeBinary::decode_binary( &EncodeBinary::encode_binary( &key)).unwrap();
+        assert_eq!( EncodePSF::encode_psf( &decoded), EncodePSF::encode_psf( &key));
+        let decoded : ae::PublicKey = DecodeBinary::decode_binary( &EncodeBinary::encode_binary( &ToPublicKey::to_public_key( &ae_key))).unwrap();
+        assert_eq!( EncodePSF::encode_psf( &decoded), EncodePSF::encode_psf( &ToPublicKey::to_public_key( &ae_key)));
+        let decoded : ae::PrivateKey = DecodeBinary::decode_binary( &EncodeBinary::encode_binary( &ae_key)).unwrap();
+        assert_eq!( EncodePSF::encode_psf( &decoded), EncodePSF::encode_psf( &ae_key));
+        let decoded : se::Key = DecodeBinary::decode_binary( &EncodeBinary::encode_binary( &se_key)).unwrap();
+        assert_eq!( EncodePSF::encode_psf( &decoded), EncodePSF::encode_psf( &se_key));
+
+        // Signed and encrypted content in both formats.
+        for format in [ Format::Json, Format::Binary].iter() {
+            let signed = aa::sign_content_bs( &key, content.clone(), format).unwrap();
+            assert_eq!( aa::verify_content_bs( &public_key, signed).unwrap(), content);
+
+            let encrypted = se::encrypt_content_bs( &rng, &se_key, content.clone(), format).unwrap();
+            assert_eq!( se::decrypt_content_bs( &se_key, &encrypted).unwrap(), content);
+        }
+
+        let signed = aa::sign_content( &key, content.clone()).unwrap();
+        let decoded = DecodeBinary::decode_binary( &EncodeBinary::encode_binary( &signed)).unwrap();
+        assert_eq!( aa::verify_content( &public_key, decoded).unwrap(), content);
+    }
+
+    for _ in 1 .. 100 {
+        run()
+    }
+}
+
+#[test]
+fn binary_manual_tests() {
+    let key : se::Key = DecodeBinary::decode_binary( &hex( "01030300000020d6ab8b6e3cc8b9f3701e1ece7396b25e2a2c137468241103187fdc11ca107c31")).unwrap();
+    let json : pkauth::PKAJ<se::Key> = serde_json::from_str( "{\"key\":\"1quLbjzIufNwHh7Oc5ayXiosE3RoJBEDGH_cEcoQfDE=\",\"algorithm\":\"se-aesgcm256\"}").unwrap();
+    assert_eq!( EncodePSF::encode_psf( &key), EncodePSF::encode_psf( &json.pkaj));
+    assert_eq!( EncodeBinary::encode_binary( &key), hex( "01030300000020d6ab8b6e3cc8b9f3701e1ece7396b25e2a2c137468241103187fdc11ca107c31"));
+
+    // The ciphertext of a JSON test vector in the binary format.
+    let cipher = hex( "0105030000003279dccd92fbaa13e054192d98d115e7b7d0b8779d81eeff1ba10b5d9f59e475259eb1afd8f51456d9cd5a700008bfc60779bd");
+    assert_eq!( se::decrypt_content_bs( &key, &cipher).unwrap(), b"this is rust plaintext");
+    let decoded : se::PKASymEncrypted = DecodeBinary::decode_binary( &cipher).unwrap();
+    assert_eq!( EncodeBinary::encode_binary( &decoded), cipher);
+
+    // Truncated, trailing, or mislabeled data.
+    assert!( se::decrypt_content_bs( &key, &cipher[.. cipher.len() - 1].to_vec()).is_err());
+    let mut trailing = cipher.clone();
+    trailing.push( 0);
+    assert!( se::decrypt_content_bs( &key, &trailing).is_err());
+    let mut version = cipher.clone();
+    version[0] = 2;
+    assert!( <se::PKASymEncrypted as DecodeBinary>::decode_binary( &version).is_err());
+    let mut tag = cipher.clone();
+    tag[2] = 0x01;
+    assert!( <se::PKASymEncrypted as DecodeBinary>::decode_binary( &tag).is_err());
+    assert!( <se::Key as DecodeBinary>::decode_binary( &cipher).is_err());
+
+    // Public and private keys are distinguished.
+    let aa_key = aa::gen_from_seed( &aa::Algorithm::AAEd25519, &[5u8; 32]).unwrap();
+    let public_key = ToPublicKey::to_public_key( &aa_key);
+    let encoded = EncodeBinary::encode_binary( &public_key);
+    assert_eq!( &encoded[.. 7], &[ 0x01, 0x01, 0x01, 0x00, 0x00, 0x00, 0x20]);
+    assert!( <aa::PrivateKey as DecodeBinary>::decode_binary( &encoded).is_err());
+    assert!( <ae::PublicKey as DecodeBinary>::decode_binary( &encoded).is_err());
+
+    // Binary signatures are smaller than JSON.
+    let json = aa::sign_content_bs( &aa_key, b"content".to_vec(), &Format::Json).unwrap();
+    let binary = aa::sign_content_bs( &aa_key, b"content".to_vec(), &Format::Binary).unwrap();
+    assert!( binary.len() < json.len());
+    assert_eq!( binary[.. 3], [ 0x01, 0x04, 0x01]);
+    assert_eq!( aa::verify_content_bs( &public_key, binary).unwrap(), b"content");
+}
+
+fn hex( s : &str) -> Vec<u8> {
+    (0 .. s.len() / 2).map(|i| u8::from_str_radix( &s[2 * i .. 2 * i + 2], 16).unwrap()).collect()
+}
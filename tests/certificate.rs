@@ -0,0 +1,101 @@
+extern crate pkauth;
+extern crate ring;
+extern crate serde_json;
+
+use pkauth::{ToIdentifier, ToPublicKey};
+use pkauth::asym::auth as aa;
+use pkauth::asym::enc as ae;
+use pkauth::certificate;
+use pkauth::certificate::{Certificate, SubjectKey};
+use pkauth::internal;
+use pkauth::internal::EncodePSF;
+use pkauth::metadata::{FixedClock, Usage};
+use ring::rand::SystemRandom;
+
+#[test]
+fn certificate_random_test() {
+    fn run() {
+        let rng = SystemRandom::new();
+        let root = aa::gen( &rng, &aa::Algorithm::AAEd25519).unwrap();
+        let intermediate = aa::gen( &rng, &aa::Algorithm::AAEd25519).unwrap();
+        let member = aa::gen( &rng, &aa::Algorithm::AAEd25519).unwrap();
+        let member_enc = ae::gen( &rng, &ae::Algorithm::AEX25519).unwrap();
+        let root_public = ToPublicKey::to_public_key( &root);
+        let intermediate_public = ToPublicKey::to_public_key( &intermediate);
+        let roots = vec![ ToPublicKey::to_public_key( &aa::gen( &rng, &aa::Algorithm::AAEd25519).unwrap()), ToPublicKey::to_public_key( &root)];
+
+        let ca = certificate::issue( &root, &Certificate::new( SubjectKey::Auth( ToPublicKey::to_public_key( &intermediate)), &root_public, 100, 1000, vec![ Usage::Certify])).unwrap();
+        let signing = certificate::issue( &intermediate, &Certificate::new( SubjectKey::Auth( ToPublicKey::to_public_key( &member)), &intermediate_public, 200, 900, vec![ Usage::Sign, Usage::Auth])).unwrap();
+        let encryption = certificate::issue( &intermediate, &Certificate::new( SubjectKey::Enc( ToPublicKey::to_public_key( &member_enc)), &intermediate_public, 200, 900, vec![ Usage::Encrypt])).unwrap();
+
+        // Valid chains.
+        let leaf = certificate::validate_chain( &FixedClock( 500), &roots, vec![ signing.clone(), ca.clone()], Usage::Sign).unwrap();
+        assert!( leaf.subject == ToIdentifier::to_identifier( &ToPublicKey::to_public_key( &member)));
+        match leaf.subject_key {
+            SubjectKey::Auth( ref key) => assert_eq!( EncodePSF::encode_psf( key), EncodePSF::encode_psf( &ToPublicKey::to_public_key( &member))),
+            SubjectKey::Enc( _) => panic!( "Expected a signing key."),
+        }
+        let leaf = certificate::validate_chain( &FixedClock( 500), &roots, vec![ encryption.clone(), ca.clone()], Usage::Encrypt).unwrap();
+        assert!( leaf.subject == ToIdentifier::to_identifier( &ToPublicKey::to_public_key( &member_enc)));
+        assert!( certificate::validate_chain( &FixedClock( 500), &roots, vec![ ca.clone()], Usage::Certify).is_ok());
+
+        // Usage, expiry, untrusted roots, and broken chains.
+        assert_eq!( certificate::validate_chain( &FixedClock( 500), &roots, vec![ signing.clone(), ca.clone()], Usage::Encrypt).err(), Some( "Certificate usage does not allow this operation."));
+        assert_eq!( certificate::validate_chain( &FixedClock( 900), &roots, vec![ signing.clone(), ca.clone()], Usage::Sign).err(), Some( "Certificate has expired."));
+        assert_eq!( certificate::validate_chain( &FixedClock( 150), &roots, vec![ signing.clone(), ca.clone()], Usage::Sign).err(), Some( "Certificate is not valid yet."));
+        assert_eq!( certificate::validate_chain( &FixedClock( 500), &roots[.. 1], vec![ signing.clone(), ca.clone()], Usage::Sign).err(), Some( "Certificate chain is not issued by a trusted root."));
+        assert!( certificate::validate_chain( &FixedClock( 500), &roots, vec![ signing.clone()], Usage::Sign).is_err());
+        assert!( certificate::validate_chain( &FixedClock( 500), &roots, vec![ signing.clone(), encryption.clone(), ca.clone()], Usage::Sign).is_err());
+        assert_eq!( certificate::validate_chain( &FixedClock( 500), &roots, vec![], Usage::Sign).err(), Some( "Empty certificate chain."));
+
+        // Issuers must be certified for issuing certificates.
+        let member_issued = certificate::issue( &member, &Certificate::new( SubjectKey::Auth( ToPublicKey::to_public_key( &root)), &ToPublicKey::to_public_key( &member), 200, 900, vec![ Usage::Sign])).unwrap();
+        assert_eq!( certificate::validate_chain( &FixedClock( 500), &roots, vec![ member_issued, signing.clone(), ca.clone()], Usage::Sign).err(), Some( "Certificate usage does not allow this operation."));
+
+        // Certificates name their issuer.
+        assert!( certificate::issue( &member, &Certificate::new( SubjectKey::Auth( ToPublicKey::to_public_key( &member)), &root_public, 200, 900, vec![ Usage::Sign])).is_err());
+    }
+
+    for _ in 1 .. 100 {
+        run()
+    }
+}
+
+#[test]
+fn certificate_manual_tests() {
+    let root = aa::gen_from_seed( &aa::Algorithm::AAEd25519, &[1u8; 32]).unwrap();
+    let member = aa::gen_from_seed( &aa::Algorithm::AAEd25519, &[2u8; 32]).unwrap();
+    let root_public = ToPublicKey::to_public_key( &root);
+
+    let mut c = Certificate::new( SubjectKey::Auth( ToPublicKey::to_public_key( &member)), &root_public, 1700000000, 1800000000, vec![ Usage::Sign]);
+    c.extensions.insert( "organization".to_string(), serde_json::Value::String( "pkauth".to_string()));
+    let json = serde_json::to_string( &c).unwrap();
+    assert_eq!( json, format!( "{{\"subject_key\":{{\"public_key\":\"{}\",\"algorithm\":\"aa-ed25519\"}},\"subject\":\"{}\",\"issuer\":\"{}\",\"not_before\":1700000000,\"not_after\":1800000000,\"usage\":[\"sign\"],\"extensions\":{{\"organization\":\"pkauth\"}}}}",
+        internal::serialize_psf( &ToPublicKey::to_public_key( &member)), ToIdentifier::to_identifier( &ToPublicKey::to_public_key( &member)), ToIdentifier::to_identifier( &root_public)));
+    let decoded : Certificate = internal::from_str_strict( &json).unwrap();
+    assert_eq!( serde_json::to_string( &decoded).unwrap(), json);
+
+    // Certificates are PKASigned, so they survive the other encodings.
+    let signed = certificate::issue( &root, &c).unwrap();
+    let token : aa::PKASigned = signed.to_string().parse().unwrap();
+    let verified = certificate::verify( &root_public, token).unwrap();
+    assert_eq!( verified.extensions.get( "organization").and_then(|v| v.as_str()), Some( "pkauth"));
+    assert!( certificate::verify( &ToPublicKey::to_public_key( &member), signed.clone()).is_err());
+
+    // Extensions are optional.
+    let minimal = json.replace( ",\"extensions\":{\"organization\":\"pkauth\"}", "");
+    assert!( serde_json::from_str::<Certificate>( &minimal).unwrap().extensions.is_empty());
+
+    // Inconsistent certificates.
+    let other = ToIdentifier::to_identifier( &root_public).to_string();
+    let invalid = [
+        json.replacen( &ToIdentifier::to_identifier( &ToPublicKey::to_public_key( &member)).to_string(), &other, 1),
+        json.replace( "\"not_after\":1800000000", "\"not_after\":1700000000"),
+        json.replace( "[\"sign\"]", "[\"sign\",\"wrap\"]"),
+        json.replace( "aa-ed25519", "se-aesgcm256"),
+        minimal.replace( ",\"usage\":[\"sign\"]", ""),
+    ];
+    for json in invalid.iter() {
+        assert!( serde_json::from_str::<Certificate>( json).is_err(), "{}", json);
+    }
+}
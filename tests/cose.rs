@@ -0,0 +1,130 @@
+extern crate pkauth;
+extern crate ring;
+extern crate serde_json;
+
+use pkauth::ToPublicKey;
+use pkauth::asym::auth as aa;
+use pkauth::asym::enc as ae;
+use pkauth::asym::enc::hpke;
+use pkauth::encoding::{DecodeCbor, EncodeCbor};
+use pkauth::encoding::cbor;
+use pkauth::encoding::cbor::Value;
+use pkauth::encoding::cose;
+use pkauth::envelope;
+use pkauth::internal::EncodePSF;
+use pkauth::sym::enc as se;
+use ring::rand::{SystemRandom, SecureRandom};
+
+#[test]
+fn cose_random_test() {
+    fn run() {
+        let rng = SystemRandom::new();
+        let key = aa::gen( &rng, &aa::Algorithm::AAEd25519).unwrap();
+        let other = aa::gen( &rng, &aa::Algorithm::AAEd25519).unwrap();
+        let public_key = ToPublicKey::to_public_key( &key);
+        let ae_key = ae::gen( &rng, &ae::Algorithm::AEX25519).unwrap();
+        let se_key = se::gen( &rng, &se::Algorithm::SEAesGcm256).unwrap();
+
+        let mut content = [0u8; 64].to_vec();
+        rng.fill( &mut content).unwrap();
+
+        // COSE_Key round trips.
+        let decoded : aa::PublicKey = DecodeCbor::decode_cbor( &EncodeCbor::encode_cbor( &public_key)).unwrap();
+        assert_eq!( EncodePSF::encode_psf( &decoded), EncodePSF::encode_psf( &public_key));
+        let decoded : aa::PrivateKey = DecodeCbor::decode_cbor( &EncodeCbor::encode_cbor( &key)).unwrap();
+        assert_eq!( EncodePSF::encode_psf( &decoded), EncodePSF::encode_psf( &key));
+        let decoded : ae::PublicKey = DecodeCbor::decode_cbor( &EncodeCbor::encode_cbor( &ToPublicKey::to_public_key( &ae_key))).unwrap();
+        assert_eq!( EncodePSF::encode_psf( &decoded), EncodePSF::encode_psf( &ToPublicKey::to_public_key( &ae_key)));
+        let decoded : ae::PrivateKey = DecodeCbor::decode_cbor( &EncodeCbor::encode_cbor( &ae_key)).unwrap();
+        assert_eq!( EncodePSF::encode_psf( &decoded), EncodePSF::encode_psf( &ae_key));
+        let decoded : se::Key = DecodeCbor::decode_cbor( &EncodeCbor::encode_cbor( &se_key)).unwrap();
+        assert_eq!( EncodePSF::encode_psf( &decoded), EncodePSF::encode_psf( &se_key));
+        assert!( <aa::PublicKey as DecodeCbor>::decode_cbor( &EncodeCbor::encode_cbor( &ToPublicKey::to_public_key( &ae_key))).is_err());
+
+        // COSE_Sign1.
+        let signed = cose::sign1( &key, &content, b"aad").unwrap();
+        assert_eq!( cose::verify1( &public_key, &signed, b"aad").unwrap(), content);
+        assert!( cose::verify1( &public_key, &signed, b"").is_err());
+        assert!( cose::verify1( &ToPublicKey::to_public_key( &other), &signed, b"aad").is_err());
+
+        // COSE_Encrypt0.
+        let encrypted = cose::encrypt0( &rng, &se_key, &content, b"aad").unwrap();
+        assert_eq!( cose::decrypt0( &se_key, &encrypted, b"aad").unwrap(), content);
+        assert!( cose::decrypt0( &se_key, &encrypted, b"").is_err());
+        assert!( cose::decrypt0( &se::gen( &rng, &se::Algorithm::SEAesGcm256).unwrap(), &encrypted, b"aad").is_err());
+
+        // CBOR messages.
+        let signed = aa::sign_content( &key, content.clone()).unwrap();
+        let decoded = DecodeCbor::decode_cbor( &EncodeCbor::encode_cbor( &signed)).unwrap();
+        assert_eq!( aa::verify_content( &public_key, decoded).unwrap(), content);
+
+        let encrypted = se::encrypt_content( &rng, &se_key, content.clone()).unwrap();
+        let decoded = DecodeCbor::decode_cbor( &EncodeCbor::encode_cbor( &encrypted)).unwrap();
+        assert_eq!( se::decrypt_content( &se_key, decoded).unwrap(), content);
+
+        let sealed = envelope::seal_content( &rng, &hpke::Algorithm::HPKEX25519AesGcm256, &key, &ToPublicKey::to_public_key( &ae_key), content.clone()).unwrap();
+        let encoded = EncodeCbor::encode_cbor( &sealed);
+        assert_eq!( cbor::decode( &encoded).unwrap().field( "key").unwrap().field( "algorithm").unwrap().as_text(), Some( "hpke-x25519-aesgcm256"));
+        let decoded = DecodeCbor::decode_cbor( &encoded).unwrap();
+        assert_eq!( envelope::open_content( &ae_key, decoded).unwrap().1, content);
+    }
+
+    for _ in 1 .. 100 {
+        run()
+    }
+}
+
+#[test]
+fn cose_manual_tests() {
+    // RFC9052, Appendix C.7.2.
+    let se_key : se::Key = DecodeCbor::decode_cbor( &hex( "a30104024a6f75722d736563726574205820849b57219dae48de646d07dbb533566e976686457c1491be3a76dcea6c427188")).unwrap();
+    assert_eq!( EncodePSF::encode_psf( &se_key), hex( "849b57219dae48de646d07dbb533566e976686457c1491be3a76dcea6c427188"));
+
+    // COSE_Encrypt0 with A256GCM. RFC9052's Encrypt0 examples use 128 bit AES keys, which are not supported, so this vector is not from the RFC.
+    // It was generated with the Python cryptography package's AESGCM, using the key above and the Enc_structure of RFC9052, Section 5.3.
+    let encrypted = hex( "d08343a10103a1054c02d1f7e6f26c43d4868d87ce5824adcd02cc9281e101b35b7e71efe16b08a778023c96a5a2971a1622017e4c5116381289d4");
+    assert_eq!( cose::decrypt0( &se_key, &encrypted, b"").unwrap(), b"This is the content.");
+    assert!( cose::decrypt0( &se_key, &encrypted, b"aad").is_err());
+
+    // COSE_Sign1 with the key of RFC8032, Section 7.1, Test 1. RFC9052's Sign1 examples use ES256, which is not supported, so this vector is not from the RFC.
+    // Ed25519 is deterministic, and the signature was checked with the Python cryptography package over the Sig_structure of RFC9052, Section 4.4.
+    let key = aa::gen_from_seed( &aa::Algorithm::AAEd25519, &hex( "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60")).unwrap();
+    let public_key = ToPublicKey::to_public_key( &key);
+    assert_eq!( EncodeCbor::encode_cbor( &public_key), hex( "a4010103272006215820d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a"));
+    let signed = cose::sign1( &key, b"This is the content.", b"").unwrap();
+    assert_eq!( signed, hex( "d28443a10127a10458189766bc6a50b376bd6fb25ecc5bd3288a663bbec9eb09c41854546869732069732074686520636f6e74656e742e58406354488f9f290e36cd80e23762e664a5cb03e4267c66a8cffaef7c66d89a40bf2cbb8222432a08e5ee410d8b540c6931d26fb6af673f7e2100655d8bae765c04"));
+    assert_eq!( cose::verify1( &public_key, &signed, b"").unwrap(), b"This is the content.");
+
+    // Untagged messages are accepted, other tags are not.
+    let untagged = match cbor::decode( &signed).unwrap() {
+        Value::Tag( _, v) => cbor::encode( &v),
+        _ => panic!(),
+    };
+    assert!( cose::verify1( &public_key, &untagged, b"").is_ok());
+    assert!( cose::decrypt0( &se_key, &signed, b"").is_err());
+
+    // Deterministic and strict CBOR.
+    assert_eq!( cbor::encode( &Value::Map( vec![ (Value::integer( -1), Value::Null), (Value::integer( 10), Value::Bool( true))])), hex( "a20af520f6"));
+    assert_eq!( cbor::decode( &hex( "3903e7")).unwrap(), Value::integer( -1000));
+    assert_eq!( cbor::decode( &hex( "3903e7")).unwrap().as_integer(), Some( -1000));
+
+    // RFC8949, Appendix A: the extremes of the integer range.
+    assert_eq!( cbor::encode( &Value::Unsigned( ::std::u64::MAX)), hex( "1bffffffffffffffff"));
+    assert_eq!( cbor::encode( &Value::Negative( ::std::u64::MAX)), hex( "3bffffffffffffffff"));
+    assert_eq!( cbor::decode( &hex( "3bffffffffffffffff")).unwrap(), Value::Negative( ::std::u64::MAX));
+    assert_eq!( cbor::decode( &hex( "3bffffffffffffffff")).unwrap().as_integer(), None);
+    assert_eq!( Value::integer( ::std::i64::MIN), Value::Negative( ::std::i64::MAX as u64));
+    assert!( cbor::decode( &hex( "1817")).is_err());
+    assert!( cbor::decode( &hex( "a201000100")).is_err());
+    assert!( cbor::decode( &hex( "0000")).is_err());
+    assert!( cbor::decode( &hex( "5f4100ff")).is_err());
+    assert!( cbor::decode( &hex( "81818181818181818181818181818181818100")).is_err());
+
+    // CBOR is more compact than JSON.
+    let signed = aa::sign_content( &key, b"This is the content.".to_vec()).unwrap();
+    assert!( EncodeCbor::encode_cbor( &signed).len() < serde_json::to_vec( &signed).unwrap().len());
+}
+
+fn hex( s : &str) -> Vec<u8> {
+    (0 .. s.len() / 2).map(|i| u8::from_str_radix( &s[2 * i .. 2 * i + 2], 16).unwrap()).collect()
+}
@@ -0,0 +1,61 @@
+extern crate pkauth;
+extern crate ring;
+extern crate serde_json;
+
+use pkauth::{PKAJ, ToIdentifier, ToPublicKey};
+use pkauth::asym::auth as aa;
+use pkauth::asym::enc as ae;
+use pkauth::derive::MasterSecret;
+use pkauth::sym::enc as se;
+use ring::rand::SystemRandom;
+
+#[test]
+fn derive_random_test() {
+    fn run() {
+        let rng = SystemRandom::new();
+        let master = MasterSecret::gen( &rng).unwrap();
+        let restored = MasterSecret::from_bytes( master.as_bytes()).unwrap();
+
+        // Derivation is deterministic.
+        let path = ["tenant-42", "signing"];
+        let aa_key = aa::gen_from_master( &master.derive_path( &path), &aa::Algorithm::AAEd25519).unwrap();
+        let aa_key_r = aa::gen_from_master( &restored.derive_path( &path), &aa::Algorithm::AAEd25519).unwrap();
+        assert_eq!( ToIdentifier::to_identifier( &aa_key), ToIdentifier::to_identifier( &aa_key_r));
+
+        let ae_key = ae::gen_from_master( &master, &ae::Algorithm::AEX25519).unwrap();
+        let ae_key_r = ae::gen_from_master( &restored, &ae::Algorithm::AEX25519).unwrap();
+        assert_eq!( ToPublicKey::to_public_key( &ae_key), ToPublicKey::to_public_key( &ae_key_r));
+
+        // Different contexts give different keys.
+        let se_key = se::gen_from_master( &master.child( "tenant-42"), &se::Algorithm::SEAesGcm256).unwrap();
+        let se_key_o = se::gen_from_master( &master.child( "tenant-43"), &se::Algorithm::SEAesGcm256).unwrap();
+        assert!( ToIdentifier::to_identifier( &se_key) != ToIdentifier::to_identifier( &se_key_o));
+    }
+
+    for _ in 1 .. 100 {
+        run()
+    }
+}
+
+#[test]
+fn derive_manual_tests() {
+    // From RFC8032.
+    let seed = [0x9d, 0x61, 0xb1, 0x9d, 0xef, 0xfd, 0x5a, 0x60, 0xba, 0x84, 0x4a, 0xf4, 0x92, 0xec, 0x2c, 0xc4, 0x44, 0x49, 0xc5, 0x69, 0x7b, 0x32, 0x69, 0x19, 0x70, 0x3b, 0xac, 0x03, 0x1c, 0xae, 0x7f, 0x60];
+    let key = aa::gen_from_seed( &aa::Algorithm::AAEd25519, &seed).unwrap();
+    let pk = serde_json::to_string( &PKAJ{ pkaj : &ToPublicKey::to_public_key( &key)}).unwrap();
+    assert_eq!( pk, "{\"public_key\":\"11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo=\",\"algorithm\":\"aa-ed25519\"}");
+
+    assert!( aa::gen_from_seed( &aa::Algorithm::AAEd25519, &seed[1..]).is_err());
+    assert!( MasterSecret::from_bytes( &seed[1..]).is_err());
+
+    let master : Vec<u8> = (0 .. 32).collect();
+    let master = MasterSecret::from_bytes( &master).unwrap().derive_path( &["tenant-42", "signing"]);
+
+    let key = se::gen_from_master( &master, &se::Algorithm::SEAesGcm256).unwrap();
+    let key = serde_json::to_string( &PKAJ{ pkaj : &key}).unwrap();
+    assert_eq!( key, "{\"key\":\"CeXUZ4FUhwUxlc2NQEQssGs-sA1z0aijSOikwV0-F1k=\",\"algorithm\":\"se-aesgcm256\"}");
+
+    let key = aa::gen_from_master( &master, &aa::Algorithm::AAEd25519).unwrap();
+    let pk = serde_json::to_string( &PKAJ{ pkaj : &ToPublicKey::to_public_key( &key)}).unwrap();
+    assert_eq!( pk, "{\"public_key\":\"aWEJlA9f8Lqk1kEfP8GVp-3i9xtUIMOx-y31eAwMyrE=\",\"algorithm\":\"aa-ed25519\"}");
+}
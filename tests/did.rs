@@ -0,0 +1,64 @@
+extern crate pkauth;
+extern crate ring;
+extern crate serde_json;
+
+use pkauth::{PKAJ, ToPublicKey};
+use pkauth::asym::auth as aa;
+use pkauth::asym::enc as ae;
+use pkauth::did;
+use ring::rand::SystemRandom;
+
+#[test]
+fn did_random_test() {
+    fn run() {
+        let rng = SystemRandom::new();
+        let pk = ToPublicKey::to_public_key( &aa::gen( &rng, &aa::Algorithm::AAEd25519).unwrap());
+        let did = did::to_did_key( &pk);
+        assert!( did.starts_with( "did:key:z6Mk"));
+        let decoded : aa::PublicKey = did::from_did_key( &did).unwrap();
+        assert_eq!( decoded, pk);
+        assert!( did::from_did_key::<ae::PublicKey>( &did).is_err());
+
+        let pk = ToPublicKey::to_public_key( &ae::gen( &rng, &ae::Algorithm::AEX25519).unwrap());
+        let did = did::to_did_key( &pk);
+        assert!( did.starts_with( "did:key:z6LS"));
+        let decoded : ae::PublicKey = did::from_did_key( &did).unwrap();
+        assert_eq!( decoded, pk);
+    }
+
+    for _ in 1 .. 100 {
+        run()
+    }
+}
+
+#[test]
+fn did_manual_tests() {
+    // Public keys from RFC8032 and RFC7748.
+    let pk = "{\"public_key\":\"11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo=\",\"algorithm\":\"aa-ed25519\"}";
+    let pk : PKAJ<aa::PublicKey> = serde_json::from_str( pk).unwrap();
+    let pk = pk.pkaj;
+    let xpk = "{\"public_key\":\"hSDwCYkwp1R0i33ctD73Wg2_Og0mOBr066SpjqqbTmo=\",\"algorithm\":\"ae-x25519\"}";
+    let xpk : PKAJ<ae::PublicKey> = serde_json::from_str( xpk).unwrap();
+    let xpk = xpk.pkaj;
+
+    let did = "did:key:z6MktwupdmLXVVqTzCw4i46r4uGyosGXRnR3XjN4Zq7oMMsw";
+    assert_eq!( did::to_did_key( &pk), did);
+    let decoded : aa::PublicKey = did::from_did_key( "did:key:z6MktwupdmLXVVqTzCw4i46r4uGyosGXRnR3XjN4Zq7oMMsw#z6MktwupdmLXVVqTzCw4i46r4uGyosGXRnR3XjN4Zq7oMMsw").unwrap();
+    assert_eq!( decoded, pk);
+    assert!( did::from_did_key::<aa::PublicKey>( "did:web:z6MktwupdmLXVVqTzCw4i46r4uGyosGXRnR3XjN4Zq7oMMsw").is_err());
+    assert!( did::from_did_key::<aa::PublicKey>( "did:key:6MktwupdmLXVVqTzCw4i46r4uGyosGXRnR3XjN4Zq7oMMsw").is_err());
+
+    let document = did::document( &pk).unwrap();
+    let expected = "{\"@context\":[\"https://www.w3.org/ns/did/v1\",\"https://w3id.org/security/suites/ed25519-2020/v1\",\"https://w3id.org/security/suites/x25519-2020/v1\"],\"assertionMethod\":[\"did:key:z6MktwupdmLXVVqTzCw4i46r4uGyosGXRnR3XjN4Zq7oMMsw#z6MktwupdmLXVVqTzCw4i46r4uGyosGXRnR3XjN4Zq7oMMsw\"],\"authentication\":[\"did:key:z6MktwupdmLXVVqTzCw4i46r4uGyosGXRnR3XjN4Zq7oMMsw#z6MktwupdmLXVVqTzCw4i46r4uGyosGXRnR3XjN4Zq7oMMsw\"],\"capabilityDelegation\":[\"did:key:z6MktwupdmLXVVqTzCw4i46r4uGyosGXRnR3XjN4Zq7oMMsw#z6MktwupdmLXVVqTzCw4i46r4uGyosGXRnR3XjN4Zq7oMMsw\"],\"capabilityInvocation\":[\"did:key:z6MktwupdmLXVVqTzCw4i46r4uGyosGXRnR3XjN4Zq7oMMsw#z6MktwupdmLXVVqTzCw4i46r4uGyosGXRnR3XjN4Zq7oMMsw\"],\"id\":\"did:key:z6MktwupdmLXVVqTzCw4i46r4uGyosGXRnR3XjN4Zq7oMMsw\",\"keyAgreement\":[\"did:key:z6MktwupdmLXVVqTzCw4i46r4uGyosGXRnR3XjN4Zq7oMMsw#z6LSrEnPXPcLyNLKJPhdJ1eWqyYKARWket5BbiN1rjdUsQ9b\"],\"verificationMethod\":[{\"controller\":\"did:key:z6MktwupdmLXVVqTzCw4i46r4uGyosGXRnR3XjN4Zq7oMMsw\",\"id\":\"did:key:z6MktwupdmLXVVqTzCw4i46r4uGyosGXRnR3XjN4Zq7oMMsw#z6MktwupdmLXVVqTzCw4i46r4uGyosGXRnR3XjN4Zq7oMMsw\",\"publicKeyMultibase\":\"z6MktwupdmLXVVqTzCw4i46r4uGyosGXRnR3XjN4Zq7oMMsw\",\"type\":\"Ed25519VerificationKey2020\"},{\"controller\":\"did:key:z6MktwupdmLXVVqTzCw4i46r4uGyosGXRnR3XjN4Zq7oMMsw\",\"id\":\"did:key:z6MktwupdmLXVVqTzCw4i46r4uGyosGXRnR3XjN4Zq7oMMsw#z6LSrEnPXPcLyNLKJPhdJ1eWqyYKARWket5BbiN1rjdUsQ9b\",\"publicKeyMultibase\":\"z6LSrEnPXPcLyNLKJPhdJ1eWqyYKARWket5BbiN1rjdUsQ9b\",\"type\":\"X25519KeyAgreementKey2020\"}]}";
+    assert_eq!( serde_json::to_string( &document).unwrap(), expected);
+
+    // Example from the did:key specification.
+    let spec : aa::PublicKey = did::from_did_key( "did:key:z6MkhaXgBZDvotDkL5257faiztiGiC2QtKLGpbnnEGta2doK").unwrap();
+    let document = did::document( &spec).unwrap();
+    assert_eq!( document["keyAgreement"][0], "did:key:z6MkhaXgBZDvotDkL5257faiztiGiC2QtKLGpbnnEGta2doK#z6LSj72tK8brWgZja8NLRwPigth2T9QRiG1uH9oKZuKjdh9p");
+
+    let document = did::key_agreement_document( &xpk);
+    assert_eq!( document["id"], "did:key:z6LSkdrX4EvewpktHBjvNxRDogPdC5iVF8LT3LPKefGAgi89");
+    assert_eq!( document["keyAgreement"][0], "did:key:z6LSkdrX4EvewpktHBjvNxRDogPdC5iVF8LT3LPKefGAgi89#z6LSkdrX4EvewpktHBjvNxRDogPdC5iVF8LT3LPKefGAgi89");
+    assert!( document.get( "authentication").is_none());
+}
@@ -0,0 +1,80 @@
+extern crate pkauth;
+extern crate ring;
+extern crate serde_json;
+
+use pkauth::{PKAIdentifier, PKAJ, ToPublicKey, ToVersionedIdentifier};
+use pkauth::asym::auth as aa;
+use pkauth::asym::enc as ae;
+use pkauth::encoding::{bech32, DecodeBech32, DecodeMultibase, EncodeBech32, EncodeMultibase};
+use ring::rand::SystemRandom;
+
+#[test]
+fn encoding_random_test() {
+    fn run() {
+        let rng = SystemRandom::new();
+        let key = aa::gen( &rng, &aa::Algorithm::AAEd25519).unwrap();
+        let pk = ToPublicKey::to_public_key( &key);
+
+        let s = EncodeBech32::encode_bech32( &pk);
+        assert!( s.starts_with( "pkapub1"));
+        let decoded : aa::PublicKey = DecodeBech32::decode_bech32( &s).unwrap();
+        assert_eq!( decoded, pk);
+        assert!( <ae::PublicKey as DecodeBech32>::decode_bech32( &s).is_err());
+
+        let s = EncodeMultibase::encode_multibase( &pk);
+        assert!( s.starts_with( "z6Mk"));
+        let decoded : aa::PublicKey = DecodeMultibase::decode_multibase( &s).unwrap();
+        assert_eq!( decoded, pk);
+        assert!( <ae::PublicKey as DecodeMultibase>::decode_multibase( &s).is_err());
+
+        let identifier = ToVersionedIdentifier::to_versioned_identifier( &key);
+        let s = EncodeBech32::encode_bech32( &identifier);
+        assert!( s.starts_with( "pkaid1"));
+        let decoded : PKAIdentifier = DecodeBech32::decode_bech32( &s).unwrap();
+        assert_eq!( decoded, identifier);
+        let decoded : PKAIdentifier = DecodeBech32::decode_bech32( &s.to_uppercase()).unwrap();
+        assert_eq!( decoded, identifier);
+
+        let key = ae::gen( &rng, &ae::Algorithm::AEX25519).unwrap();
+        let pk = ToPublicKey::to_public_key( &key);
+        let decoded : ae::PublicKey = DecodeBech32::decode_bech32( &EncodeBech32::encode_bech32( &pk)).unwrap();
+        assert_eq!( decoded, pk);
+        let decoded : ae::PublicKey = DecodeMultibase::decode_multibase( &EncodeMultibase::encode_multibase( &pk)).unwrap();
+        assert_eq!( decoded, pk);
+    }
+
+    for _ in 1 .. 100 {
+        run()
+    }
+}
+
+#[test]
+fn encoding_manual_tests() {
+    // From BIP-350.
+    assert_eq!( bech32::decode( "A1LQFN3A").unwrap(), ("a".to_owned(), vec![]));
+    assert_eq!( bech32::decode( "?1v759aa").unwrap(), ("?".to_owned(), vec![]));
+    assert!( bech32::decode( "abcdef1l7aum6echk45nj3s0wdvt2fg8x9yrzpqzd3ryx").is_ok());
+    assert!( bech32::decode( "A1G7SGD8").is_err()); // Bech32 checksum.
+    assert!( bech32::decode( "1xj0phk").is_err());
+    assert!( bech32::decode( "M1VUXWEZ").is_err());
+
+    // Public key from RFC8032.
+    let pk = "{\"public_key\":\"11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo=\",\"algorithm\":\"aa-ed25519\"}";
+    let pk : PKAJ<aa::PublicKey> = serde_json::from_str( pk).unwrap();
+    let pk = pk.pkaj;
+    assert_eq!( EncodeBech32::encode_bech32( &pk), "pkapub1q8t44xqps2cs4d74f0ld8jtyquaqactj70d2vge94upp568hqag35mrufpg");
+    assert_eq!( EncodeMultibase::encode_multibase( &pk), "z6MktwupdmLXVVqTzCw4i46r4uGyosGXRnR3XjN4Zq7oMMsw");
+
+    let identifier = ToVersionedIdentifier::to_versioned_identifier( &pk);
+    assert_eq!( identifier.to_string(), "YgJQLmum1LEzimjaxPw24AuCZLJGWWw4B");
+    assert_eq!( EncodeBech32::encode_bech32( &identifier), "pkaid1q9dh4fsvxcj08vyz645z8zglvc8dswcjx4cq2u7g00txyg");
+    assert!( <PKAIdentifier as DecodeBech32>::decode_bech32( "pkaid1q9dh4fsvxcj08vyz645z8zglvc8dswcjx4cq2u7g00txyq").is_err());
+    assert!( <aa::PublicKey as DecodeBech32>::decode_bech32( "pkaid1q9dh4fsvxcj08vyz645z8zglvc8dswcjx4cq2u7g00txyg").is_err());
+
+    // Public key from RFC7748.
+    let pk = "{\"public_key\":\"hSDwCYkwp1R0i33ctD73Wg2_Og0mOBr066SpjqqbTmo=\",\"algorithm\":\"ae-x25519\"}";
+    let pk : PKAJ<ae::PublicKey> = serde_json::from_str( pk).unwrap();
+    let pk = pk.pkaj;
+    assert_eq!( EncodeBech32::encode_bech32( &pk), "pkapub1q2zjpuqf3yc2w4r53d7aedp77adqm0e6p5nrsxh5awj2nr42nd8x5lh0c5s");
+    assert_eq!( EncodeMultibase::encode_multibase( &pk), "z6LSkdrX4EvewpktHBjvNxRDogPdC5iVF8LT3LPKefGAgi89");
+}
@@ -0,0 +1,87 @@
+extern crate pkauth;
+extern crate ring;
+#[macro_use]
+extern crate serde_json;
+
+use pkauth::{ToIdentifier, ToPublicKey};
+use pkauth::asym::auth as aa;
+use pkauth::asym::enc as ae;
+use pkauth::asym::enc::hpke;
+use pkauth::envelope;
+use ring::rand::{SystemRandom, SecureRandom};
+
+#[test]
+fn envelope_random_test() {
+    fn run() {
+        let rng = SystemRandom::new();
+        let alice = aa::gen( &rng, &aa::Algorithm::AAEd25519).unwrap();
+        let mallory = aa::gen( &rng, &aa::Algorithm::AAEd25519).unwrap();
+        let bob = ae::gen( &rng, &ae::Algorithm::AEX25519).unwrap();
+        let carol = ae::gen( &rng, &ae::Algorithm::AEX25519).unwrap();
+        let alice_pk = ToPublicKey::to_public_key( &alice);
+        let bob_pk = ToPublicKey::to_public_key( &bob);
+
+        let mut content = [0u8; 256].to_vec();
+        rng.fill( &mut content).unwrap();
+
+        let sealed = envelope::seal_content( &rng, &hpke::Algorithm::HPKEX25519ChaCha20Poly1305, &alice, &bob_pk, content.clone()).unwrap();
+        let (sender, opened) = envelope::open_content( &bob, sealed).unwrap();
+        assert_eq!( sender, ToIdentifier::to_identifier( &alice_pk));
+        assert_ne!( sender, ToIdentifier::to_identifier( &ToPublicKey::to_public_key( &mallory)));
+        assert_eq!( opened, content);
+
+        let sealed = envelope::seal_content( &rng, &hpke::Algorithm::HPKEX25519AesGcm256, &alice, &bob_pk, content.clone()).unwrap();
+        assert!( envelope::open_content( &carol, sealed).is_err());
+
+        let sealed = envelope::seal_bs( &rng, &hpke::Algorithm::HPKEX25519AesGcm128, &alice, &bob_pk, &content).unwrap();
+        let (sender, opened) : (_, Vec<u8>) = envelope::open_bs( &bob, &sealed).unwrap();
+        assert_eq!( sender, ToIdentifier::to_identifier( &alice_pk));
+        assert_eq!( opened, content);
+    }
+
+    for _ in 1 .. 100 {
+        run()
+    }
+}
+
+#[test]
+fn envelope_manual_tests() {
+    let rng = SystemRandom::new();
+    let alice = aa::gen_from_seed( &aa::Algorithm::AAEd25519, &[1u8; 32]).unwrap();
+    let bob = ae::gen_from_seed( &ae::Algorithm::AEX25519, &[2u8; 32]).unwrap();
+    let carol = ae::gen_from_seed( &ae::Algorithm::AEX25519, &[3u8; 32]).unwrap();
+    let bob_pk = ToPublicKey::to_public_key( &bob);
+    let carol_pk = ToPublicKey::to_public_key( &carol);
+
+    let sealed = envelope::seal( &rng, &hpke::Algorithm::HPKEX25519ChaCha20Poly1305, &alice, &bob_pk, &"Meet at noon.").unwrap();
+    let json = serde_json::to_value( &sealed).unwrap();
+    assert_eq!( json["key"]["algorithm"], "hpke-x25519-chacha20poly1305");
+    assert!( json["ciphertext"].is_string());
+
+    // Tampered ciphertext.
+    let mut tampered = json.clone();
+    let ciphertext = tampered["ciphertext"].as_str().unwrap().to_string();
+    let flipped = if ciphertext.starts_with( "A") { "B" } else { "A" };
+    tampered["ciphertext"] = serde_json::Value::String( format!( "{}{}", flipped, &ciphertext[1 ..]));
+    assert!( envelope::open::<String>( &bob, serde_json::from_value( tampered).unwrap()).is_err());
+
+    // Bob decrypts Alice's signed content and forwards it to Carol, who detects that it was signed for Bob.
+    let key : hpke::EncapsulatedKey = serde_json::from_value( json["key"].clone()).unwrap();
+    let ciphertext = from_base64url( json["ciphertext"].as_str().unwrap());
+    let inner = hpke::open( &key, &bob, &hpke::ReceiverMode::Base, b"PKAuth envelope", &[], &ciphertext).unwrap();
+    let (key, ciphertext) = hpke::seal( &rng, &hpke::Algorithm::HPKEX25519ChaCha20Poly1305, &carol_pk, &hpke::SenderMode::Base, b"PKAuth envelope", &[], &inner).unwrap();
+    let forwarded = serde_json::from_value( json!({ "key": key, "ciphertext": to_base64url( &ciphertext) })).unwrap();
+    assert_eq!( envelope::open::<String>( &carol, forwarded), Err( "Envelope was signed for a different recipient."));
+
+    let (sender, message) : (_, String) = envelope::open( &bob, serde_json::from_value( json).unwrap()).unwrap();
+    assert_eq!( sender, ToIdentifier::to_identifier( &alice));
+    assert_eq!( message, "Meet at noon.");
+}
+
+fn to_base64url( bs : &[u8]) -> String {
+    pkauth::internal::serialize_base64url( &bs.to_vec())
+}
+
+fn from_base64url( s : &str) -> Vec<u8> {
+    pkauth::internal::deserialize_base64url( &s.to_string()).unwrap()
+}
@@ -0,0 +1,131 @@
+extern crate pkauth;
+extern crate ring;
+extern crate serde_json;
+
+use pkauth::ToPublicKey;
+use pkauth::asym::enc as ae;
+use pkauth::asym::enc::hpke;
+use pkauth::asym::enc::hpke::{Psk, ReceiverMode, SenderMode};
+use pkauth::internal::DecodePSF;
+use ring::rand::{SystemRandom, SecureRandom};
+
+const ALGORITHMS : [hpke::Algorithm; 3] = [hpke::Algorithm::HPKEX25519AesGcm128, hpke::Algorithm::HPKEX25519AesGcm256, hpke::Algorithm::HPKEX25519ChaCha20Poly1305];
+
+#[test]
+fn hpke_random_test() {
+    fn run() {
+        let rng = SystemRandom::new();
+        let recipient = ae::gen( &rng, &ae::Algorithm::AEX25519).unwrap();
+        let sender = ae::gen( &rng, &ae::Algorithm::AEX25519).unwrap();
+        let recipient_pk = ToPublicKey::to_public_key( &recipient);
+        let sender_pk = ToPublicKey::to_public_key( &sender);
+
+        let mut secret = [0u8; 32];
+        rng.fill( &mut secret).unwrap();
+        let psk = Psk{ psk : &secret, psk_id : b"psk"};
+
+        let mut content = [0u8; 256].to_vec();
+        rng.fill( &mut content).unwrap();
+
+        for alg in ALGORITHMS.iter() {
+            let modes = [
+                (SenderMode::Base, ReceiverMode::Base),
+                (SenderMode::Psk( psk), ReceiverMode::Psk( psk)),
+                (SenderMode::Auth( &sender), ReceiverMode::Auth( &sender_pk)),
+                (SenderMode::AuthPsk( psk, &sender), ReceiverMode::AuthPsk( psk, &sender_pk)),
+            ];
+
+            for &(ref sender_mode, ref receiver_mode) in modes.iter() {
+                let (encapsulated, mut s) = hpke::setup_sender( &rng, alg, &recipient_pk, sender_mode, b"info").unwrap();
+
+                // The encapsulated key is sent as JSON.
+                let encapsulated = serde_json::to_string( &encapsulated).unwrap();
+                let encapsulated : hpke::EncapsulatedKey = serde_json::from_str( &encapsulated).unwrap();
+                let mut r = hpke::setup_receiver( &encapsulated, &recipient, receiver_mode, b"info").unwrap();
+
+                for _ in 0 .. 3 {
+                    let ciphertext = s.seal( b"aad", &content).unwrap();
+                    assert!( r.open( b"other", &ciphertext).is_err());
+                    assert_eq!( r.open( b"aad", &ciphertext).unwrap(), content);
+                }
+                assert_eq!( s.export( b"context", 64).unwrap(), r.export( b"context", 64).unwrap());
+
+                // Single-shot.
+                let (encapsulated, ciphertext) = hpke::seal( &rng, alg, &recipient_pk, sender_mode, b"info", b"aad", &content).unwrap();
+                assert_eq!( hpke::open( &encapsulated, &recipient, receiver_mode, b"info", b"aad", &ciphertext).unwrap(), content);
+                assert!( hpke::open( &encapsulated, &recipient, receiver_mode, b"other", b"aad", &ciphertext).is_err());
+                assert!( hpke::open( &encapsulated, &sender, receiver_mode, b"info", b"aad", &ciphertext).is_err());
+            }
+
+            // Mismatched modes fail to open.
+            let (encapsulated, ciphertext) = hpke::seal( &rng, alg, &recipient_pk, &SenderMode::Auth( &sender), b"info", b"aad", &content).unwrap();
+            assert!( hpke::open( &encapsulated, &recipient, &ReceiverMode::Base, b"info", b"aad", &ciphertext).is_err());
+            assert!( hpke::open( &encapsulated, &recipient, &ReceiverMode::Auth( &recipient_pk), b"info", b"aad", &ciphertext).is_err());
+        }
+    }
+
+    for _ in 1 .. 20 {
+        run()
+    }
+}
+
+fn hex( s : &str) -> Vec<u8> {
+    (0 .. s.len() / 2).map(|i| u8::from_str_radix( &s[2 * i .. 2 * i + 2], 16).unwrap()).collect()
+}
+
+fn hpke_manual_test( alg : &hpke::Algorithm, mode : u8, ikm_e : &str, ikm_r : &str, sk_s : &str, enc : &str, ciphertexts : &[&str], exported : &str) {
+    let info = hex( "4f6465206f6e2061204772656369616e2055726e");
+    let psk = hex( "0247fd33b913760fa1fa51e1892d9f307fbe65eb171e8132c2af18555a738b82");
+    let psk = Psk{ psk : &psk, psk_id : b"Ennyn Durin aran Moria"};
+    let plaintext = b"Beauty is truth, truth beauty";
+
+    let ephemeral = hpke::derive_key_pair( &hex( ikm_e)).unwrap();
+    let recipient = hpke::derive_key_pair( &hex( ikm_r)).unwrap();
+    // The sender's key is given as the private key, as RFC9180 lists it.
+    let sender = if sk_s.is_empty() { hpke::derive_key_pair( b"unused").unwrap() } else { <ae::PrivateKey as DecodePSF>::decode_psf( &ae::Algorithm::AEX25519, &hex( sk_s)).unwrap() };
+    let sender_pk = ToPublicKey::to_public_key( &sender);
+
+    let (sender_mode, receiver_mode) = match mode {
+        0 => (SenderMode::Base, ReceiverMode::Base),
+        1 => (SenderMode::Psk( psk), ReceiverMode::Psk( psk)),
+        2 => (SenderMode::Auth( &sender), ReceiverMode::Auth( &sender_pk)),
+        _ => (SenderMode::AuthPsk( psk, &sender), ReceiverMode::AuthPsk( psk, &sender_pk)),
+    };
+
+    let (encapsulated, mut s) = hpke::setup_sender_with_ephemeral( &ephemeral, alg, &ToPublicKey::to_public_key( &recipient), &sender_mode, &info).unwrap();
+    let encapsulated = serde_json::to_string( &encapsulated).unwrap();
+    assert_eq!( encapsulated, format!( "{{\"encapsulated_key\":\"{}\",\"algorithm\":\"{}\"}}", enc, pkauth::AlgorithmId::to_algorithm_id( alg)));
+
+    let encapsulated : hpke::EncapsulatedKey = serde_json::from_str( &encapsulated).unwrap();
+    let mut r = hpke::setup_receiver( &encapsulated, &recipient, &receiver_mode, &info).unwrap();
+
+    for (i, ciphertext) in ciphertexts.iter().enumerate() {
+        let aad = format!( "Count-{}", i).into_bytes();
+        assert_eq!( s.seal( &aad, plaintext).unwrap(), hex( ciphertext));
+        assert_eq!( r.open( &aad, &hex( ciphertext)).unwrap(), plaintext.to_vec());
+    }
+
+    assert_eq!( s.export( b"TestContext", 32).unwrap(), hex( exported));
+    assert_eq!( r.export( b"TestContext", 32).unwrap(), hex( exported));
+}
+
+#[test]
+fn hpke_manual_tests() {
+    // From RFC9180, appendix A.1.
+    hpke_manual_test( &hpke::Algorithm::HPKEX25519AesGcm128, 0, "7268600d403fce431561aef583ee1613527cff655c1343f29812e66706df3234", "6db9df30aa07dd42ee5e8181afdb977e538f5e1fec8a06223f33f7013e525037", "", "N_2jVnvb1ijohmjDyNfpfR0SU7bU6m1EwVD3QfG_RDE=", &["f938558b5d72f1a23810b4be2ab4f84331acc02fc97babc53a52ae8218a355a96d8770ac83d07bea87e13c512a", "af2d7e9ac9ae7e270f46ba1f975be53c09f8d875bdc8535458c2494e8a6eab251c03d0c22a56b8ca42c2063b84"], "e9e43065102c3836401bed8c3c3c75ae46be1639869391d62c61f1ec7af54931");
+    hpke_manual_test( &hpke::Algorithm::HPKEX25519AesGcm128, 1, "78628c354e46f3e169bd231be7b2ff1c77aa302460a26dbfa15515684c00130b", "d4a09d09f575fef425905d2ab396c1449141463f698f8efdb7accfaff8995098", "", "CtCVDZ-5WI5ZaQt08SN-zfHXdc1gvi7KV69aSwRxyRs=", &["e52c6fed7f758d0cf7145689f21bc1be6ec9ea097fef4e959440012f4feb73fb611b946199e681f4cfc34db8ea", "49f3b19b28a9ea9f43e8c71204c00d4a490ee7f61387b6719db765e948123b45b61633ef059ba22cd62437c8ba"], "8aff52b45a1be3a734bc7a41e20b4e055ad4c4d22104b0c20285a7c4302401cd");
+    hpke_manual_test( &hpke::Algorithm::HPKEX25519AesGcm128, 2, "6e6d8f200ea2fb20c30b003a8b4f433d2f4ed4c2658d5bc8ce2fef718059c9f7", "f1d4a30a4cef8d6d4e3b016e6fd3799ea057db4f345472ed302a67ce1c20cdec", "dc4a146313cce60a278a5323d321f051c5707e9c45ba21a3479fecdf76fc69dd", "I_uVJXGhSiXj1ngUDNDl60eglhuxivz4WJblRTwxLnY=", &["5fd92cc9d46dbf8943e72a07e42f363ed5f721212cd90bcfd072bfd9f44e06b80fd17824947496e21b680c141b", "d3736bb256c19bfa93d79e8f80b7971262cb7c887e35c26370cfed62254369a1b52e3d505b79dd699f002bc8ed"], "5a0131813abc9a522cad678eb6bafaabc43389934adb8097d23c5ff68059eb64");
+    hpke_manual_test( &hpke::Algorithm::HPKEX25519AesGcm128, 3, "4303619085a20ebcf18edd22782952b8a7161e1dbae6e46e143a52a96127cf84", "4b16221f3b269a88e207270b5e1de28cb01f847841b344b8314d6a622fe5ee90", "fc1c87d2f3832adb178b431fce2ac77c7ca2fd680f3406c77b5ecdf818b119f4", "gggY08I5k0ksxWI6tDekigp8o-ljnBQP4eM4EeuES3w=", &["a84c64df1e11d8fd11450039d4fe64ff0c8a99fca0bd72c2d4c3e0400bc14a40f27e45e141a24001697737533e", "4d19303b848f424fc3c3beca249b2c6de0a34083b8e909b6aa4c3688505c05ffe0c8f57a0a4c5ab9da127435d9"], "a30c20370c026bbea4dca51cb63761695132d342bae33a6a11527d3e7679436d");
+
+    // From RFC9180, appendix A.2.
+    hpke_manual_test( &hpke::Algorithm::HPKEX25519ChaCha20Poly1305, 0, "909a9b35d3dc4713a5e72a4da274b55d3d3821a37e5d099e74a647db583a904b", "1ac01f181fdf9f352797655161c58b75c656a6cc2716dcb66372da835542e1df", "", "GvoI097AR6ZDiFFj8RgEdvp921TGqAKeoz-VeWvyrEo=", &["1c5250d8034ec2b784ba2cfd69dbdb8af406cfe3ff938e131f0def8c8b60b4db21993c62ce81883d2dd1b51a28", "6b53c051e4199c518de79594e1c4ab18b96f081549d45ce015be002090bb119e85285337cc95ba5f59992dc98c"], "5acb09211139c43b3090489a9da433e8a30ee7188ba8b0a9a1ccf0c229283e53");
+    hpke_manual_test( &hpke::Algorithm::HPKEX25519ChaCha20Poly1305, 1, "35706a0b09fb26fb45c39c2f5079c709c7cf98e43afa973f14d88ece7e29c2e3", "26b923eade72941c8a85b09986cdfa3f1296852261adedc52d58d2930269812b", "", "ImEpnD9Aqa_BM7lpqX8F6VvixRTlTz3ibL5WRKxzWwQ=", &["4a177f9c0d6f15cfdf533fb65bf84aecdc6ab16b8b85b4cf65a370e07fc1d78d28fb073214525276f4a89608ff", "5c3cabae2f0b3e124d8d864c116fd8f20f3f56fda988c3573b40b09997fd6c769e77c8eda6cda4f947f5b704a8"], "ad40e3ae14f21c99bfdebc20ae14ab86f4ca2dc9a4799d200f43a25f99fa78ae");
+    hpke_manual_test( &hpke::Algorithm::HPKEX25519ChaCha20Poly1305, 2, "938d3daa5a8904540bc24f48ae90eed3f4f7f11839560597b55e7c9598c996c0", "64835d5ee64aa7aad57c6f2e4f758f7696617f8829e70bc9ac7a5ef95d1c756c", "2def0cb58ffcf83d1062dd085c8aceca7f4c0c3fd05912d847b61f3e54121f05", "92dMyM17qlhy0fM9uv_jMUI59hl9313tF0Z2C_yEfg4=", &["ab1a13c9d4f01a87ec3440dbd756e2677bd2ecf9df0ce7ed73869b98e00c09be111cb9fdf077347aeb88e61bdf", "3265c7807ffff7fdace21659a2c6ccffee52a26d270c76468ed74202a65478bfaedfff9c2b7634e24f10b71016"], "1df39dc5dd60edcbf5f9ae804e15ada66e885b28ed7929116f768369a3f950ee");
+    hpke_manual_test( &hpke::Algorithm::HPKEX25519ChaCha20Poly1305, 3, "49d6eac8c6c558c953a0a252929a818745bb08cd3d29e15f9f5db5eb2e7d4b84", "f3304ddcf15848488271f12b75ecaf72301faabf6ad283654a14c398832eb184", "90761c5b0a7ef0985ed66687ad708b921d9803d51637c8d1cb72d03ed0f64418", "ZWouANyZkP0Ynm5HNFk5LfVW6aJ1h1SgnbP1EXmj_AI=", &["9aa52e29274fc6172e38a4461361d2342585d3aeec67fb3b721ecd63f059577c7fe886be0ede01456ebc67d597", "59460bacdbe7a920ef2806a74937d5a691d6d5062d7daafcad7db7e4d8c649adffe575c1889c5c2e3a49af8e3e"], "d3bae066aa8da27d527d85c040f7dd6ccb60221c902ee36a82f70bcd62a60ee4");
+
+    // Small order encapsulated keys are rejected.
+    assert!( serde_json::from_str::<hpke::EncapsulatedKey>( "{\"encapsulated_key\":\"AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=\",\"algorithm\":\"hpke-x25519-aesgcm256\"}").is_err());
+    let encapsulated = hpke::EncapsulatedKey::new( hpke::Algorithm::HPKEX25519AesGcm256, ae::PublicKey::AEX25519( [0u8; 32]));
+    let recipient = hpke::derive_key_pair( b"recipient").unwrap();
+    assert!( hpke::setup_receiver( &encapsulated, &recipient, &ReceiverMode::Base, b"").is_err());
+}
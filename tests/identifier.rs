@@ -0,0 +1,97 @@
+extern crate pkauth;
+extern crate ring;
+extern crate serde_json;
+
+use pkauth::{IdentifierType, PKAIdentifier, PKAJ, ToIdentifier, ToVersionedIdentifier};
+use pkauth::asym::auth as aa;
+use pkauth::asym::enc as ae;
+use pkauth::sym::enc as se;
+use ring::rand::SystemRandom;
+
+#[test]
+fn identifier_random_test() {
+    fn run() {
+        let rng = SystemRandom::new();
+        let key = aa::gen( &rng, &aa::Algorithm::AAEd25519).unwrap();
+        let identifier = ToIdentifier::to_identifier( &key);
+
+        let parsed : PKAIdentifier = identifier.to_string().parse().unwrap();
+        assert_eq!( parsed, identifier);
+
+        let json = serde_json::to_string( &identifier).unwrap();
+        let parsed : PKAIdentifier = serde_json::from_str( &json).unwrap();
+        assert_eq!( parsed, identifier);
+        assert!( parsed.identifies( &key));
+
+        let versioned = ToVersionedIdentifier::to_versioned_identifier( &key);
+        let parsed : PKAIdentifier = versioned.to_string().parse().unwrap();
+        assert_eq!( parsed.key_type(), Some( IdentifierType::AAEd25519));
+        assert!( parsed.identifies( &key));
+        assert!( parsed != identifier);
+    }
+
+    for _ in 1 .. 100 {
+        run()
+    }
+}
+
+fn identifier_test( key : &str, identifier : &str) {
+    let key : PKAJ<se::Key> = serde_json::from_str( key).unwrap();
+    let parsed : PKAIdentifier = identifier.parse().unwrap();
+
+    assert_eq!( ToIdentifier::to_identifier( &key.pkaj), parsed);
+    assert_eq!( parsed.to_string(), identifier);
+    assert_eq!( serde_json::to_string( &parsed).unwrap(), format!( "\"{}\"", identifier));
+}
+
+#[test]
+fn identifier_manual_tests() {
+    // Identifiers generated by the Haskell implementation.
+    identifier_test( "{\"key\":\"1quLbjzIufNwHh7Oc5ayXiosE3RoJBEDGH_cEcoQfDE=\",\"algorithm\":\"se-aesgcm256\"}", "GzD1i8BgqvT7tTd24K9CrHqWRqMSbfVsp");
+    identifier_test( "{\"key\":\"blZ9VO3AZxbHbv7RflznaEvWC7j3X1FH6pOTwpcYqo4=\",\"algorithm\":\"se-aesgcm256\"}", "C3drTBjjW16c1gzFsoqTBihvKiVq2MBqG");
+    identifier_test( "{\"key\":\"bd6QR9EkrVX9Aq-eCfukKgt_uv6kj5OdFodx6MSwaZ4=\",\"algorithm\":\"se-aesgcm256\"}", "5bUT5uwxft5fGagNjWJtXphwrPmUoWQRW");
+
+    // Mistyped characters fail the checksum.
+    assert!( "GzD1i8BgqvT7tTd24K9CrHqWRqMSbfVsq".parse::<PKAIdentifier>().is_err());
+    assert!( "GzD1i8BgqvT7tTd24K9CrHqWRqMSbfsVp".parse::<PKAIdentifier>().is_err());
+    // Wrong length.
+    assert!( "GzD1i8BgqvT7tTd24K9CrHqWRqMSbfVs".parse::<PKAIdentifier>().is_err());
+    assert!( "".parse::<PKAIdentifier>().is_err());
+    // Invalid Base58.
+    assert!( "GzD1i8BgqvT7tTd24K9CrHqWRqMSbfV0p".parse::<PKAIdentifier>().is_err());
+    assert!( serde_json::from_str::<PKAIdentifier>( "\"GzD1i8BgqvT7tTd24K9CrHqWRqMSbfVsq\"").is_err());
+}
+
+#[test]
+fn identifier_versioned_tests() {
+    // The same 32 bytes as a symmetric key and as an X25519 public key.
+    let key : PKAJ<se::Key> = serde_json::from_str( "{\"key\":\"1quLbjzIufNwHh7Oc5ayXiosE3RoJBEDGH_cEcoQfDE=\",\"algorithm\":\"se-aesgcm256\"}").unwrap();
+    let pk : PKAJ<ae::PublicKey> = serde_json::from_str( "{\"public_key\":\"1quLbjzIufNwHh7Oc5ayXiosE3RoJBEDGH_cEcoQfDE=\",\"algorithm\":\"ae-x25519\"}").unwrap();
+
+    // Legacy identifiers collide.
+    assert_eq!( ToIdentifier::to_identifier( &key.pkaj), ToIdentifier::to_identifier( &pk.pkaj));
+
+    // Versioned identifiers do not.
+    let key_id = ToVersionedIdentifier::to_versioned_identifier( &key.pkaj);
+    let pk_id = ToVersionedIdentifier::to_versioned_identifier( &pk.pkaj);
+    assert_eq!( key_id.to_string(), "2QxbNLCHJ2mzodJqWr2YWWBuHkUEMJsQxR");
+    assert_eq!( pk_id.to_string(), "t3VGJRA4LeQ5dX8foZ7Z2T8X1GgsvBFqj");
+
+    let key_id : PKAIdentifier = "2QxbNLCHJ2mzodJqWr2YWWBuHkUEMJsQxR".parse().unwrap();
+    assert_eq!( key_id.key_type(), Some( IdentifierType::SEAesGcm256));
+    assert_eq!( key_id.key_type().unwrap().algorithm_id(), "se-aesgcm256");
+    assert!( key_id.identifies( &key.pkaj));
+    assert!( !key_id.identifies( &pk.pkaj));
+
+    let pk_id : PKAIdentifier = "t3VGJRA4LeQ5dX8foZ7Z2T8X1GgsvBFqj".parse().unwrap();
+    assert_eq!( pk_id.key_type(), Some( IdentifierType::AEX25519));
+
+    // Legacy identifiers are still recognized.
+    let legacy : PKAIdentifier = "GzD1i8BgqvT7tTd24K9CrHqWRqMSbfVsp".parse().unwrap();
+    assert!( legacy.is_legacy());
+    assert_eq!( legacy.key_type(), None);
+    assert!( legacy.identifies( &key.pkaj));
+
+    // Corrupted versioned identifiers are rejected.
+    assert!( "2QxbNLCHJ2mzodJqWr2YWWBuHkUEMJsQxS".parse::<PKAIdentifier>().is_err());
+}
@@ -0,0 +1,138 @@
+extern crate pkauth;
+extern crate ring;
+#[macro_use]
+extern crate serde_json;
+
+use pkauth::ToPublicKey;
+use pkauth::asym::auth as aa;
+use pkauth::encoding::jcs;
+use ring::rand::{SystemRandom, SecureRandom};
+use serde_json::Value;
+
+#[test]
+fn jcs_random_test() {
+    fn run() {
+        let rng = SystemRandom::new();
+        let key = aa::gen( &rng, &aa::Algorithm::AAEd25519).unwrap();
+        let other = aa::gen( &rng, &aa::Algorithm::AAEd25519).unwrap();
+        let public_key = ToPublicKey::to_public_key( &key);
+
+        let mut bs = [0u8; 16];
+        rng.fill( &mut bs).unwrap();
+        let f = f64::from_bits( u64::from_le_bytes( [bs[0], bs[1], bs[2], bs[3], bs[4], bs[5], bs[6], bs[7]]));
+        let f = if f.is_finite() { f } else { 0.5 };
+        let s : String = bs[8 ..].iter().map(|b| *b as char).collect();
+
+        // The same object with its properties in a different order and formatting.
+        let o = json!({ "z": f, "a": [ s, 1, true, null], "m": { "y": -7, "b": "\u{20ac}" }});
+        let reordered : Value = serde_json::from_str( &format!( "{{ \"m\" : {{\"b\":\"\u{20ac}\", \"y\":-7}}, \"a\":{}, \"z\":{:e} }}", o["a"], f)).unwrap();
+        assert_eq!( jcs::to_canonical( &o).unwrap(), jcs::to_canonical( &reordered).unwrap());
+        assert!( jcs::is_canonical( &jcs::to_canonical( &o).unwrap()));
+
+        let signed = aa::sign_canonical( &key, &reordered).unwrap();
+        let verified : Value = aa::verify_canonical( &public_key, signed).unwrap();
+        assert_eq!( jcs::canonicalize( &verified).unwrap(), jcs::canonicalize( &o).unwrap());
+
+        let signed = aa::sign_inline( &key, &o).unwrap();
+        let verified : Value = aa::verify_inline( &public_key, &signed).unwrap();
+        assert_eq!( jcs::canonicalize( &verified).unwrap(), jcs::canonicalize( &o).unwrap());
+        assert!( aa::verify_inline::<Value>( &ToPublicKey::to_public_key( &other), &signed).is_err());
+
+        let mut tampered = signed.clone();
+        tampered["m"]["y"] = json!( 7);
+        assert!( aa::verify_inline::<Value>( &public_key, &tampered).is_err());
+    }
+
+    for _ in 1 .. 100 {
+        run()
+    }
+}
+
+#[test]
+fn jcs_manual_tests() {
+    // RFC8785, Appendix B.
+    let numbers = [
+        (0x0000000000000000u64, "0"),
+        (0x8000000000000000, "0"),
+        (0x0000000000000001, "5e-324"),
+        (0x8000000000000001, "-5e-324"),
+        (0x7fefffffffffffff, "1.7976931348623157e+308"),
+        (0xffefffffffffffff, "-1.7976931348623157e+308"),
+        (0x4340000000000000, "9007199254740992"),
+        (0xc340000000000000, "-9007199254740992"),
+        (0x4430000000000000, "295147905179352830000"),
+        (0x44b52d02c7e14af5, "9.999999999999997e+22"),
+        (0x44b52d02c7e14af6, "1e+23"),
+        (0x44b52d02c7e14af7, "1.0000000000000001e+23"),
+        (0x444b1ae4d6e2ef4e, "999999999999999700000"),
+        (0x444b1ae4d6e2ef4f, "999999999999999900000"),
+        (0x444b1ae4d6e2ef50, "1e+21"),
+        (0x3eb0c6f7a0b5ed8c, "9.999999999999997e-7"),
+        (0x3eb0c6f7a0b5ed8d, "0.000001"),
+        (0x41b3de4355555553, "333333333.3333332"),
+        (0x41b3de4355555554, "333333333.33333325"),
+        (0x41b3de4355555555, "333333333.3333333"),
+        (0x41b3de4355555556, "333333333.3333334"),
+        (0x41b3de4355555557, "333333333.33333343"),
+        (0xbecbf647612f3696, "-0.0000033333333333333333"),
+        (0x43143ff3c1cb0959, "1424953923781206.2"),
+    ];
+    for &(bits, expected) in numbers.iter() {
+        let canonical = jcs::to_canonical( &f64::from_bits( bits)).unwrap();
+        assert_eq!( String::from_utf8( canonical).unwrap(), expected);
+
+        // The canonical form is its own canonical form.
+        let v : Value = serde_json::from_str( expected).unwrap();
+        assert_eq!( jcs::canonicalize( &v).unwrap(), expected.as_bytes());
+    }
+    // NaN and Infinity are errors, both in Appendix B and in nested values.
+    for &bits in [ 0x7fffffffffffffffu64, 0x7ff0000000000000].iter() {
+        assert!( jcs::to_canonical( &f64::from_bits( bits)).is_err());
+    }
+    assert!( jcs::to_canonical( &::std::f64::NEG_INFINITY).is_err());
+    assert!( jcs::to_canonical( &vec![ ( "x", ::std::f32::NAN)]).is_err());
+    assert!( jcs::to_canonical( &9007199254740993u64).is_err());
+    assert!( jcs::to_canonical( &::std::u64::MAX).is_err());
+    assert_eq!( jcs::to_canonical( &-9007199254740994i64).unwrap(), b"-9007199254740994");
+
+    // RFC8785, Section 3.2.2.
+    let o : Value = serde_json::from_str( r#"{
+        "numbers": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000000000000000000000000001],
+        "string": "\u20ac$\u000F\u000aA'\u0042\u0022\u005c\\\"\/",
+        "literals": [null, true, false]
+    }"#).unwrap();
+    assert_eq!( String::from_utf8( jcs::canonicalize( &o).unwrap()).unwrap(),
+        r#"{"literals":[null,true,false],"numbers":[333333333.3333333,1e+30,4.5,0.002,1e-27],"string":"€$\u000f\nA'B\"\\\\\"/"}"#);
+
+    // RFC8785, Section 3.2.3.
+    let o : Value = serde_json::from_str( r#"{
+        "€": "Euro Sign",
+        "\r": "Carriage Return",
+        "דּ": "Hebrew Letter Dalet With Dagesh",
+        "1": "One",
+        "😀": "Emoji: Grinning Face",
+        "\u0080": "Control",
+        "ö": "Latin Small Letter O With Diaeresis"
+    }"#).unwrap();
+    let keys : Vec<String> = serde_json::from_slice::<Value>( &jcs::canonicalize( &o).unwrap()).unwrap().as_object().unwrap().keys().cloned().collect();
+    let canonical = String::from_utf8( jcs::canonicalize( &o).unwrap()).unwrap();
+    let positions : Vec<usize> = [ "\\r", "1", "\u{80}", "\u{f6}", "\u{20ac}", "\u{1f600}", "\u{fb33}"].iter().map(|k| canonical.find( &format!( "\"{}\"", k)).unwrap()).collect();
+    assert!( positions.windows( 2).all(|w| w[0] < w[1]));
+    assert_eq!( keys.len(), 7);
+
+    // Signatures over canonical json.
+    let key = aa::gen_from_seed( &aa::Algorithm::AAEd25519, &[7u8; 32]).unwrap();
+    let public_key = ToPublicKey::to_public_key( &key);
+    let signed = aa::sign_inline( &key, &json!({ "b": 2, "a": 1 })).unwrap();
+    assert_eq!( signed["signature"]["algorithm"], "aa-ed25519");
+    let verified : Value = aa::verify_inline( &public_key, &serde_json::from_str( &signed.to_string()).unwrap()).unwrap();
+    assert_eq!( verified, json!({ "a": 1, "b": 2 }));
+    assert!( aa::sign_inline( &key, &json!([ 1, 2])).is_err());
+    assert!( aa::sign_inline( &key, &signed).is_err());
+
+    // Non-canonical content is rejected.
+    let signed = aa::sign_content( &key, b"{\"b\":2, \"a\":1}".to_vec()).unwrap();
+    assert!( aa::verify_canonical::<Value>( &public_key, signed).is_err());
+    let signed = aa::sign_content( &key, b"{\"a\":1,\"b\":2}".to_vec()).unwrap();
+    assert_eq!( aa::verify_canonical::<Value>( &public_key, signed).unwrap(), json!({ "a": 1, "b": 2 }));
+}
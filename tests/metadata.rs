@@ -0,0 +1,132 @@
+extern crate pkauth;
+extern crate ring;
+extern crate serde_json;
+
+use pkauth::{PKAJ, ToPublicKey};
+use pkauth::asym::auth as aa;
+use pkauth::asym::enc as ae;
+use pkauth::asym::enc::{age, hpke, sodium};
+use pkauth::envelope;
+use pkauth::internal;
+use pkauth::internal::EncodePSF;
+use pkauth::metadata::{FixedClock, Metadata, PKAKey, SystemClock, Usage};
+use pkauth::sym::enc as se;
+use ring::rand::{SystemRandom, SecureRandom};
+
+#[test]
+fn metadata_random_test() {
+    fn run() {
+        let rng = SystemRandom::new();
+        let key = aa::gen( &rng, &aa::Algorithm::AAEd25519).unwrap();
+        let se_key = se::gen( &rng, &se::Algorithm::SEAesGcm256).unwrap();
+
+        let mut content = [0u8; 64].to_vec();
+        rng.fill( &mut content).unwrap();
+
+        let metadata = Metadata{ created : Some( 1000), expires : Some( 2000), usage : Some( vec![ Usage::Sign, Usage::Auth]), label : Some( "laptop".to_string())};
+        let public_key = PKAKey::new( ToPublicKey::to_public_key( &key), metadata.clone());
+        let json = serde_json::to_vec( &public_key).unwrap();
+        let decoded : PKAKey<aa::PublicKey> = internal::from_slice_strict( &json).unwrap();
+        assert_eq!( decoded.metadata, metadata);
+        assert_eq!( EncodePSF::encode_psf( &decoded.key), EncodePSF::encode_psf( &public_key.key));
+
+        // Metadata is ignored by readers of plain PKAJ keys.
+        let plain : PKAJ<aa::PublicKey> = serde_json::from_slice( &json).unwrap();
+        assert_eq!( EncodePSF::encode_psf( &plain.pkaj), EncodePSF::encode_psf( &public_key.key));
+
+        let signed = aa::sign_content( &key, content.clone()).unwrap();
+        assert_eq!( aa::verify_content_with_metadata( &FixedClock( 1500), &decoded, signed).unwrap(), content);
+        let signed = aa::sign_content( &key, content.clone()).unwrap();
+        assert_eq!( aa::verify_content_with_metadata( &FixedClock( 2000), &decoded, signed).err(), Some( "Key has expired."));
+        let signed = aa::sign_content( &key, content.clone()).unwrap();
+        assert_eq!( aa::verify_content_with_metadata( &FixedClock( 999), &decoded, signed).err(), Some( "Key is not valid yet."));
+
+        // A signing key cannot decrypt.
+        let encryption_key = PKAKey::new( se_key, metadata.clone());
+        let encrypted = se::encrypt_content( &rng, &encryption_key.key, content.clone()).unwrap();
+        assert_eq!( se::decrypt_content_with_metadata( &FixedClock( 1500), &encryption_key, encrypted).err(), Some( "Key usage does not allow this operation."));
+
+        // Keys without metadata may be used for anything, at any time.
+        let unrestricted = PKAKey::new( encryption_key.key, Metadata::default());
+        let encrypted = se::encrypt_content( &rng, &unrestricted.key, content.clone()).unwrap();
+        assert_eq!( se::decrypt_content_with_metadata( &SystemClock, &unrestricted, encrypted).unwrap(), content);
+
+        // Asymmetric decryption.
+        let mut seed = [0u8; 32];
+        rng.fill( &mut seed).unwrap();
+        let ae_key = ae::gen_from_seed( &ae::Algorithm::AEX25519, &seed).unwrap();
+        let ae_public_key = ToPublicKey::to_public_key( &ae_key);
+        let encryption = Metadata{ created : Some( 1000), expires : Some( 2000), usage : Some( vec![ Usage::Encrypt]), label : None};
+        let recipient = PKAKey::new( ae_key, encryption);
+        let signing = PKAKey::new( ae::gen_from_seed( &ae::Algorithm::AEX25519, &seed).unwrap(), metadata.clone());
+
+        let (encapsulated, ciphertext) = hpke::seal( &rng, &hpke::Algorithm::HPKEX25519AesGcm256, &ae_public_key, &hpke::SenderMode::Base, b"", b"", &content).unwrap();
+        assert_eq!( hpke::open_with_metadata( &FixedClock( 1500), &encapsulated, &recipient, &hpke::ReceiverMode::Base, b"", b"", &ciphertext).unwrap(), content);
+        assert_eq!( hpke::open_with_metadata( &FixedClock( 2000), &encapsulated, &recipient, &hpke::ReceiverMode::Base, b"", b"", &ciphertext).err(), Some( "Key has expired."));
+        assert_eq!( hpke::open_with_metadata( &FixedClock( 1500), &encapsulated, &signing, &hpke::ReceiverMode::Base, b"", b"", &ciphertext).err(), Some( "Key usage does not allow this operation."));
+
+        let sealed = envelope::seal_content( &rng, &hpke::Algorithm::HPKEX25519AesGcm256, &key, &ae_public_key, content.clone()).unwrap();
+        assert_eq!( envelope::open_content_with_metadata( &FixedClock( 1500), &recipient, sealed).unwrap().1, content);
+        let sealed = envelope::seal_content( &rng, &hpke::Algorithm::HPKEX25519AesGcm256, &key, &ae_public_key, content.clone()).unwrap();
+        assert_eq!( envelope::open_content_with_metadata( &FixedClock( 999), &recipient, sealed).err(), Some( "Key is not valid yet."));
+
+        let sealed = sodium::seal( &rng, &ae_public_key, &content).unwrap();
+        assert_eq!( sodium::seal_open_with_metadata( &FixedClock( 1500), &recipient, &sealed).unwrap(), content);
+        assert!( sodium::seal_open_with_metadata( &FixedClock( 1500), &signing, &sealed).is_err());
+        let sender = ae::gen( &rng, &ae::Algorithm::AEX25519).unwrap();
+        let nonce = sodium::gen_nonce( &rng).unwrap();
+        let boxed = sodium::box_encrypt( &sender, &ae_public_key, &nonce, &content).unwrap();
+        assert_eq!( sodium::box_decrypt_with_metadata( &FixedClock( 1500), &recipient, &ToPublicKey::to_public_key( &sender), &nonce, &boxed).unwrap(), content);
+        assert!( sodium::box_decrypt_with_metadata( &FixedClock( 2500), &recipient, &ToPublicKey::to_public_key( &sender), &nonce, &boxed).is_err());
+
+        let file = age::encrypt( &rng, &[ age::Recipient::X25519( &ae_public_key)], &content).unwrap();
+        assert_eq!( age::decrypt_with_metadata( &FixedClock( 1500), &[ &signing, &recipient], &file).unwrap(), content);
+        assert_eq!( age::decrypt_with_metadata( &FixedClock( 1500), &[ &signing], &file).err(), Some( "Key usage does not allow this operation."));
+    }
+
+    for _ in 1 .. 100 {
+        run()
+    }
+}
+
+#[test]
+fn metadata_manual_tests() {
+    let json = "{\"key\":\"1quLbjzIufNwHh7Oc5ayXiosE3RoJBEDGH_cEcoQfDE=\",\"algorithm\":\"se-aesgcm256\",\"created\":1700000000,\"expires\":1800000000,\"usage\":[\"encrypt\"],\"label\":\"backups\"}";
+    let key : PKAKey<se::Key> = internal::from_str_strict( json).unwrap();
+    assert_eq!( key.metadata, Metadata{ created : Some( 1700000000), expires : Some( 1800000000), usage : Some( vec![ Usage::Encrypt]), label : Some( "backups".to_string())});
+    assert_eq!( serde_json::to_string( &key).unwrap(), json);
+
+    let cipher = "{\"ciphertext\":\"edzNkvuqE-BUGS2Y0RXnt9C4d52B7v8boQtdn1nkdSWesa_Y9RRW2c1acAAIv8YHeb0=\",\"algorithm\":\"se-aesgcm256\"}";
+    let decrypted : Result<Vec<u8>, &'static str> = se::decrypt_content_with_metadata( &FixedClock( 1750000000), &key, serde_json::from_str( cipher).unwrap());
+    assert_eq!( decrypted.unwrap(), b"this is rust plaintext");
+    assert_eq!( se::decrypt_content_with_metadata( &FixedClock( 1800000000), &key, serde_json::from_str( cipher).unwrap()).err(), Some( "Key has expired."));
+
+    // Keys without metadata are plain PKAJ keys.
+    let plain = "{\"key\":\"1quLbjzIufNwHh7Oc5ayXiosE3RoJBEDGH_cEcoQfDE=\",\"algorithm\":\"se-aesgcm256\"}";
+    let key : PKAKey<se::Key> = serde_json::from_str( plain).unwrap();
+    assert_eq!( key.metadata, Metadata::default());
+    assert_eq!( serde_json::to_string( &key).unwrap(), plain);
+    assert_eq!( serde_json::to_string( &key).unwrap(), serde_json::to_string( &PKAJ{ pkaj : &key.key}).unwrap());
+
+    // Invalid metadata.
+    let invalid = [
+        "{\"algorithm\":\"se-aesgcm256\",\"key\":\"1quLbjzIufNwHh7Oc5ayXiosE3RoJBEDGH_cEcoQfDE=\",\"usage\":[\"wrap\"]}",
+        "{\"algorithm\":\"se-aesgcm256\",\"key\":\"1quLbjzIufNwHh7Oc5ayXiosE3RoJBEDGH_cEcoQfDE=\",\"usage\":[\"encrypt\",\"encrypt\"]}",
+        "{\"algorithm\":\"se-aesgcm256\",\"key\":\"1quLbjzIufNwHh7Oc5ayXiosE3RoJBEDGH_cEcoQfDE=\",\"usage\":\"encrypt\"}",
+        "{\"algorithm\":\"se-aesgcm256\",\"key\":\"1quLbjzIufNwHh7Oc5ayXiosE3RoJBEDGH_cEcoQfDE=\",\"created\":-1}",
+        "{\"algorithm\":\"se-aesgcm256\",\"key\":\"1quLbjzIufNwHh7Oc5ayXiosE3RoJBEDGH_cEcoQfDE=\",\"expires\":\"2030-01-01\"}",
+        "{\"algorithm\":\"se-aesgcm256\",\"key\":\"1quLbjzIufNwHh7Oc5ayXiosE3RoJBEDGH_cEcoQfDE=\",\"created\":2,\"expires\":1}",
+        "{\"algorithm\":\"se-aesgcm256\",\"key\":\"1quLbjzIufNwHh7Oc5ayXiosE3RoJBEDGH_cEcoQfDE=\",\"label\":7}",
+        "{\"algorithm\":\"se-aesgcm256\",\"key\":\"1quLbjzIufNwHh7Oc5ayXiosE3RoJBEDGH_cEcoQfDE=\",\"label\":\"a\",\"label\":\"b\"}",
+        "{\"algorithm\":\"se-aesgcm256\",\"key\":\"1quLbjzIufNwHh7Oc5ayXiosE3RoJBEDGH_cEcoQfDE=\",\"key\":\"1quLbjzIufNwHh7Oc5ayXiosE3RoJBEDGH_cEcoQfDE=\"}",
+        "[\"se-aesgcm256\"]",
+    ];
+    for json in invalid.iter() {
+        assert!( serde_json::from_str::<PKAKey<se::Key>>( json).is_err(), "{}", json);
+    }
+
+    // Strict deserialization still rejects unknown fields.
+    let extra = "{\"algorithm\":\"se-aesgcm256\",\"key\":\"1quLbjzIufNwHh7Oc5ayXiosE3RoJBEDGH_cEcoQfDE=\",\"comment\":\"x\"}";
+    assert!( serde_json::from_str::<PKAKey<se::Key>>( extra).is_ok());
+    assert!( internal::from_str_strict::<PKAKey<se::Key>>( extra).is_err());
+}
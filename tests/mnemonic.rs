@@ -0,0 +1,81 @@
+extern crate pkauth;
+extern crate ring;
+extern crate serde_json;
+
+use pkauth::{PKAJ, ToIdentifier};
+use pkauth::asym::auth as aa;
+use pkauth::asym::enc as ae;
+use pkauth::internal::mnemonic;
+use pkauth::sym::enc as se;
+use ring::rand::SystemRandom;
+
+#[test]
+fn mnemonic_random_test() {
+    fn run() {
+        let rng = SystemRandom::new();
+
+        let key = aa::gen( &rng, &aa::Algorithm::AAEd25519).unwrap();
+        let restored = aa::from_mnemonic( &aa::to_mnemonic( &key).unwrap()).unwrap();
+        assert_eq!( serde_json::to_string( &PKAJ{ pkaj : &key}).unwrap(), serde_json::to_string( &PKAJ{ pkaj : &restored}).unwrap());
+        assert_eq!( ToIdentifier::to_identifier( &key), ToIdentifier::to_identifier( &restored));
+
+        let key = ae::gen( &rng, &ae::Algorithm::AEX25519).unwrap();
+        let restored = ae::from_mnemonic( &ae::to_mnemonic( &key).unwrap()).unwrap();
+        assert_eq!( serde_json::to_string( &PKAJ{ pkaj : &key}).unwrap(), serde_json::to_string( &PKAJ{ pkaj : &restored}).unwrap());
+        assert_eq!( ToIdentifier::to_identifier( &key), ToIdentifier::to_identifier( &restored));
+
+        let key = se::gen( &rng, &se::Algorithm::SEAesGcm256).unwrap();
+        let restored = se::from_mnemonic( &se::to_mnemonic( &key).unwrap()).unwrap();
+        assert_eq!( serde_json::to_string( &PKAJ{ pkaj : &key}).unwrap(), serde_json::to_string( &PKAJ{ pkaj : &restored}).unwrap());
+        assert_eq!( ToIdentifier::to_identifier( &key), ToIdentifier::to_identifier( &restored));
+    }
+
+    for _ in 1 .. 100 {
+        run()
+    }
+}
+
+fn bip39_test( entropy : &str, phrase : &str) {
+    let entropy = hex_to_u8( entropy);
+    let words : Vec<&str> = phrase.split( ' ').collect();
+
+    assert_eq!( mnemonic::entropy_to_words( &entropy).unwrap(), words);
+    assert_eq!( mnemonic::words_to_entropy( &words).unwrap(), entropy);
+}
+
+fn hex_to_u8( hex : &str)  -> Vec<u8> {
+    let c = hex.len()/2;
+    let mut v = vec![0;c];
+
+    for i in 0 .. c {
+        v[i] = u8::from_str_radix( &hex[2*i .. 2*i + 2], 16).unwrap();
+    }
+
+    v
+}
+
+#[test]
+fn mnemonic_manual_tests() {
+    // From the BIP39 test vectors.
+    bip39_test( "00000000000000000000000000000000", "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about");
+    bip39_test( "9e885d952ad362caeb4efe34a8e91bd2", "ozone drill grab fiber curtain grace pudding thank cruise elder eight picnic");
+    bip39_test( "7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f", "legal winner thank year wave sausage worth useful legal winner thank year wave sausage worth useful legal winner thank year wave sausage worth title");
+    bip39_test( "8080808080808080808080808080808080808080808080808080808080808080", "letter advice cage absurd amount doctor acoustic avoid letter advice cage absurd amount doctor acoustic avoid letter advice cage absurd amount doctor acoustic bless");
+    bip39_test( "68a79eaca2324873eacc50cb9c6eca8cc68ea5d936f98787c60c7ebc74e6ce7c", "hamster diagram private dutch cause delay private meat slide toddler razor book happy fancy gospel tennis maple dilemma loan word shrug inflict delay length");
+
+    // Phrases carry the algorithm identifier.
+    let phrase = "se-aesgcm256 zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo vote";
+    let key = se::from_mnemonic( phrase).unwrap();
+    assert_eq!( serde_json::to_string( &PKAJ{ pkaj : &key}).unwrap(), "{\"key\":\"__________________________________________8=\",\"algorithm\":\"se-aesgcm256\"}");
+    assert_eq!( se::to_mnemonic( &key).unwrap(), phrase);
+
+    // Case and whitespace are ignored.
+    assert!( se::from_mnemonic( "  SE-AESGCM256 zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo\n zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo Vote ").is_ok());
+
+    // Bad checksum.
+    assert!( se::from_mnemonic( "se-aesgcm256 zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo").is_err());
+    // Wrong key type.
+    assert!( aa::from_mnemonic( phrase).is_err());
+    // Unknown word.
+    assert!( se::from_mnemonic( "se-aesgcm256 zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo pkauth").is_err());
+}
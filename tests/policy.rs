@@ -0,0 +1,145 @@
+extern crate pkauth;
+extern crate ring;
+extern crate serde_json;
+
+use pkauth::{AlgorithmStrength, PKAJ, ToPublicKey};
+use pkauth::asym::auth as aa;
+use pkauth::asym::enc as ae;
+use pkauth::asym::enc::{age, hpke, sodium};
+use pkauth::envelope;
+use pkauth::internal::EncodePSF;
+use pkauth::policy::{Operation, Policy};
+use pkauth::sym::enc as se;
+use ring::rand::{SystemRandom, SecureRandom};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[test]
+fn policy_random_test() {
+    fn run() {
+        let rng = SystemRandom::new();
+        let key = aa::gen( &rng, &aa::Algorithm::AAEd25519).unwrap();
+        let public_key = ToPublicKey::to_public_key( &key);
+        let se_key = se::gen( &rng, &se::Algorithm::SEAesGcm256).unwrap();
+
+        let mut content = [0u8; 64].to_vec();
+        rng.fill( &mut content).unwrap();
+
+        let policy = Policy::default();
+        let signed = aa::sign_content( &key, content.clone()).unwrap();
+        assert_eq!( aa::verify_content_with_policy( &policy, &public_key, signed).unwrap(), content);
+        let encrypted = se::encrypt_content( &rng, &se_key, content.clone()).unwrap();
+        assert_eq!( se::decrypt_content_with_policy( &policy, &se_key, encrypted).unwrap(), content);
+
+        // Allowed for one operation only.
+        let policy = Policy::new().allow( Operation::Decrypt, "aa-ed25519");
+        let signed = aa::sign_content( &key, content.clone()).unwrap();
+        assert!( aa::verify_content_with_policy( &policy, &public_key, signed).is_err());
+        let encrypted = se::encrypt_content( &rng, &se_key, content.clone()).unwrap();
+        assert!( se::decrypt_content_with_policy( &policy, &se_key, encrypted).is_err());
+
+        let json = serde_json::to_vec( &PKAJ{ pkaj : &public_key}).unwrap();
+        let loaded : aa::PublicKey = Policy::default().load_key( &json).unwrap();
+        assert_eq!( EncodePSF::encode_psf( &loaded), EncodePSF::encode_psf( &public_key));
+        assert!( Policy::default().forbid( Operation::Verify, "aa-ed25519").load_key::<aa::PublicKey>( &json).is_err());
+
+        // Asymmetric decryption.
+        let ae_key = ae::gen( &rng, &ae::Algorithm::AEX25519).unwrap();
+        let ae_public_key = ToPublicKey::to_public_key( &ae_key);
+        let without_x25519 = Policy::default().forbid( Operation::Decrypt, "ae-x25519");
+
+        let (encapsulated, ciphertext) = hpke::seal( &rng, &hpke::Algorithm::HPKEX25519AesGcm128, &ae_public_key, &hpke::SenderMode::Base, b"info", b"", &content).unwrap();
+        assert_eq!( hpke::open_with_policy( &Policy::default(), &encapsulated, &ae_key, &hpke::ReceiverMode::Base, b"info", b"", &ciphertext).unwrap(), content);
+        assert!( hpke::open_with_policy( &without_x25519, &encapsulated, &ae_key, &hpke::ReceiverMode::Base, b"info", b"", &ciphertext).is_err());
+        let without_suite = Policy::default().forbid( Operation::Decrypt, "hpke-x25519-aesgcm128");
+        assert!( hpke::open_with_policy( &without_suite, &encapsulated, &ae_key, &hpke::ReceiverMode::Base, b"info", b"", &ciphertext).is_err());
+        let large_keys = Policy::default().minimum_key_bits( 256);
+        assert_eq!( hpke::open_with_policy( &large_keys, &encapsulated, &ae_key, &hpke::ReceiverMode::Base, b"info", b"", &ciphertext).err(), Some( "Key is smaller than policy allows."));
+
+        let envelope = envelope::seal_content( &rng, &hpke::Algorithm::HPKEX25519AesGcm256, &key, &ae_public_key, content.clone()).unwrap();
+        assert_eq!( envelope::open_content_with_policy( &large_keys, &ae_key, envelope).unwrap().1, content);
+        let envelope = envelope::seal_content( &rng, &hpke::Algorithm::HPKEX25519AesGcm256, &key, &ae_public_key, content.clone()).unwrap();
+        assert!( envelope::open_content_with_policy( &without_x25519, &ae_key, envelope).is_err());
+        let envelope = envelope::seal_content( &rng, &hpke::Algorithm::HPKEX25519AesGcm256, &key, &ae_public_key, content.clone()).unwrap();
+        assert!( envelope::open_content_with_policy( &Policy::default().forbid( Operation::Verify, "aa-ed25519"), &ae_key, envelope).is_err());
+
+        let sealed = sodium::seal( &rng, &ae_public_key, &content).unwrap();
+        assert_eq!( sodium::seal_open_with_policy( &Policy::default(), &ae_key, &sealed).unwrap(), content);
+        assert!( sodium::seal_open_with_policy( &without_x25519, &ae_key, &sealed).is_err());
+        let sender = ae::gen( &rng, &ae::Algorithm::AEX25519).unwrap();
+        let nonce = sodium::gen_nonce( &rng).unwrap();
+        let boxed = sodium::box_encrypt( &sender, &ae_public_key, &nonce, &content).unwrap();
+        assert_eq!( sodium::box_decrypt_with_policy( &Policy::default(), &ae_key, &ToPublicKey::to_public_key( &sender), &nonce, &boxed).unwrap(), content);
+        assert!( sodium::box_decrypt_with_policy( &without_x25519, &ae_key, &ToPublicKey::to_public_key( &sender), &nonce, &boxed).is_err());
+
+        let file = age::encrypt( &rng, &[ age::Recipient::X25519( &ae_public_key)], &content).unwrap();
+        assert_eq!( age::decrypt_with_policy( &Policy::default(), &[ age::Identity::X25519( &ae_key)], &file).unwrap(), content);
+        assert!( age::decrypt_with_policy( &without_x25519, &[ age::Identity::X25519( &ae_key)], &file).is_err());
+    }
+
+    for _ in 1 .. 100 {
+        run()
+    }
+}
+
+static DEPRECATED : AtomicUsize = AtomicUsize::new( 0);
+
+fn count_deprecated( algorithm : &str) {
+    assert_eq!( algorithm, "se-aesgcm256");
+    DEPRECATED.fetch_add( 1, Ordering::SeqCst);
+}
+
+#[test]
+fn policy_manual_tests() {
+    let key : PKAJ<se::Key> = serde_json::from_str( "{\"key\":\"1quLbjzIufNwHh7Oc5ayXiosE3RoJBEDGH_cEcoQfDE=\",\"algorithm\":\"se-aesgcm256\"}").unwrap();
+    let cipher = "{\"ciphertext\":\"edzNkvuqE-BUGS2Y0RXnt9C4d52B7v8boQtdn1nkdSWesa_Y9RRW2c1acAAIv8YHeb0=\",\"algorithm\":\"se-aesgcm256\"}";
+
+    let policy = Policy::default();
+    assert!( policy.is_allowed( Operation::Verify, "aa-ed25519"));
+    assert!( policy.is_allowed( Operation::Decrypt, "ae-x25519"));
+    assert!( !policy.is_allowed( Operation::Verify, "se-aesgcm256"));
+    assert!( !policy.is_allowed( Operation::Verify, "aa-rsa-pkcs1"));
+    assert!( policy.check( Operation::Verify, &se::Algorithm::SEAesGcm256).is_err());
+    assert_eq!( se::decrypt_content_with_policy( &policy, &key.pkaj, serde_json::from_str( cipher).unwrap()).unwrap(), b"this is rust plaintext");
+
+    // Deprecated algorithms are reported, but still allowed.
+    let policy = Policy::default().deprecate( "se-aesgcm256").on_deprecated( count_deprecated);
+    assert_eq!( se::decrypt_content_with_policy( &policy, &key.pkaj, serde_json::from_str( cipher).unwrap()).unwrap(), b"this is rust plaintext");
+    assert_eq!( DEPRECATED.load( Ordering::SeqCst), 1);
+    assert!( policy.check( Operation::Verify, &aa::Algorithm::AAEd25519).is_ok());
+    assert_eq!( DEPRECATED.load( Ordering::SeqCst), 1);
+
+    // Handlers may capture their environment.
+    let reported = Rc::new( RefCell::new( Vec::new()));
+    let log = reported.clone();
+    let policy = Policy::default().deprecate( "aa-ed25519").on_deprecated( move |algorithm| log.borrow_mut().push( algorithm.to_string()));
+    assert!( policy.check( Operation::Verify, &aa::Algorithm::AAEd25519).is_ok());
+    assert_eq!( *reported.borrow(), vec![ "aa-ed25519".to_string()]);
+
+    // Minimum key sizes.
+    let policy = Policy::default().minimum_key_bits( 384);
+    assert_eq!( policy.check( Operation::Decrypt, &se::Algorithm::SEAesGcm256), Err( "Key is smaller than policy allows."));
+    assert!( se::decrypt_content_with_policy( &policy, &key.pkaj, serde_json::from_str( cipher).unwrap()).is_err());
+
+    // Key sizes and authentication come from the typed algorithms.
+    assert_eq!( AlgorithmStrength::key_bits( &hpke::Algorithm::HPKEX25519AesGcm128), 128);
+    assert_eq!( AlgorithmStrength::key_bits( &se::Algorithm::SEAesGcm256), 256);
+    assert!( AlgorithmStrength::is_aead( &se::Algorithm::SEAesGcm256));
+    assert!( !AlgorithmStrength::is_aead( &aa::Algorithm::AAEd25519));
+    let policy = Policy::default().require_aead();
+    assert!( se::decrypt_content_with_policy( &policy, &key.pkaj, serde_json::from_str( cipher).unwrap()).is_ok());
+    assert!( policy.check( Operation::Verify, &aa::Algorithm::AAEd25519).is_ok());
+    let policy = Policy::default().allow( Operation::Decrypt, "aa-ed25519").require_aead();
+    assert_eq!( policy.check( Operation::Decrypt, &aa::Algorithm::AAEd25519), Err( "Algorithm is not authenticated encryption."));
+
+    // Keys of forbidden algorithms are rejected before they are decoded.
+    let policy = Policy::new().allow( Operation::Verify, "aa-ed25519");
+    assert_eq!( policy.load_key::<se::Key>( b"{\"key\":\"invalid\",\"algorithm\":\"se-aesgcm256\"}").err(), Some( "Key algorithm is not allowed by policy."));
+    assert!( policy.load_key::<ae::PublicKey>( b"{\"public_key\":\"3p7bfXt9wbTTW2HC7OQ1Nz-DQ8hbeGdNrfx-FG-IK08=\",\"algorithm\":\"ae-x25519\"}").is_err());
+    assert!( Policy::default().load_key::<ae::PublicKey>( b"{\"public_key\":\"3p7bfXt9wbTTW2HC7OQ1Nz-DQ8hbeGdNrfx-FG-IK08=\",\"algorithm\":\"ae-x25519\"}").is_ok());
+    assert_eq!( Policy::default().load_key::<ae::PublicKey>( b"{\"public_key\":\"3p7bfXt9wbTTW2HC7OQ1Nz-DQ8hbeGdNrfx-FG-IK08=\",\"algorithm\":\"ae-rsa\"}").err(), Some( "Unknown key algorithm."));
+    let policy = Policy::new().allow( Operation::Decrypt, "ae-x25519");
+    assert!( policy.load_key::<ae::PublicKey>( b"{\"public_key\":\"3p7bfXt9wbTTW2HC7OQ1Nz-DQ8hbeGdNrfx-FG-IK08=\",\"algorithm\":\"ae-x25519\"}").is_ok());
+    assert!( policy.load_key::<ae::PublicKey>( b"{\"public_key\":\"3p7bfXt9wbTTW2HC7OQ1Nz-DQ8hbeGdNrfx-FG-IK08=\"}").is_err());
+}
@@ -0,0 +1,143 @@
+extern crate pkauth;
+extern crate ring;
+extern crate serde_json;
+
+use pkauth::{ToIdentifier, ToPublicKey};
+use pkauth::asym::auth as aa;
+use pkauth::certificate;
+use pkauth::certificate::{Certificate, SubjectKey};
+use pkauth::internal;
+use pkauth::metadata::{FixedClock, Usage};
+use pkauth::revocation;
+use pkauth::revocation::{Reason, RevocationList, RevocationSet, Revoked};
+use ring::rand::{SystemRandom, SecureRandom};
+
+#[test]
+fn revocation_random_test() {
+    fn run() {
+        let rng = SystemRandom::new();
+        let issuer = aa::gen( &rng, &aa::Algorithm::AAEd25519).unwrap();
+        let laptop = aa::gen( &rng, &aa::Algorithm::AAEd25519).unwrap();
+        let phone = aa::gen( &rng, &aa::Algorithm::AAEd25519).unwrap();
+        let issuer_public = ToPublicKey::to_public_key( &issuer);
+        let laptop_public = ToPublicKey::to_public_key( &laptop);
+        let phone_public = ToPublicKey::to_public_key( &phone);
+
+        let mut content = [0u8; 64].to_vec();
+        rng.fill( &mut content).unwrap();
+
+        // Pre-generated revocation certificate.
+        let pregenerated = revocation::revoke( &phone, Reason::Unspecified, 300).unwrap();
+
+        let mut set = RevocationSet::new();
+        assert_eq!( aa::verify_content_with_revocations( &set, &laptop_public, aa::sign_content( &laptop, content.clone()).unwrap()).unwrap(), content);
+
+        // The laptop is stolen, and the issuer revokes it.
+        let laptop_identifier = ToIdentifier::to_identifier( &laptop_public);
+        let issuer_identifier = ToIdentifier::to_identifier( &issuer_public);
+        let mut list = RevocationList::new( &issuer_public, 1);
+        list.revoked.push( Revoked{ identifier : laptop_identifier.clone(), reason : Reason::KeyCompromise, time : 200});
+        set.add_list( &issuer_public, revocation::sign_list( &issuer, &list).unwrap()).unwrap();
+        assert!( set.is_revoked_by( &issuer_identifier, &laptop_identifier));
+        assert_eq!( set.get_issued( &issuer_identifier, &laptop_identifier).map(|r| (r.reason, r.time)), Some( (Reason::KeyCompromise, 200)));
+
+        // The issuer's list only revokes the certificates it signed.
+        assert!( !set.is_revoked( &laptop_identifier));
+        assert!( !set.is_revoked_by( &ToIdentifier::to_identifier( &phone_public), &laptop_identifier));
+        assert_eq!( aa::verify_content_with_revocations( &set, &laptop_public, aa::sign_content( &laptop, content.clone()).unwrap()).unwrap(), content);
+
+        // Lists cannot be replayed, and are only accepted from their issuer.
+        assert!( set.add_list( &issuer_public, revocation::sign_list( &issuer, &list).unwrap()).is_err());
+        assert!( set.add_list( &phone_public, revocation::sign_list( &issuer, &RevocationList::new( &issuer_public, 2)).unwrap()).is_err());
+        assert!( revocation::sign_list( &phone, &RevocationList::new( &issuer_public, 2)).is_err());
+
+        // The phone is lost, and its holder publishes the revocation certificate.
+        set.add_certificate( pregenerated.clone()).unwrap();
+        assert!( set.is_revoked( &ToIdentifier::to_identifier( &phone_public)));
+        assert!( set.is_revoked_by( &issuer_identifier, &ToIdentifier::to_identifier( &phone_public)));
+        assert!( aa::verify_content_with_revocations( &set, &phone_public, aa::sign_content( &phone, content.clone()).unwrap()).is_err());
+        assert_eq!( revocation::verify_revocation( pregenerated).unwrap().reason, Reason::Unspecified);
+
+        // Certificate chains consult the revocation list of each certificate's issuer.
+        let clock = FixedClock( 500);
+        let signed = certificate::issue( &issuer, &Certificate::new( SubjectKey::Auth( ToPublicKey::to_public_key( &laptop)), &issuer_public, 100, 1000, vec![ Usage::Sign])).unwrap();
+        assert!( certificate::validate_chain( &clock, &[ ToPublicKey::to_public_key( &issuer)], vec![ signed.clone()], Usage::Sign).is_ok());
+        assert_eq!( certificate::validate_chain_with_revocations( &clock, &set, &[ ToPublicKey::to_public_key( &issuer)], vec![ signed.clone()], Usage::Sign).err(), Some( "Key has been revoked."));
+
+        let other = aa::gen( &rng, &aa::Algorithm::AAEd25519).unwrap();
+        let other_public = ToPublicKey::to_public_key( &other);
+        let other_signed = certificate::issue( &other, &Certificate::new( SubjectKey::Auth( laptop_public), &other_public, 100, 1000, vec![ Usage::Sign])).unwrap();
+        assert!( certificate::validate_chain_with_revocations( &clock, &set, &[ ToPublicKey::to_public_key( &other)], vec![ other_signed], Usage::Sign).is_ok());
+
+        // Issuers cannot revoke the keys of other issuers' certificates.
+        let mut forged = RevocationList::new( &other_public, 1);
+        forged.revoked.push( Revoked{ identifier : issuer_identifier.clone(), reason : Reason::KeyCompromise, time : 200});
+        set.add_list( &other_public, revocation::sign_list( &other, &forged).unwrap()).unwrap();
+        assert!( !set.is_revoked( &issuer_identifier));
+        assert_eq!( aa::verify_content_with_revocations( &set, &issuer_public, aa::sign_content( &issuer, content.clone()).unwrap()).unwrap(), content);
+
+        let mut revoked_root = RevocationSet::new();
+        revoked_root.add_certificate( revocation::revoke( &issuer, Reason::Retired, 400).unwrap()).unwrap();
+        assert_eq!( certificate::validate_chain_with_revocations( &clock, &revoked_root, &[ ToPublicKey::to_public_key( &issuer)], vec![ signed], Usage::Sign).err(), Some( "Key has been revoked."));
+    }
+
+    for _ in 1 .. 100 {
+        run()
+    }
+}
+
+#[test]
+fn revocation_manual_tests() {
+    let issuer = aa::gen_from_seed( &aa::Algorithm::AAEd25519, &[3u8; 32]).unwrap();
+    let laptop = aa::gen_from_seed( &aa::Algorithm::AAEd25519, &[4u8; 32]).unwrap();
+    let issuer_public = ToPublicKey::to_public_key( &issuer);
+    let laptop_identifier = ToIdentifier::to_identifier( &ToPublicKey::to_public_key( &laptop));
+
+    let mut list = RevocationList::new( &issuer_public, 7);
+    list.revoked.push( Revoked{ identifier : laptop_identifier.clone(), reason : Reason::Superseded, time : 1700000000});
+    let json = serde_json::to_string( &list).unwrap();
+    assert_eq!( json, format!( "{{\"issuer\":\"{}\",\"sequence\":7,\"revoked\":[{{\"identifier\":\"{}\",\"reason\":\"superseded\",\"time\":1700000000}}]}}", ToIdentifier::to_identifier( &issuer_public), laptop_identifier));
+    let decoded : RevocationList = internal::from_str_strict( &json).unwrap();
+    assert_eq!( serde_json::to_string( &decoded).unwrap(), json);
+
+    // Older lists are rejected, and the newest list replaces the issuer's earlier ones.
+    let issuer_identifier = ToIdentifier::to_identifier( &issuer_public);
+    let mut set = RevocationSet::new();
+    set.add_list( &issuer_public, revocation::sign_list( &issuer, &list).unwrap()).unwrap();
+    assert!( set.check_issued( &issuer_identifier, &laptop_identifier).is_err());
+    assert!( set.check( &laptop_identifier).is_ok());
+    assert!( set.add_list( &issuer_public, revocation::sign_list( &issuer, &RevocationList::new( &issuer_public, 6)).unwrap()).is_err());
+    set.add_list( &issuer_public, revocation::sign_list( &issuer, &RevocationList::new( &issuer_public, 8)).unwrap()).unwrap();
+    assert!( set.check_issued( &issuer_identifier, &laptop_identifier).is_ok());
+    assert!( set.add_list( &issuer_public, revocation::sign_list( &issuer, &list).unwrap()).is_err());
+
+    // The earliest revocation of a key is kept.
+    set.add_certificate( revocation::revoke( &laptop, Reason::KeyCompromise, 1600000000).unwrap()).unwrap();
+    set.add_certificate( revocation::revoke( &laptop, Reason::Retired, 1750000000).unwrap()).unwrap();
+    assert_eq!( set.get( &laptop_identifier).map(|r| (r.reason, r.time)), Some( (Reason::KeyCompromise, 1600000000)));
+    let mut list = RevocationList::new( &issuer_public, 9);
+    list.revoked.push( Revoked{ identifier : laptop_identifier.clone(), reason : Reason::Superseded, time : 1500000000});
+    set.add_list( &issuer_public, revocation::sign_list( &issuer, &list).unwrap()).unwrap();
+    assert_eq!( set.get_issued( &issuer_identifier, &laptop_identifier).map(|r| (r.reason, r.time)), Some( (Reason::Superseded, 1500000000)));
+    assert_eq!( set.get( &laptop_identifier).map(|r| (r.reason, r.time)), Some( (Reason::KeyCompromise, 1600000000)));
+
+    // Revocation certificates must be signed by the key they revoke.
+    let certificate = revocation::revoke( &laptop, Reason::KeyCompromise, 1600000000).unwrap();
+    let forged = certificate.to_string().replace( "pka1.aa-ed25519.", "");
+    let forged = forged.split( '.').next().unwrap();
+    let other = revocation::revoke( &issuer, Reason::KeyCompromise, 1600000000).unwrap().to_string();
+    let other_signature = other.rsplit( '.').next().unwrap();
+    let forged : aa::PKASigned = format!( "pka1.aa-ed25519.{}.{}", forged, other_signature).parse().unwrap();
+    assert!( revocation::verify_revocation( forged).is_err());
+
+    // Invalid lists.
+    let invalid = [
+        json.replace( "superseded", "stolen"),
+        json.replace( "\"sequence\":7", "\"sequence\":-7"),
+        json.replace( "]}", &format!( ",{{\"identifier\":\"{}\",\"reason\":\"retired\",\"time\":1}}]}}", laptop_identifier)),
+        json.replace( ",\"time\":1700000000", ""),
+    ];
+    for json in invalid.iter() {
+        assert!( serde_json::from_str::<RevocationList>( json).is_err(), "{}", json);
+    }
+}
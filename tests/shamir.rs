@@ -0,0 +1,78 @@
+extern crate pkauth;
+extern crate ring;
+extern crate serde_json;
+
+use pkauth::{PKAJ, ToIdentifier};
+use pkauth::asym::auth as aa;
+use pkauth::asym::enc as ae;
+use pkauth::shamir::Share;
+use pkauth::sym::enc as se;
+use ring::rand::SystemRandom;
+
+// Round trip shares through JSON.
+fn to_json( shares : Vec<Share>) -> Vec<Share> {
+    shares.iter().map(|s| serde_json::from_str( &serde_json::to_string( s).unwrap()).unwrap()).collect()
+}
+
+#[test]
+fn shamir_random_test() {
+    fn run() {
+        let rng = SystemRandom::new();
+
+        let key = aa::gen( &rng, &aa::Algorithm::AAEd25519).unwrap();
+        let mut shares = to_json( aa::split_shares( &rng, &key, 3, 5).unwrap());
+        assert!( aa::combine_shares( &shares[3 ..]).is_err());
+        shares.reverse();
+        let restored = aa::combine_shares( &shares[1 .. 4]).unwrap();
+        assert_eq!( ToIdentifier::to_identifier( &key), ToIdentifier::to_identifier( &restored));
+
+        let key = ae::gen( &rng, &ae::Algorithm::AEX25519).unwrap();
+        let shares = to_json( ae::split_shares( &rng, &key, 2, 2).unwrap());
+        let restored = ae::combine_shares( &shares).unwrap();
+        assert_eq!( serde_json::to_string( &PKAJ{ pkaj : &key}).unwrap(), serde_json::to_string( &PKAJ{ pkaj : &restored}).unwrap());
+
+        let key = se::gen( &rng, &se::Algorithm::SEAesGcm256).unwrap();
+        let shares = to_json( se::split_shares( &rng, &key, 1, 3).unwrap());
+        let restored = se::combine_shares( &shares[2 ..]).unwrap();
+        assert_eq!( serde_json::to_string( &PKAJ{ pkaj : &key}).unwrap(), serde_json::to_string( &PKAJ{ pkaj : &restored}).unwrap());
+
+        assert!( se::split_shares( &rng, &key, 4, 3).is_err());
+    }
+
+    for _ in 1 .. 100 {
+        run()
+    }
+}
+
+fn share( index : u8, share : &str) -> Share {
+    let s = format!( "{{\"identifier\":\"8jDcLQ31ZkjbUzCwghNpu4RwHKrR3Zr6k\",\"threshold\":3,\"index\":{},\"share\":\"{}\",\"algorithm\":\"se-aesgcm256\"}}", index, share);
+    serde_json::from_str( &s).unwrap()
+}
+
+#[test]
+fn shamir_manual_tests() {
+    let s1 = share( 1, "m5ubk5OTk5ubm5uDg4ODm5ubm5OTk5Obm5ubY2NjY1s=");
+    let s2 = share( 2, "cnh-TEpARkRCSE48OjA2FBIYHiwqICYkIigu8ff9-5k=");
+    let s3 = share( 3, "FhwaICYsKiAmLCpARkxKcHZ8ekBGTEpARkxKbWthZz0=");
+    let s4 = share( 4, "8c3ZNSEdCUVRbXmOmqayvqqWgm56RlIeCjYiV0N_a-c=");
+
+    let key = se::combine_shares( &[s4, s1, s3]).unwrap();
+    assert_eq!( serde_json::to_string( &PKAJ{ pkaj : &key}).unwrap(), "{\"key\":\"__________________________________________8=\",\"algorithm\":\"se-aesgcm256\"}");
+
+    // Duplicate indices.
+    let s1 = share( 1, "m5ubk5OTk5ubm5uDg4ODm5ubm5OTk5Obm5ubY2NjY1s=");
+    let s1_dup = share( 1, "m5ubk5OTk5ubm5uDg4ODm5ubm5OTk5Obm5ubY2NjY1s=");
+    let s3 = share( 3, "FhwaICYsKiAmLCpARkxKcHZ8ekBGTEpARkxKbWthZz0=");
+    assert!( se::combine_shares( &[s1, s1_dup, s3]).is_err());
+
+    // Corrupted share fails the identifier check.
+    let s1 = share( 1, "m5ubk5OTk5ubm5uDg4ODm5ubm5OTk5Obm5ubY2NjY1s=");
+    let s2_bad = share( 2, "cnh-TEpARkRCSE48OjA2FBIYHiwqICYkIigu8ff9-5o=");
+    let s3 = share( 3, "FhwaICYsKiAmLCpARkxKcHZ8ekBGTEpARkxKbWthZz0=");
+    assert!( se::combine_shares( &[s1, s2_bad, s3]).is_err());
+
+    // Wrong key type.
+    let s1 = share( 1, "m5ubk5OTk5ubm5uDg4ODm5ubm5OTk5Obm5ubY2NjY1s=");
+    let s3 = share( 3, "FhwaICYsKiAmLCpARkxKcHZ8ekBGTEpARkxKbWthZz0=");
+    assert!( aa::combine_shares( &[s1, s2, s3]).is_err());
+}
@@ -0,0 +1,67 @@
+extern crate pkauth;
+extern crate ring;
+extern crate serde_json;
+
+use pkauth::{PKAJ, ToPublicKey};
+use pkauth::asym::enc as ae;
+use pkauth::asym::enc::sodium;
+use ring::rand::{SystemRandom, SecureRandom};
+
+#[test]
+fn sodium_random_test() {
+    fn run() {
+        let rng = SystemRandom::new();
+        let alice = ae::gen( &rng, &ae::Algorithm::AEX25519).unwrap();
+        let bob = ae::gen( &rng, &ae::Algorithm::AEX25519).unwrap();
+        let alice_pk = ToPublicKey::to_public_key( &alice);
+        let bob_pk = ToPublicKey::to_public_key( &bob);
+
+        let mut content = [0u8; 256].to_vec();
+        rng.fill( &mut content).unwrap();
+
+        let nonce = sodium::gen_nonce( &rng).unwrap();
+        let boxed = sodium::box_encrypt( &alice, &bob_pk, &nonce, &content).unwrap();
+        assert_eq!( boxed.len(), content.len() + 16);
+        assert_eq!( sodium::box_decrypt( &bob, &alice_pk, &nonce, &boxed).unwrap(), content);
+        assert!( sodium::box_decrypt( &bob, &bob_pk, &nonce, &boxed).is_err());
+
+        let sealed = sodium::seal( &rng, &bob_pk, &content).unwrap();
+        assert_eq!( sealed.len(), content.len() + 48);
+        assert_eq!( sodium::seal_open( &bob, &sealed).unwrap(), content);
+        assert!( sodium::seal_open( &alice, &sealed).is_err());
+    }
+
+    for _ in 1 .. 100 {
+        run()
+    }
+}
+
+fn hex( s : &str) -> Vec<u8> {
+    (0 .. s.len() / 2).map(|i| u8::from_str_radix( &s[2 * i .. 2 * i + 2], 16).unwrap()).collect()
+}
+
+#[test]
+fn sodium_manual_tests() {
+    // NaCl's crypto_box test vector (tests/box.c), whose keys are those of RFC7748, Section 6.1.
+    let alice : PKAJ<ae::PrivateKey> = serde_json::from_str( "{\"private_key\":\"dwdtCnMYpX08FsFyUbJmRd9ML4frwJkqsXf7pR25LCo=\",\"algorithm\":\"ae-x25519\"}").unwrap();
+    let alice_pk : PKAJ<ae::PublicKey> = serde_json::from_str( "{\"public_key\":\"hSDwCYkwp1R0i33ctD73Wg2_Og0mOBr066SpjqqbTmo=\",\"algorithm\":\"ae-x25519\"}").unwrap();
+    let bob : PKAJ<ae::PrivateKey> = serde_json::from_str( "{\"private_key\":\"XasIfmJKikt54X-Lg4AO5m87sSkmGLb9HC-LJ_-I4Os=\",\"algorithm\":\"ae-x25519\"}").unwrap();
+    let bob_pk : PKAJ<ae::PublicKey> = serde_json::from_str( "{\"public_key\":\"3p7bfXt9wbTTW2HC7OQ1Nz-DQ8hbeGdNrfx-FG-IK08=\",\"algorithm\":\"ae-x25519\"}").unwrap();
+    let nonce = hex( "69696ee955b62b73cd62bda875fc73d68219e0036b7a0b37");
+    let message = hex( "be075fc53c81f2d5cf141316ebeb0c7b5228c52a4c62cbd44b66849b64244ffce5ecbaaf33bd751a1ac728d45e6c61296cdc3c01233561f41db66cce314adb310e3be8250c46f06dceea3a7fa1348057e2f6556ad6b1318a024a838f21af1fde048977eb48f59ffd4924ca1c60902e52f0a089bc76897040e082f937763848645e0705");
+    let boxed = hex( "f3ffc7703f9400e52a7dfb4b3d3305d98e993b9f48681273c29650ba32fc76ce48332ea7164d96a4476fb8c531a1186ac0dfc17c98dce87b4da7f011ec48c97271d2c20f9b928fe2270d6fb863d51738b48eeee314a7cc8ab932164548e526ae90224368517acfeabd6bb3732bc0e9da99832b61ca01b6de56244a9e88d5f9b37973f622a43d14a6599b1f654cb45a74e355a5");
+
+    assert_eq!( sodium::box_encrypt( &alice.pkaj, &bob_pk.pkaj, &nonce, &message).unwrap(), boxed);
+    assert_eq!( sodium::box_decrypt( &bob.pkaj, &alice_pk.pkaj, &nonce, &boxed).unwrap(), message);
+    assert!( sodium::box_decrypt( &bob.pkaj, &alice_pk.pkaj, &nonce[1..], &boxed).is_err());
+
+    // Sealed by libsodium.
+    let key : PKAJ<ae::PrivateKey> = serde_json::from_str( "{\"private_key\":\"FbNssAITNz-z-wOVj7DMABLsrKES_SSdPPCWHjEcqsk=\",\"algorithm\":\"ae-x25519\"}").unwrap();
+    let sealed = hex( "95eb5bf05ada25ee51f4158201c261a00bfb1955a9176c8c7f1a62f299a32e54f6ebccc8ab9d2ce1b1d3710ba37d8db17aeeec0b78fc3d32b39b79ed96f18948c5a574b8e3f8eccc2f132408c21646f3aedae4a67fde4f77153b5458b8a6bd712dd8365534c567ec");
+    assert_eq!( sodium::seal_open( &key.pkaj, &sealed).unwrap(), b"Lorem ipsum dolor sit amet, consectetur adipiscing elit.".to_vec());
+    assert!( sodium::seal_open( &key.pkaj, &sealed[1..]).is_err());
+
+    // Small order peers are rejected.
+    assert!( serde_json::from_str::<PKAJ<ae::PublicKey>>( "{\"public_key\":\"AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=\",\"algorithm\":\"ae-x25519\"}").is_err());
+    assert!( sodium::box_encrypt( &alice.pkaj, &ae::PublicKey::AEX25519( [0u8; 32]), &nonce, &message).is_err());
+}
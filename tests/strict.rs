@@ -0,0 +1,161 @@
+extern crate pkauth;
+extern crate ring;
+#[macro_use]
+extern crate serde_json;
+
+use pkauth::{PKAJ, ToPublicKey};
+use pkauth::asym::auth as aa;
+use pkauth::encoding::{DecodeArmor, EncodeArmor};
+use pkauth::encoding::armor;
+use pkauth::internal;
+use pkauth::policy::Policy;
+use pkauth::sym::enc as se;
+use ring::rand::{SystemRandom, SecureRandom};
+use serde_json::Value;
+use std::panic;
+
+#[test]
+fn strict_random_test() {
+    fn run() {
+        let rng = SystemRandom::new();
+        let key = aa::gen( &rng, &aa::Algorithm::AAEd25519).unwrap();
+        let public_key = ToPublicKey::to_public_key( &key);
+
+        let mut content = [0u8; 64].to_vec();
+        rng.fill( &mut content).unwrap();
+
+        let signed = serde_json::to_vec( &aa::sign_content( &key, content.clone()).unwrap()).unwrap();
+        let parsed : aa::PKASigned = internal::from_slice_strict( &signed).unwrap();
+        assert_eq!( aa::verify_content( &public_key, parsed).unwrap(), content);
+
+        let pk = serde_json::to_vec( &PKAJ{ pkaj : &public_key}).unwrap();
+        let parsed : PKAJ<aa::PublicKey> = internal::from_slice_strict( &pk).unwrap();
+        assert_eq!( aa::verify_content( &parsed.pkaj, serde_json::from_slice( &signed).unwrap()).unwrap(), content);
+
+        // Unknown fields.
+        let mut extra : Value = serde_json::from_slice( &signed).unwrap();
+        extra["identifier"] = json!( "GzD1i8BgqvT7tTd24K9CrHqWRqMSbfVsp");
+        let extra = serde_json::to_vec( &extra).unwrap();
+        assert!( serde_json::from_slice::<aa::PKASigned>( &extra).is_ok());
+        assert!( internal::from_slice_strict::<aa::PKASigned>( &extra).is_err());
+
+        // Trailing whitespace is part of a json text, other trailing data is not.
+        let mut trailing = signed.clone();
+        trailing.extend( b" \r\n");
+        assert!( internal::from_slice_strict::<aa::PKASigned>( &trailing).is_ok());
+        trailing.extend( b"{}");
+        assert!( internal::from_slice_strict::<aa::PKASigned>( &trailing).is_err());
+        let mut trailing = signed.clone();
+        trailing.push( b'x');
+        assert!( internal::from_slice_strict::<aa::PKASigned>( &trailing).is_err());
+    }
+
+    for _ in 1 .. 100 {
+        run()
+    }
+}
+
+#[test]
+fn strict_manual_tests() {
+    let key = "{\"key\":\"1quLbjzIufNwHh7Oc5ayXiosE3RoJBEDGH_cEcoQfDE=\",\"algorithm\":\"se-aesgcm256\"}";
+    let key : PKAJ<se::Key> = internal::from_str_strict( key).unwrap();
+    let cipher = "{\"ciphertext\":\"edzNkvuqE-BUGS2Y0RXnt9C4d52B7v8boQtdn1nkdSWesa_Y9RRW2c1acAAIv8YHeb0=\",\"algorithm\":\"se-aesgcm256\"}";
+    let cipher : se::PKASymEncrypted = internal::from_str_strict( cipher).unwrap();
+    assert_eq!( se::decrypt_content( &key.pkaj, cipher).unwrap(), b"this is rust plaintext");
+
+    // Legacy ciphertexts include an identifier.
+    let legacy = "{\"ciphertext\":\"edzNkvuqE-BUGS2Y0RXnt9C4d52B7v8boQtdn1nkdSWesa_Y9RRW2c1acAAIv8YHeb0=\",\"identifier\":\"GzD1i8BgqvT7tTd24K9CrHqWRqMSbfVsp\",\"algorithm\":\"se-aesgcm256\"}";
+    assert!( serde_json::from_str::<se::PKASymEncrypted>( legacy).is_ok());
+    assert!( internal::from_str_strict::<se::PKASymEncrypted>( legacy).is_err());
+
+    // Non-canonical base64url.
+    for key in [ "1quLbjzIufNwHh7Oc5ayXiosE3RoJBEDGH_cEcoQfDE", "1quLbjzIufNwHh7Oc5ayXiosE3RoJBEDGH_cEcoQfDF="].iter() {
+        let key = format!( "{{\"key\":\"{}\",\"algorithm\":\"se-aesgcm256\"}}", key);
+        assert!( internal::from_str_strict::<PKAJ<se::Key>>( &key).is_err());
+    }
+
+    // Duplicate fields are always rejected.
+    let duplicate = "{\"key\":\"1quLbjzIufNwHh7Oc5ayXiosE3RoJBEDGH_cEcoQfDE=\",\"algorithm\":\"se-aesgcm256\",\"algorithm\":\"se-aesgcm256\"}";
+    assert!( serde_json::from_str::<PKAJ<se::Key>>( duplicate).is_err());
+    assert!( internal::from_str_strict::<PKAJ<se::Key>>( duplicate).is_err());
+
+    // Unknown fields of any type are skipped when deserializing leniently.
+    let nested = "{\"key\":\"1quLbjzIufNwHh7Oc5ayXiosE3RoJBEDGH_cEcoQfDE=\",\"usage\":[\"encrypt\",{\"a\":[1,2]}],\"extra\":{\"b\":null},\"algorithm\":\"se-aesgcm256\"}";
+    assert!( serde_json::from_str::<PKAJ<se::Key>>( nested).is_ok());
+    assert!( internal::from_str_strict::<PKAJ<se::Key>>( nested).is_err());
+
+    // Strictness is scoped, even when deserialization panics.
+    assert!( internal::from_str_strict::<se::PKASymEncrypted>( "{}").is_err());
+    assert!( !internal::is_strict());
+    assert!( internal::strict( internal::is_strict));
+    assert!( serde_json::from_str::<se::PKASymEncrypted>( legacy).is_ok());
+    assert!( panic::catch_unwind(|| internal::strict(|| panic!( "deserialization panicked"))).is_err());
+    assert!( !internal::is_strict());
+}
+
+#[test]
+fn strict_entry_points_random_test() {
+    fn run() {
+        let rng = SystemRandom::new();
+        let key = aa::gen( &rng, &aa::Algorithm::AAEd25519).unwrap();
+        let public_key = ToPublicKey::to_public_key( &key);
+
+        let signed = aa::sign( &key, &json!({ "a": 1 })).unwrap();
+        let signed = serde_json::to_vec( &signed).unwrap();
+        let parsed : Value = aa::verify_bs_strict( &public_key, signed.clone()).unwrap();
+        assert_eq!( parsed, json!({ "a": 1 }));
+
+        // A strict verify rejects an unknown field in the signed object.
+        let mut extra : Value = serde_json::from_slice( &signed).unwrap();
+        extra["extra"] = json!( "field");
+        let extra = serde_json::to_vec( &extra).unwrap();
+        assert!( aa::verify_content_bs( &public_key, extra.clone()).is_ok());
+        assert!( aa::verify_content_bs_strict( &public_key, extra.clone()).is_err());
+        assert!( aa::verify_bs_strict::<Value>( &public_key, extra).is_err());
+
+        // Signed content is parsed strictly too.
+        let mut trailing = serde_json::to_vec( &json!({ "a": 1 })).unwrap();
+        trailing.push( b'x');
+        let signed = serde_json::to_vec( &aa::sign_content( &key, trailing).unwrap()).unwrap();
+        assert!( aa::verify_content_bs_strict( &public_key, signed.clone()).is_ok());
+        assert!( aa::verify_bs_strict::<Value>( &public_key, signed).is_err());
+
+        // Armor must stand alone.
+        let armored = EncodeArmor::encode_armor( &public_key);
+        assert!( armor::decode_armor_strict::<aa::PublicKey>( &armored).is_ok());
+        let surrounded = format!( "Hi,\n{}Thanks!\n", armored);
+        assert!( DecodeArmor::decode_armor( &surrounded).map(|k : aa::PublicKey| k == public_key).unwrap_or( false));
+        assert!( armor::decode_armor_strict::<aa::PublicKey>( &surrounded).is_err());
+        let unknown = armored.replacen( "\nType:", "\nComment: hi\nType:", 1);
+        assert!( DecodeArmor::decode_armor( &unknown).map(|k : aa::PublicKey| k == public_key).unwrap_or( false));
+        assert!( armor::decode_armor_strict::<aa::PublicKey>( &unknown).is_err());
+    }
+
+    for _ in 1 .. 100 {
+        run()
+    }
+}
+
+#[test]
+fn strict_entry_points_manual_tests() {
+    let key = "{\"key\":\"1quLbjzIufNwHh7Oc5ayXiosE3RoJBEDGH_cEcoQfDE=\",\"algorithm\":\"se-aesgcm256\"}";
+    let policy = Policy::default();
+    let key : se::Key = policy.load_key_strict( key.as_bytes()).unwrap();
+
+    let cipher = "{\"ciphertext\":\"edzNkvuqE-BUGS2Y0RXnt9C4d52B7v8boQtdn1nkdSWesa_Y9RRW2c1acAAIv8YHeb0=\",\"algorithm\":\"se-aesgcm256\"}";
+    assert_eq!( se::decrypt_content_bs_strict( &key, &cipher.as_bytes().to_vec()).unwrap(), b"this is rust plaintext");
+
+    let legacy = "{\"ciphertext\":\"edzNkvuqE-BUGS2Y0RXnt9C4d52B7v8boQtdn1nkdSWesa_Y9RRW2c1acAAIv8YHeb0=\",\"identifier\":\"GzD1i8BgqvT7tTd24K9CrHqWRqMSbfVsp\",\"algorithm\":\"se-aesgcm256\"}";
+    assert!( se::decrypt_content_bs( &key, &legacy.as_bytes().to_vec()).is_ok());
+    assert!( se::decrypt_content_bs_strict( &key, &legacy.as_bytes().to_vec()).is_err());
+
+    // The plaintext is not json.
+    assert!( se::decrypt_bs_strict::<Value>( &key, &cipher.as_bytes().to_vec()).is_err());
+
+    let extra = "{\"key\":\"1quLbjzIufNwHh7Oc5ayXiosE3RoJBEDGH_cEcoQfDE=\",\"extra\":1,\"algorithm\":\"se-aesgcm256\"}";
+    assert!( policy.load_key::<se::Key>( extra.as_bytes()).is_ok());
+    assert!( policy.load_key_strict::<se::Key>( extra.as_bytes()).is_err());
+    let trailing = "{\"key\":\"1quLbjzIufNwHh7Oc5ayXiosE3RoJBEDGH_cEcoQfDE=\",\"algorithm\":\"se-aesgcm256\"}x";
+    assert!( policy.load_key_strict::<se::Key>( trailing.as_bytes()).is_err());
+    assert!( !internal::is_strict());
+}
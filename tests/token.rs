@@ -0,0 +1,79 @@
+extern crate pkauth;
+extern crate ring;
+extern crate serde_json;
+
+use pkauth::{PKAJ, ToPublicKey};
+use pkauth::asym::auth as aa;
+use pkauth::sym::enc as se;
+use ring::rand::{SystemRandom, SecureRandom};
+
+#[test]
+fn token_random_test() {
+    fn run() {
+        let rng = SystemRandom::new();
+        let key = aa::gen( &rng, &aa::Algorithm::AAEd25519).unwrap();
+        let se_key = se::gen( &rng, &se::Algorithm::SEAesGcm256).unwrap();
+
+        let mut content = [0u8; 100].to_vec();
+        rng.fill( &mut content).unwrap();
+
+        let signed = aa::sign_content( &key, content.clone()).unwrap();
+        let token = signed.to_string();
+        assert!( token.starts_with( "pka1.aa-ed25519."));
+        assert!( token.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'.' || b == b'-' || b == b'_'));
+        let parsed : aa::PKASigned = token.parse().unwrap();
+        assert_eq!( parsed.to_string(), token);
+        assert_eq!( aa::verify_content( &ToPublicKey::to_public_key( &key), parsed).unwrap(), content);
+
+        let encrypted = se::encrypt_content( &rng, &se_key, content.clone()).unwrap();
+        let token = encrypted.to_string();
+        assert!( token.starts_with( "pka1.se-aesgcm256."));
+        let parsed : se::PKASymEncrypted = token.parse().unwrap();
+        assert_eq!( parsed.to_string(), token);
+        assert_eq!( se::decrypt_content( &se_key, parsed).unwrap(), content);
+    }
+
+    for _ in 1 .. 100 {
+        run()
+    }
+}
+
+#[test]
+fn token_manual_tests() {
+    // RFC8032, Section 7.1, Test 1.
+    let key = aa::gen_from_seed( &aa::Algorithm::AAEd25519, &hex( "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60")).unwrap();
+    let public_key = ToPublicKey::to_public_key( &key);
+    let token = "pka1.aa-ed25519..5VZDAMNgrHKQhuLMgG6CioSHfx645dl02HPgZSJJAVVfuIIVkKM7rMYeOXAc-bRr0lv18FlbviRlUUFDjnoQCw";
+    assert_eq!( aa::sign_content( &key, vec![]).unwrap().to_string(), token);
+    assert_eq!( aa::verify_content( &public_key, token.parse().unwrap()).unwrap(), b"");
+
+    // Strict parsing.
+    let invalid = [
+        "pka2.aa-ed25519..5VZDAMNgrHKQhuLMgG6CioSHfx645dl02HPgZSJJAVVfuIIVkKM7rMYeOXAc-bRr0lv18FlbviRlUUFDjnoQCw",
+        "pka1.aa-ed448..5VZDAMNgrHKQhuLMgG6CioSHfx645dl02HPgZSJJAVVfuIIVkKM7rMYeOXAc-bRr0lv18FlbviRlUUFDjnoQCw",
+        "pka1.aa-ed25519.5VZDAMNgrHKQhuLMgG6CioSHfx645dl02HPgZSJJAVVfuIIVkKM7rMYeOXAc-bRr0lv18FlbviRlUUFDjnoQCw",
+        "pka1.aa-ed25519...5VZDAMNgrHKQhuLMgG6CioSHfx645dl02HPgZSJJAVVfuIIVkKM7rMYeOXAc-bRr0lv18FlbviRlUUFDjnoQCw",
+        "pka1.aa-ed25519..5VZDAMNgrHKQhuLMgG6CioSHfx645dl02HPgZSJJAVVfuIIVkKM7rMYeOXAc-bRr0lv18FlbviRlUUFDjnoQCw==",
+        "pka1.aa-ed25519..5VZDAMNgrHKQhuLMgG6CioSHfx645dl02HPgZSJJAVVfuIIVkKM7rMYeOXAc+bRr0lv18FlbviRlUUFDjnoQCw",
+        "pka1.aa-ed25519..5VZDAMNgrHKQhuLMgG6CioSHfx645dl02HPgZSJJAVVfuIIVkKM7rMYeOXAc-bRr0lv18FlbviRlUUFDjnoQCx",
+        "pka1.aa-ed25519.AA.5VZDAMNgrHKQhuLMgG6CioSHfx645dl02HPgZSJJAVVfuIIVkKM7rMYeOXAc-bRr0lv18FlbviRlUUFDjnoQ",
+        " pka1.aa-ed25519..5VZDAMNgrHKQhuLMgG6CioSHfx645dl02HPgZSJJAVVfuIIVkKM7rMYeOXAc-bRr0lv18FlbviRlUUFDjnoQCw",
+        "",
+    ];
+    for token in invalid.iter() {
+        assert!( token.parse::<aa::PKASigned>().is_err(), "{}", token);
+    }
+
+    // The ciphertext of a JSON test vector as a token.
+    let key : PKAJ<se::Key> = serde_json::from_str( "{\"key\":\"1quLbjzIufNwHh7Oc5ayXiosE3RoJBEDGH_cEcoQfDE=\",\"algorithm\":\"se-aesgcm256\"}").unwrap();
+    let token = "pka1.se-aesgcm256.edzNkvuqE-BUGS2Y0RXnt9C4d52B7v8boQtdn1nkdSWesa_Y9RRW2c1acAAIv8YHeb0";
+    let encrypted : se::PKASymEncrypted = token.parse().unwrap();
+    assert_eq!( encrypted.to_string(), token);
+    assert_eq!( se::decrypt_content( &key.pkaj, encrypted).unwrap(), b"this is rust plaintext");
+    assert!( "pka1.se-aesgcm256.edzNkvuqE-BUGS2Y0RXnt9C4d52B7v8boQtdn1nkdSWesa_Y9RRW2c1acAAIv8YHeb0=".parse::<se::PKASymEncrypted>().is_err());
+    assert!( "pka1.aa-ed25519.edzNkvuqE-BUGS2Y0RXnt9C4d52B7v8boQtdn1nkdSWesa_Y9RRW2c1acAAIv8YHeb0".parse::<se::PKASymEncrypted>().is_err());
+}
+
+fn hex( s : &str) -> Vec<u8> {
+    (0 .. s.len() / 2).map(|i| u8::from_str_radix( &s[2 * i .. 2 * i + 2], 16).unwrap()).collect()
+}
@@ -0,0 +1,95 @@
+extern crate pkauth;
+extern crate ring;
+extern crate serde_json;
+
+use pkauth::{DecodeKey, KeyError, PKAJ, ToPublicKey, Validate};
+use pkauth::asym::auth as aa;
+use pkauth::asym::enc as ae;
+use pkauth::internal::{DecodePSF, EncodePSF};
+use pkauth::sym::enc as se;
+use ring::rand::SystemRandom;
+
+#[test]
+fn validate_random_test() {
+    fn run() {
+        let rng = SystemRandom::new();
+        let key = aa::gen( &rng, &aa::Algorithm::AAEd25519).unwrap();
+        let other = aa::gen( &rng, &aa::Algorithm::AAEd25519).unwrap();
+        let ae_key = ae::gen( &rng, &ae::Algorithm::AEX25519).unwrap();
+        let se_key = se::gen( &rng, &se::Algorithm::SEAesGcm256).unwrap();
+
+        assert_eq!( key.validate(), Ok(()));
+        assert_eq!( ToPublicKey::to_public_key( &key).validate(), Ok(()));
+        assert_eq!( ae_key.validate(), Ok(()));
+        assert_eq!( ToPublicKey::to_public_key( &ae_key).validate(), Ok(()));
+        assert_eq!( se_key.validate(), Ok(()));
+
+        // A private key whose embedded public key belongs to another key.
+        let mut psf = EncodePSF::encode_psf( &key);
+        psf.truncate( 32);
+        psf.extend( EncodePSF::encode_psf( &ToPublicKey::to_public_key( &other)));
+        assert_eq!( <aa::PrivateKey as DecodePSF>::decode_psf( &aa::Algorithm::AAEd25519, &psf).err(), Some( KeyError::PublicKeyMismatch.as_str()));
+        assert_eq!( <aa::PrivateKey as DecodeKey>::decode_key( &aa::Algorithm::AAEd25519, &psf).err(), Some( KeyError::PublicKeyMismatch));
+
+        let json = serde_json::to_string( &PKAJ{ pkaj : &key}).unwrap();
+        let decoded : PKAJ<aa::PrivateKey> = serde_json::from_str( &json).unwrap();
+        assert_eq!( decoded.pkaj.validate(), Ok(()));
+    }
+
+    for _ in 1 .. 100 {
+        run()
+    }
+}
+
+#[test]
+fn validate_manual_tests() {
+    // X25519 points of small order, on the curve and its twist, including non-canonical encodings.
+    let small_order = [
+        "0000000000000000000000000000000000000000000000000000000000000000",
+        "0100000000000000000000000000000000000000000000000000000000000000",
+        "e0eb7a7c3b41b8ae1656e3faf19fc46ada098deb9c32b1fd866205165f49b800",
+        "5f9c95bca3508c24b1d0b1559c83ef5b04445cc4581c8e86d8224eddd09f1157",
+        "ecffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f",
+        "edffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f",
+        "eeffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f",
+    ];
+    for point in small_order.iter() {
+        assert_eq!( <ae::PublicKey as DecodePSF>::decode_psf( &ae::Algorithm::AEX25519, &hex( point)).err(), Some( "Public key has small order."));
+        let mut key = [0u8; 32];
+        key.copy_from_slice( &hex( point));
+        assert_eq!( ae::PublicKey::AEX25519( key).validate(), Err( KeyError::SmallOrder));
+    }
+
+    // Decoding a key directly returns the reason it is invalid.
+    assert_eq!( <ae::PublicKey as DecodeKey>::decode_key( &ae::Algorithm::AEX25519, &[0u8; 32].to_vec()).err(), Some( KeyError::SmallOrder));
+    assert_eq!( <ae::PublicKey as DecodeKey>::decode_key( &ae::Algorithm::AEX25519, &[0u8; 31].to_vec()).err(), Some( KeyError::WrongLength));
+    assert_eq!( <se::Key as DecodeKey>::decode_key( &se::Algorithm::SEAesGcm256, &[0u8; 33].to_vec()).err(), Some( KeyError::WrongLength));
+
+    // RFC7748, Section 6.1.
+    let bob = hex( "de9edb7d7b7dc1b4d35b61c2ece435373f8343c85b78674dadfc7e146f882b4f");
+    assert!( <ae::PublicKey as DecodePSF>::decode_psf( &ae::Algorithm::AEX25519, &bob).is_ok());
+
+    // Ed25519 identity, a point of order 2, and a y coordinate that is not on the curve.
+    let mut identity = [0u8; 32];
+    identity[0] = 1;
+    assert_eq!( aa::PublicKey::AAEd25519( identity).validate(), Err( KeyError::SmallOrder));
+    let order_two = hex( "ecffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f");
+    assert_eq!( <aa::PublicKey as DecodePSF>::decode_psf( &aa::Algorithm::AAEd25519, &order_two).err(), Some( KeyError::SmallOrder.as_str()));
+    let mut invalid = [0u8; 32];
+    invalid[0] = 2;
+    assert_eq!( aa::PublicKey::AAEd25519( invalid).validate(), Err( KeyError::InvalidPoint));
+    assert!( serde_json::from_str::<PKAJ<aa::PublicKey>>( "{\"public_key\":\"AgAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=\",\"algorithm\":\"aa-ed25519\"}").is_err());
+
+    // RFC8032, Section 7.1, Test 1, with the public key of Test 2.
+    let mut psf = hex( "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60");
+    psf.extend( hex( "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a"));
+    assert!( <aa::PrivateKey as DecodePSF>::decode_psf( &aa::Algorithm::AAEd25519, &psf).is_ok());
+    psf.truncate( 32);
+    psf.extend( hex( "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c"));
+    assert_eq!( <aa::PrivateKey as DecodePSF>::decode_psf( &aa::Algorithm::AAEd25519, &psf).err(), Some( "Public key does not match private key."));
+    assert_eq!( <aa::PrivateKey as DecodeKey>::decode_key( &aa::Algorithm::AAEd25519, &psf).err(), Some( KeyError::PublicKeyMismatch));
+}
+
+fn hex( s : &str) -> Vec<u8> {
+    (0 .. s.len() / 2).map(|i| u8::from_str_radix( &s[2 * i .. 2 * i + 2], 16).unwrap()).collect()
+}